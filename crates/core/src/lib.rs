@@ -1,13 +1,14 @@
-use std::{fmt::Display, ops::Deref};
+use std::{collections::BTreeMap, fmt::Display, ops::Deref};
 
 use async_trait::async_trait;
 use bon::Builder;
 use facet::Facet;
 use facet_json::{DeserializeError, JsonError};
 use facet_pretty::FacetPretty;
-use facet_value::Value as FacetValue;
+use facet_value::{VObject, Value as FacetValue};
 use issuecraft_ql::{
-    CloseReason, CommentId, EntityType, IqlError, IqlQuery, IssueId, IssueKind, ProjectId, UserId,
+    COMMENT_COUNT_COLUMN, CloseReason, CommentId, EntityType, IqlError, IqlQuery, IssueId,
+    IssueKind, LinkId, ProjectId, Schema, SessionOptions, UserId,
 };
 
 #[derive(thiserror::Error, Debug)]
@@ -22,6 +23,39 @@ pub enum ClientError {
     DeserializationError(#[from] DeserializeError<JsonError>),
     #[error("Client specific: {0}")]
     ClientSpecific(String),
+    #[error("Not authorized, log in first")]
+    Unauthorized,
+    #[error("Backend error: {0}")]
+    Backend(BackendError),
+}
+
+/// Errors raised by a backend surface to clients as follows:
+///
+/// - [`BackendError::IqlError`] becomes [`ClientError::IqlError`]
+/// - [`BackendError::NotImplemented`] and [`BackendError::NotSupported`] keep their meaning
+/// - every other variant is wrapped in [`ClientError::Backend`]
+///
+/// Parse errors reach both types through [`IqlError::MalformedIql`].
+///
+/// ```
+/// use issuecraft_core::{BackendError, ClientError};
+///
+/// let error = ClientError::from(BackendError::PermissionDenied("alice".to_string()));
+/// assert!(matches!(
+///     error,
+///     ClientError::Backend(BackendError::PermissionDenied(ref user)) if user == "alice"
+/// ));
+/// assert_eq!(error.to_string(), "Backend error: Permission denied: alice");
+/// ```
+impl From<BackendError> for ClientError {
+    fn from(error: BackendError) -> Self {
+        match error {
+            BackendError::IqlError(error) => ClientError::IqlError(error),
+            BackendError::NotImplemented => ClientError::NotImplemented,
+            BackendError::NotSupported => ClientError::NotSupported,
+            error => ClientError::Backend(error),
+        }
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -32,18 +66,37 @@ pub enum BackendError {
     PermissionDenied(String),
     #[error("A project with the name '{0}' already exists")]
     ProjectAlreadyExists(String),
+    #[error("Another project is already named '{0}'")]
+    DuplicateDisplayName(String),
     #[error("User with id '{id}' not found")]
     UserNotFound { id: String },
     #[error("No item of type '{kind}' with the id '{id}' exists")]
     ItemNotFound { kind: String, id: String },
+    #[error("An item of type '{kind}' with the id '{id}' already exists")]
+    ItemAlreadyExists { kind: String, id: String },
     #[error("The issue withe the name '{0}' was already closed. Reason '{1}'")]
     IssueAlreadyClosed(String, CloseReason),
     #[error("Field not found: {0}")]
     FieldNotFound(String),
+    #[error("An issue of kind '{kind}' requires the field '{field}'")]
+    MissingRequiredField { kind: String, field: IssueField },
     #[error("IQL impl {0}")]
     ImplementationSpecific(String),
     #[error("Could not parse id: {0}")]
     InvalidId(String),
+    #[error("Invalid {field}: {reason}")]
+    InvalidField { field: String, reason: String },
+    #[error(
+        "The database has schema version {found}, but version {expected} is required. \
+         Export it with a matching version of issuecraft and import it into a new database"
+    )]
+    IncompatibleSchemaVersion { found: String, expected: u64 },
+    #[error("The database is read-only")]
+    ReadOnly,
+    #[error("A transaction is already open")]
+    TransactionAlreadyOpen,
+    #[error("There is no open transaction")]
+    NoOpenTransaction,
     #[error("Not implemented")]
     NotImplemented,
     #[error("This action is not supported by the chosen backend")]
@@ -65,6 +118,12 @@ pub struct ProjectInfo {
     pub owner: UserId,
     #[facet(skip_serializing_if = Option::is_none)]
     pub name: Option<String>,
+    /// The user who last changed the project. Projects written before this was tracked have none.
+    #[facet(default)]
+    pub updated_by: Option<UserId>,
+    /// Archived projects are left out of selects unless they ask for them.
+    #[facet(default)]
+    pub archived: bool,
 }
 
 #[derive(Debug, Clone, Facet)]
@@ -85,6 +144,17 @@ pub enum Priority {
     Critical,
 }
 
+impl Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Priority::Low => write!(f, "LOW"),
+            Priority::Medium => write!(f, "MEDIUM"),
+            Priority::High => write!(f, "HIGH"),
+            Priority::Critical => write!(f, "CRITICAL"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Facet)]
 pub struct IssueInfo {
     pub author: UserId,
@@ -97,6 +167,18 @@ pub struct IssueInfo {
     #[facet(skip_serializing_if = Option::is_none)]
     pub priority: Option<Priority>,
     pub assignee: UserId,
+    #[facet(default)]
+    pub labels: Vec<String>,
+    #[facet(default)]
+    #[facet(skip_serializing_if = Option::is_none)]
+    #[facet(proxy = StoredOptionalTimestamp)]
+    pub updated_at: Option<time::UtcDateTime>,
+    /// The user who last changed the issue. Issues written before this was tracked have none.
+    #[facet(default)]
+    pub updated_by: Option<UserId>,
+    /// Archived issues are left out of selects unless they ask for them.
+    #[facet(default)]
+    pub archived: bool,
 }
 
 impl IssueInfo {
@@ -106,12 +188,260 @@ impl IssueInfo {
     }
 }
 
+/// An optional issue field that an [`IssueTemplates`] policy can make mandatory.
+#[derive(Debug, Clone, Copy, Facet, PartialEq)]
+#[repr(C)]
+pub enum IssueField {
+    Description,
+    Priority,
+    Assignee,
+    Labels,
+}
+
+impl Display for IssueField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IssueField::Description => write!(f, "description"),
+            IssueField::Priority => write!(f, "priority"),
+            IssueField::Assignee => write!(f, "assignee"),
+            IssueField::Labels => write!(f, "labels"),
+        }
+    }
+}
+
+/// The fields an issue has to be created with, per kind. Nothing beyond the title is
+/// required by default.
+///
+/// ```
+/// use issuecraft_core::{IssueField, IssueTemplates};
+/// use issuecraft_ql::IssueKind;
+///
+/// let templates = IssueTemplates::default()
+///     .require(IssueKind::Bug, IssueField::Description)
+///     .require(IssueKind::Epic, IssueField::Description);
+/// assert_eq!(
+///     templates.required_fields_for(&IssueKind::Bug),
+///     vec![IssueField::Description]
+/// );
+/// assert!(templates.required_fields_for(&IssueKind::Task).is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IssueTemplates {
+    required: Vec<(IssueKind, IssueField)>,
+}
+
+impl IssueTemplates {
+    #[must_use]
+    pub fn require(mut self, kind: IssueKind, field: IssueField) -> Self {
+        if !self.required.contains(&(kind.clone(), field)) {
+            self.required.push((kind, field));
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn required_fields_for(&self, kind: &IssueKind) -> Vec<IssueField> {
+        self.required
+            .iter()
+            .filter(|(required_kind, _)| required_kind == kind)
+            .map(|(_, field)| *field)
+            .collect()
+    }
+}
+
+/// A field that differs between two versions of an entity. `None` means the field was unset.
+#[derive(Debug, Clone, Facet, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Option<FacetValue>,
+    pub new: Option<FacetValue>,
+}
+
+/// A directed relationship between two issues, like `blocks` or `relates`.
+#[derive(Debug, Clone, Facet)]
+pub struct LinkInfo {
+    pub source: IssueId,
+    pub target: IssueId,
+    #[facet(rename = "type")]
+    pub link_type: String,
+}
+
 #[derive(Debug, Clone, Facet)]
 pub struct CommentInfo {
     pub issue: IssueId,
+    /// The position of the comment within its issue, as used in its id (`project#1/c3`).
+    #[facet(default)]
+    pub number: u64,
+    #[facet(proxy = StoredTimestamp)]
     pub created_at: time::UtcDateTime,
     pub content: String,
     pub author: UserId,
+    /// The users who reacted, per reaction. The count of a reaction is the number of its users.
+    #[facet(default)]
+    pub reactions: BTreeMap<String, Vec<UserId>>,
+    /// The user who last changed the comment. Comments written before this was tracked have none.
+    #[facet(default)]
+    pub updated_by: Option<UserId>,
+}
+
+/// The fields of every stored entity, for checking queries with [`issuecraft_ql::validate`]
+/// before running them.
+///
+/// ```
+/// let query = issuecraft_ql::parse_query("SELECT titel FROM issues").unwrap();
+/// assert!(issuecraft_ql::validate(&query, &issuecraft_core::schema()).is_err());
+/// ```
+#[must_use]
+pub fn schema() -> Schema {
+    Schema::default()
+        .with_entity::<UserInfo>(EntityType::Users)
+        .with_entity::<ProjectInfo>(EntityType::Projects)
+        .with_entity::<IssueInfo>(EntityType::Issues)
+        .with_fields(EntityType::Issues, [COMMENT_COUNT_COLUMN])
+        .with_entity::<LinkInfo>(EntityType::Links)
+        .with_entity::<CommentInfo>(EntityType::Comments)
+        .with_entity::<AuditEntry>(EntityType::Audit)
+}
+
+/// The current time, to the microsecond. Every stored timestamp is taken from here.
+///
+/// Timestamps are stored as RFC 3339 strings in UTC with exactly six fractional digits
+/// (`2024-01-01T12:00:00.000000Z`), so two that are stored order in time even as strings.
+///
+/// ```
+/// assert_eq!(issuecraft_core::now().nanosecond() % 1_000, 0);
+/// let stored = facet_json::to_string(&issuecraft_core::CommentInfo {
+///     issue: issuecraft_ql::IssueId::new("a#1"),
+///     number: 1,
+///     created_at: time::UtcDateTime::from_unix_timestamp(1_704_110_400).unwrap(),
+///     content: String::new(),
+///     author: issuecraft_ql::UserId::new("alice"),
+///     reactions: Default::default(),
+///     updated_by: None,
+/// })
+/// .unwrap();
+/// assert!(stored.contains(r#""created_at":"2024-01-01T12:00:00.000000Z""#));
+/// ```
+#[must_use]
+pub fn now() -> time::UtcDateTime {
+    let now = time::UtcDateTime::now();
+    now.replace_nanosecond(now.microsecond() * 1_000)
+        .expect("Whole microseconds are valid nanoseconds")
+}
+
+/// How a timestamp is stored, see [`now`]. Reads any RFC 3339 timestamp, so those stored with
+/// another precision before still load.
+#[derive(Facet)]
+#[facet(transparent)]
+pub struct StoredTimestamp(String);
+
+impl TryFrom<StoredTimestamp> for time::UtcDateTime {
+    type Error = String;
+
+    fn try_from(stored: StoredTimestamp) -> Result<Self, Self::Error> {
+        time::UtcDateTime::parse(&stored.0, &time::format_description::well_known::Rfc3339)
+            .map_err(|err| format!("'{}' is not a timestamp: {err}", stored.0))
+    }
+}
+
+impl TryFrom<&time::UtcDateTime> for StoredTimestamp {
+    type Error = String;
+
+    fn try_from(timestamp: &time::UtcDateTime) -> Result<Self, Self::Error> {
+        Ok(Self(format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+            timestamp.year(),
+            u8::from(timestamp.month()),
+            timestamp.day(),
+            timestamp.hour(),
+            timestamp.minute(),
+            timestamp.second(),
+            timestamp.microsecond()
+        )))
+    }
+}
+
+/// Like [`StoredTimestamp`], for timestamps that may be absent.
+#[derive(Facet)]
+#[facet(transparent)]
+pub struct StoredOptionalTimestamp(Option<StoredTimestamp>);
+
+impl TryFrom<StoredOptionalTimestamp> for Option<time::UtcDateTime> {
+    type Error = String;
+
+    fn try_from(stored: StoredOptionalTimestamp) -> Result<Self, Self::Error> {
+        stored.0.map(TryFrom::try_from).transpose()
+    }
+}
+
+impl TryFrom<&Option<time::UtcDateTime>> for StoredOptionalTimestamp {
+    type Error = String;
+
+    fn try_from(timestamp: &Option<time::UtcDateTime>) -> Result<Self, Self::Error> {
+        Ok(Self(
+            timestamp
+                .as_ref()
+                .map(StoredTimestamp::try_from)
+                .transpose()?,
+        ))
+    }
+}
+
+/// A stored field of an entity, as listed by `DESCRIBE`.
+#[derive(Debug, Clone, PartialEq, Eq, Facet)]
+pub struct FieldDescription {
+    pub field: String,
+    #[facet(rename = "type")]
+    pub field_type: String,
+    /// Whether the field may be absent. `field_type` is then the type of its value.
+    pub optional: bool,
+}
+
+/// The fields `entity` is stored with, in declaration order.
+///
+/// Returns `None` for entities that are not stored as a struct, like `meta`.
+///
+/// ```
+/// let fields = issuecraft_core::describe(issuecraft_ql::EntityType::Issues).unwrap();
+/// assert!(fields.iter().any(|f| f.field == "priority" && f.optional));
+/// ```
+#[must_use]
+pub fn describe(entity: EntityType) -> Option<Vec<FieldDescription>> {
+    let shape = match entity {
+        EntityType::Users => UserInfo::SHAPE,
+        EntityType::Projects => ProjectInfo::SHAPE,
+        EntityType::Issues => IssueInfo::SHAPE,
+        EntityType::Links => LinkInfo::SHAPE,
+        EntityType::Comments => CommentInfo::SHAPE,
+        EntityType::Audit => AuditEntry::SHAPE,
+        EntityType::Meta => return None,
+    };
+    let facet::Type::User(facet::UserType::Struct(s)) = shape.ty else {
+        return None;
+    };
+    Some(
+        s.fields
+            .iter()
+            .map(|field| {
+                let (shape, optional) = match field.shape().def {
+                    facet::Def::Option(option) => (option.t, true),
+                    _ => (field.shape(), false),
+                };
+                FieldDescription {
+                    field: field.effective_name().to_string(),
+                    field_type: type_name(shape),
+                    optional,
+                }
+            })
+            .collect(),
+    )
+}
+
+fn type_name(shape: &facet::Shape) -> String {
+    match shape.def {
+        facet::Def::List(list) => format!("List<{}>", type_name(list.t)),
+        _ => shape.type_identifier.to_string(),
+    }
 }
 
 #[derive(Debug, Clone, Facet)]
@@ -133,6 +463,35 @@ pub enum Resource {
     Comment,
 }
 
+/// The kind of mutation recorded in the audit log.
+#[derive(Debug, Clone, Copy, Facet, PartialEq)]
+#[repr(C)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    Assign,
+    Close,
+    Reopen,
+    Move,
+    Rename,
+    Archive,
+    Unarchive,
+    Comment,
+}
+
+/// A row of the append-only audit log, written together with the mutation it describes.
+#[derive(Debug, Clone, Facet)]
+pub struct AuditEntry {
+    #[facet(proxy = StoredTimestamp)]
+    pub timestamp: time::UtcDateTime,
+    pub user: UserId,
+    pub action: AuditAction,
+    pub resource: Resource,
+    pub id: String,
+    pub query: String,
+}
+
 #[derive(Debug, Clone, Copy, Facet, PartialEq)]
 #[repr(C)]
 #[facet(transparent)]
@@ -193,13 +552,44 @@ impl AuthorizationProvider for SingleUserAuthorizationProvider {
     }
 }
 
+/// Authorizes every user, for setups where users were already authenticated elsewhere.
+pub struct AnyUserAuthorizationProvider;
+
+#[async_trait]
+impl AuthorizationProvider for AnyUserAuthorizationProvider {
+    async fn check_authorization(
+        &self,
+        principal: &UserId,
+        action: &Action,
+        resource: &Resource,
+        _context: Option<FacetValue>,
+    ) -> Result<AuthorizationResult, BackendError> {
+        Ok(AuthorizationResult {
+            user: principal.clone(),
+            action: action.clone(),
+            resource: resource.clone(),
+            status: AuthorizationStatus::Authorized,
+        })
+    }
+}
+
+/// Resolves the user a bearer token was issued to.
+#[async_trait]
+pub trait UserProvider {
+    /// Returns `None` if the token does not belong to any user.
+    async fn user_for_token(&self, token: &str) -> Result<Option<UserId>, BackendError>;
+}
+
 #[async_trait]
 pub trait ExecutionEngine {
+    /// Runs `query` as `user`. `session` holds the options set with `SET`, which apply to the
+    /// queries that follow in the same session, like the statements of a file or a connection.
     async fn execute<AP: AuthorizationProvider + Sync>(
         &mut self,
         authorization_provider: &AP,
         user: UserId,
         query: &IqlQuery,
+        session: &mut SessionOptions,
     ) -> Result<ExecutionResult, BackendError>;
 }
 
@@ -215,10 +605,17 @@ pub struct UntypedEntry {
     pub value: FacetValue,
 }
 
-#[derive(Debug, Clone, Builder)]
+/// The data of a SELECT: the names of the selected columns in order, and the matching rows.
+#[derive(Debug, Facet)]
+pub struct SelectData<T: 'static> {
+    pub columns: Vec<String>,
+    pub rows: Vec<T>,
+}
+
+#[derive(Debug, Clone, Builder, Facet)]
 pub struct ExecutionResult {
     #[builder(start_fn)]
-    pub rows: u128,
+    pub rows: u64,
     pub info: Option<String>,
     pub data: Option<String>,
 }
@@ -229,14 +626,80 @@ impl Display for ExecutionResult {
         if let Some(info) = &self.info {
             write!(f, "\nInfo: {info}")?;
         }
+        if let Some((columns, rows)) = self.table() {
+            return write_table(f, &columns, &rows);
+        }
         if let Some(data) = &self.data {
-            let data: Vec<UntypedEntry> = facet_json::from_str(&data).unwrap();
-            write!(f, "\nData: {}", data.pretty())?;
+            // The data is rendered generically so results of any entity or shape can be shown
+            match facet_json::from_str::<FacetValue>(data) {
+                Ok(value) => write!(f, "\nData: {}", value.pretty())?,
+                Err(_) => write!(f, "\nData: {data}")?,
+            }
         }
         Ok(())
     }
 }
 
+/// Writes the rows below their columns, each column as wide as its widest cell.
+fn write_table(
+    f: &mut std::fmt::Formatter<'_>,
+    columns: &[String],
+    rows: &[Vec<String>],
+) -> std::fmt::Result {
+    let widths = columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            rows.iter()
+                .map(|row| row[index].chars().count())
+                .chain([column.chars().count()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    let line = |cells: &mut dyn Iterator<Item = &String>| {
+        cells
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+    write!(f, "\n{}", line(&mut columns.iter()))?;
+    let separator = widths
+        .iter()
+        .map(|width| "-".repeat(*width))
+        .collect::<Vec<_>>()
+        .join("-+-");
+    write!(f, "\n{separator}")?;
+    for row in rows {
+        write!(f, "\n{}", line(&mut row.iter()))?;
+    }
+    Ok(())
+}
+
+/// The key and the fields of a row that is an entry, which is serialized as its key next to
+/// an object of the selected fields.
+fn entry(row: &FacetValue) -> Option<(&FacetValue, &VObject)> {
+    let row = row.as_object()?;
+    let fields = row.get("value")?.as_object()?;
+    (row.len() == 2).then_some((row.get("key")?, fields))
+}
+
+/// Renders a value for a table cell. Null becomes an empty cell and strings are shown
+/// without quotes.
+fn cell(value: Option<&FacetValue>) -> String {
+    match value {
+        None => String::new(),
+        Some(value) if value.is_null() => String::new(),
+        Some(value) => match value.as_string() {
+            Some(string) => string.as_str().to_string(),
+            None => facet_json::to_string(value).unwrap_or_default(),
+        },
+    }
+}
+
 impl From<String> for ExecutionResult {
     fn from(s: String) -> Self {
         Self {
@@ -259,7 +722,7 @@ impl From<&str> for ExecutionResult {
 
 impl ExecutionResult {
     #[must_use]
-    pub fn new(rows: u128) -> Self {
+    pub fn new(rows: u64) -> Self {
         Self {
             rows: rows,
             info: None,
@@ -280,6 +743,80 @@ impl ExecutionResult {
     pub fn inc(&mut self) {
         self.rows += 1;
     }
+
+    /// The columns and the cells of each row if the data is the [`SelectData`] of a SELECT.
+    ///
+    /// Rows that are entries also get their id, as the first column unless it was selected.
+    ///
+    /// ```
+    /// use issuecraft_core::ExecutionResult;
+    ///
+    /// let data = r#"{"columns":["title"],"rows":[{"key":"a#1","value":{"title":"Crash"}}]}"#;
+    /// let result = ExecutionResult::zero().with_data(data).build();
+    /// let (columns, rows) = result.table().unwrap();
+    /// assert_eq!(columns, ["id", "title"]);
+    /// assert_eq!(rows, [["a#1", "Crash"]]);
+    /// ```
+    #[must_use]
+    pub fn table(&self) -> Option<(Vec<String>, Vec<Vec<String>>)> {
+        let data = facet_json::from_str::<FacetValue>(self.data.as_deref()?).ok()?;
+        let data = data.as_object()?;
+        let mut columns = data
+            .get("columns")?
+            .as_array()?
+            .iter()
+            .map(|column| Some(column.as_string()?.as_str().to_string()))
+            .collect::<Option<Vec<_>>>()?;
+        let rows = data.get("rows")?.as_array()?;
+        if rows.iter().any(|row| entry(row).is_some()) && !columns.iter().any(|c| c == "id") {
+            columns.insert(0, "id".to_string());
+        }
+        let rows = rows
+            .iter()
+            .map(|row| {
+                columns
+                    .iter()
+                    .map(|column| match entry(row) {
+                        Some((key, _)) if column == "id" => cell(Some(key)),
+                        Some((_, fields)) => cell(fields.get(column)),
+                        None => cell(row.as_object().and_then(|row| row.get(column))),
+                    })
+                    .collect()
+            })
+            .collect();
+        Some((columns, rows))
+    }
+}
+
+impl<S: execution_result_builder::State> ExecutionResultBuilder<S> {
+    /// Sets the info message from a string slice.
+    ///
+    /// ```
+    /// use issuecraft_core::ExecutionResult;
+    ///
+    /// let result = ExecutionResult::one().with_info("created").build();
+    /// assert_eq!(result.info.as_deref(), Some("created"));
+    /// ```
+    pub fn with_info(
+        self,
+        info: &str,
+    ) -> ExecutionResultBuilder<execution_result_builder::SetInfo<S>>
+    where
+        S::Info: execution_result_builder::IsUnset,
+    {
+        self.info(info.to_string())
+    }
+
+    /// Sets the serialized data from a string slice.
+    pub fn with_data(
+        self,
+        data: &str,
+    ) -> ExecutionResultBuilder<execution_result_builder::SetData<S>>
+    where
+        S::Data: execution_result_builder::IsUnset,
+    {
+        self.data(data.to_string())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -356,3 +893,13 @@ impl EntityId for CommentId {
         EntityType::Comments
     }
 }
+
+impl EntityId for LinkId {
+    type EntityType = LinkInfo;
+    fn from_str(s: &str) -> Self {
+        Self::new(s)
+    }
+    fn kind() -> EntityType {
+        EntityType::Links
+    }
+}
@@ -1,10 +1,12 @@
 use std::{
+    collections::HashSet,
     fmt::{self, Display},
     ops::Deref,
 };
 
 use facet::{Facet, Type};
 use facet_value::Value as FacetValue;
+use time::{OffsetDateTime, UtcOffset, format_description::well_known::Rfc3339};
 
 use crate::IqlError;
 
@@ -17,7 +19,240 @@ pub enum IqlQuery {
     Assign(AssignStatement),
     Close(CloseStatement),
     Reopen(ReopenStatement),
+    /// Transfers an issue to another project, where it gets a new number.
+    Move(MoveStatement),
+    /// Changes the id of a project, and with it the ids of its issues and their comments.
+    Rename(RenameStatement),
+    Archive(ArchiveStatement),
+    Unarchive(ArchiveStatement),
     Comment(CommentStatement),
+    React(ReactionStatement),
+    Unreact(ReactionStatement),
+    /// Rebuilds the secondary indexes of the backend.
+    Reindex,
+    /// Loads a dump of every table, as the backend exported it.
+    Import(ImportStatement),
+    /// Lists what happened to an issue and its comments, oldest first.
+    History(IssueId),
+    /// Lists the stored fields of an entity with their types.
+    Describe(EntityType),
+    /// Starts a transaction that the following statements are part of.
+    Begin,
+    /// Ends the open transaction, keeping its changes.
+    Commit,
+    /// Ends the open transaction, undoing its changes.
+    Rollback,
+    /// Reports what the wrapped query would do without writing anything.
+    Explain(Box<IqlQuery>),
+    /// Changes an option of the session for the queries that follow it.
+    Set(SessionOption),
+}
+
+impl IqlQuery {
+    /// Returns every field name the query reads or writes.
+    #[must_use]
+    pub fn referenced_fields(&self) -> HashSet<String> {
+        struct FieldCollector(HashSet<String>);
+
+        impl Visitor for FieldCollector {
+            fn visit_field(&mut self, field: &str) {
+                self.0.insert(field.to_string());
+            }
+        }
+
+        let mut collector = FieldCollector(HashSet::new());
+        walk_query(&mut collector, self);
+        collector.0
+    }
+
+    /// Returns the kinds of entities the query operates on.
+    #[must_use]
+    pub fn referenced_entities(&self) -> HashSet<EntityType> {
+        struct EntityCollector(HashSet<EntityType>);
+
+        impl Visitor for EntityCollector {
+            fn visit_entity(&mut self, entity: EntityType) {
+                self.0.insert(entity);
+            }
+        }
+
+        let mut collector = EntityCollector(HashSet::new());
+        walk_query(&mut collector, self);
+        collector.0
+    }
+
+    /// Whether the query only reads. `EXPLAIN` never writes, whatever it wraps.
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            IqlQuery::Select(_)
+                | IqlQuery::History(_)
+                | IqlQuery::Describe(_)
+                | IqlQuery::Explain(_)
+                | IqlQuery::Set(_)
+        )
+    }
+}
+
+/// Read-only traversal over the parts of a query.
+///
+/// All methods default to doing nothing, so implementors only override what they are
+/// interested in. Use [`walk_query`] to drive a visitor over a whole query.
+pub trait Visitor {
+    /// Called once for the query before any of its parts.
+    fn visit_query(&mut self, _query: &IqlQuery) {}
+
+    /// Called with the kind of entity the query operates on.
+    fn visit_entity(&mut self, _entity: EntityType) {}
+
+    /// Called for every field referenced by columns, filters, grouping, ordering and updates.
+    fn visit_field(&mut self, _field: &str) {}
+
+    /// Called for every filter node, including nested ones.
+    fn visit_filter(&mut self, _filter: &FilterExpression) {}
+
+    /// Called for every literal value in filters and updates.
+    fn visit_value(&mut self, _value: &IqlValue) {}
+}
+
+pub fn walk_query<V: Visitor + ?Sized>(visitor: &mut V, query: &IqlQuery) {
+    visitor.visit_query(query);
+    match query {
+        IqlQuery::Create(create) => {
+            visitor.visit_entity(match create {
+                CreateStatement::User { .. } => EntityType::Users,
+                CreateStatement::Project { .. } => EntityType::Projects,
+                CreateStatement::Issue { .. } | CreateStatement::Issues { .. } => {
+                    EntityType::Issues
+                }
+            });
+            if let CreateStatement::Project {
+                on_conflict: Some(OnConflict::Update(updates)),
+                ..
+            } = create
+            {
+                for field_update in updates {
+                    visitor.visit_field(&field_update.field);
+                    visitor.visit_value(&field_update.value);
+                }
+            }
+        }
+        IqlQuery::Select(select) => {
+            visitor.visit_entity(select.from);
+            if let Some(join) = &select.join {
+                visitor.visit_entity(join.entity);
+                visitor.visit_field(&join.left_field);
+            }
+            if let Columns::Named(columns) = &select.columns {
+                for (column, _) in columns.iter().filter(|(column, _)| column != COUNT_COLUMN) {
+                    visitor.visit_field(column);
+                }
+            }
+            if let Some(filter) = &select.filter {
+                walk_filter(visitor, filter);
+            }
+            if let Some(group_by) = &select.group_by {
+                visitor.visit_field(group_by);
+            }
+            if let Some(order_by) = &select.order_by {
+                visitor.visit_field(&order_by.field);
+            }
+        }
+        IqlQuery::Update(update) => {
+            visitor.visit_entity(update.entity.entity_type());
+            for field_update in &update.updates {
+                visitor.visit_field(&field_update.field);
+                visitor.visit_value(&field_update.value);
+            }
+            if let UpdateTarget::Bulk {
+                filter: Some(filter),
+                ..
+            } = &update.entity
+            {
+                walk_filter(visitor, filter);
+            }
+        }
+        IqlQuery::Delete(delete) => {
+            visitor.visit_entity(delete.entity.entity_type());
+            if let DeleteTarget::Bulk {
+                filter: Some(filter),
+                ..
+            } = &delete.entity
+            {
+                walk_filter(visitor, filter);
+            }
+        }
+        IqlQuery::Close(close) => {
+            visitor.visit_entity(EntityType::Issues);
+            if let CloseTarget::Bulk { filter } = &close.target {
+                walk_filter(visitor, filter);
+            }
+        }
+        IqlQuery::Assign(_) | IqlQuery::Reopen(_) => {
+            visitor.visit_entity(EntityType::Issues);
+        }
+        IqlQuery::Move(_) => {
+            visitor.visit_entity(EntityType::Issues);
+            visitor.visit_entity(EntityType::Comments);
+        }
+        IqlQuery::Rename(_) => {
+            visitor.visit_entity(EntityType::Projects);
+            visitor.visit_entity(EntityType::Issues);
+            visitor.visit_entity(EntityType::Comments);
+        }
+        IqlQuery::Archive(archive) | IqlQuery::Unarchive(archive) => {
+            visitor.visit_entity(archive.target.entity_type());
+        }
+        IqlQuery::Comment(_) | IqlQuery::React(_) | IqlQuery::Unreact(_) => {
+            visitor.visit_entity(EntityType::Comments);
+        }
+        IqlQuery::History(_) => {
+            visitor.visit_entity(EntityType::Issues);
+            visitor.visit_entity(EntityType::Comments);
+        }
+        IqlQuery::Describe(entity) => visitor.visit_entity(*entity),
+        IqlQuery::Import(_) => {
+            visitor.visit_entity(EntityType::Users);
+            visitor.visit_entity(EntityType::Projects);
+            visitor.visit_entity(EntityType::Issues);
+            visitor.visit_entity(EntityType::Comments);
+        }
+        IqlQuery::Reindex
+        | IqlQuery::Begin
+        | IqlQuery::Commit
+        | IqlQuery::Rollback
+        | IqlQuery::Set(_) => {}
+        IqlQuery::Explain(query) => walk_query(visitor, query),
+    }
+}
+
+pub fn walk_filter<V: Visitor + ?Sized>(visitor: &mut V, filter: &FilterExpression) {
+    visitor.visit_filter(filter);
+    match filter {
+        FilterExpression::Comparison { field, value, .. } => {
+            visitor.visit_field(field);
+            visitor.visit_value(value);
+        }
+        FilterExpression::And(left, right) | FilterExpression::Or(left, right) => {
+            walk_filter(visitor, left);
+            walk_filter(visitor, right);
+        }
+        FilterExpression::Not(expr) => walk_filter(visitor, expr),
+        FilterExpression::In { field, values } => {
+            visitor.visit_field(field);
+            for value in values {
+                visitor.visit_value(value);
+            }
+        }
+        FilterExpression::IsNull(field) | FilterExpression::IsNotNull(field) => {
+            visitor.visit_field(field);
+        }
+        FilterExpression::Has { field, value } => {
+            visitor.visit_field(field);
+            visitor.visit_value(value);
+        }
+    }
 }
 
 #[derive(Debug, Clone, Facet, PartialEq)]
@@ -118,6 +353,26 @@ impl Deref for CommentId {
     }
 }
 
+#[derive(Debug, Clone, Facet, PartialEq)]
+#[repr(C)]
+#[facet(transparent)]
+pub struct LinkId(String);
+
+impl LinkId {
+    #[must_use]
+    pub fn new(s: &str) -> Self {
+        Self(s.to_owned())
+    }
+}
+
+impl Deref for LinkId {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum CreateStatement {
     User {
@@ -130,6 +385,8 @@ pub enum CreateStatement {
         name: Option<String>,
         description: Option<String>,
         owner: Option<UserId>,
+        /// What to do instead of failing when the project already exists.
+        on_conflict: Option<OnConflict>,
     },
     Issue {
         project: ProjectId,
@@ -138,23 +395,77 @@ pub enum CreateStatement {
         description: Option<String>,
         priority: Option<Priority>,
         assignee: Option<UserId>,
+        labels: Vec<String>,
+    },
+    /// Creates an issue per title in one transaction, numbered in the order of the titles.
+    Issues {
+        project: ProjectId,
+        kind: IssueKind,
+        titles: Vec<String>,
     },
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum OnConflict {
+    /// Leaves the existing entity as it is, written `ON CONFLICT DO NOTHING`.
+    DoNothing,
+    /// Updates the existing entity instead, written `ON CONFLICT UPDATE SET <field> = <value>`.
+    Update(Vec<FieldUpdate>),
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct SelectStatement {
     pub columns: Columns,
     pub from: EntityType,
+    pub from_alias: Option<String>,
+    pub join: Option<Join>,
     pub filter: Option<FilterExpression>,
+    pub group_by: Option<String>,
     pub order_by: Option<OrderBy>,
+    /// Key of the entity to resume after, for paging in key order.
+    pub after: Option<String>,
+    /// Whether archived issues and projects are selected too, written `INCLUDING ARCHIVED`.
+    pub include_archived: bool,
     pub limit: Option<u64>,
     pub offset: Option<u64>,
 }
 
+/// An inner equality join of the selected entity with another entity.
+///
+/// Fields of the joined entity are referenced as `<entity>.<field>` everywhere else in the
+/// statement, e.g. `projects.name`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Join {
+    pub entity: EntityType,
+    pub alias: Option<String>,
+    /// Field of the selected entity.
+    pub left_field: String,
+    /// Field of the joined entity.
+    pub right_field: String,
+}
+
+/// Column name used for the `COUNT(*)` aggregate.
+pub const COUNT_COLUMN: &str = "COUNT(*)";
+
+/// Column of issues with the number of their comments. It is computed when selected, so it
+/// cannot be filtered or ordered by.
+pub const COMMENT_COUNT_COLUMN: &str = "comment_count";
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Columns {
     All,
-    Named(Vec<String>),
+    /// Column names with their optional `AS` alias.
+    Named(Vec<(String, Option<String>)>),
+}
+
+/// Returns the key a selected column is rendered under.
+#[must_use]
+pub fn column_output_name<'a>(column: &'a str, alias: Option<&'a str>) -> &'a str {
+    match alias {
+        Some(alias) => alias,
+        None if column == COUNT_COLUMN => "count",
+        None => column,
+    }
 }
 
 impl Columns {
@@ -165,27 +476,97 @@ impl Columns {
             Columns::Named(cols) => cols.len(),
         }
     }
+
+    #[must_use]
+    pub fn has_count(&self) -> bool {
+        match self {
+            Columns::All => false,
+            Columns::Named(cols) => cols.iter().any(|(col, _)| col == COUNT_COLUMN),
+        }
+    }
+}
+
+/// The kind of a statement, as far as its leading keywords tell.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StatementKind {
+    /// No statement keyword has been typed yet.
+    Unknown,
+    /// `CREATE`, with the kind of entity once it has been typed.
+    Create(Option<EntityType>),
+    Select,
+    Update,
+    Delete,
+    Assign,
+    Close,
+    Reopen,
+    Move,
+    Rename,
+    Archive,
+    Unarchive,
+    Comment,
+    React,
+    Unreact,
+    Reindex,
+    Import,
+    History,
+    Describe,
+    Begin,
+    Commit,
+    Rollback,
+    Set,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum EntityType {
     Users,
     Projects,
     Issues,
     Comments,
+    /// Relationships between issues. Can only be selected.
+    Links,
+    /// The storage's own key/value pairs. Can only be selected.
+    Meta,
+    /// The log of every mutation, oldest first. Can only be selected.
+    Audit,
 }
 
-impl fmt::Display for EntityType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl EntityType {
+    /// The name of a single entity, as used in statements like `CREATE ISSUE` and in errors
+    /// about one entity.
+    #[must_use]
+    pub fn singular(self) -> &'static str {
+        match self {
+            EntityType::Users => "USER",
+            EntityType::Projects => "PROJECT",
+            EntityType::Issues => "ISSUE",
+            EntityType::Comments => "COMMENT",
+            EntityType::Links => "LINK",
+            EntityType::Meta => "META",
+            EntityType::Audit => "AUDIT",
+        }
+    }
+
+    /// The name of the collection, as used after `FROM`. This is also what `Display` writes.
+    #[must_use]
+    pub fn plural(self) -> &'static str {
         match self {
-            EntityType::Users => write!(f, "USERS"),
-            EntityType::Projects => write!(f, "PROJECTS"),
-            EntityType::Issues => write!(f, "ISSUES"),
-            EntityType::Comments => write!(f, "COMMENTS"),
+            EntityType::Users => "USERS",
+            EntityType::Projects => "PROJECTS",
+            EntityType::Issues => "ISSUES",
+            EntityType::Comments => "COMMENTS",
+            EntityType::Links => "LINKS",
+            EntityType::Meta => "META",
+            EntityType::Audit => "AUDIT",
         }
     }
 }
 
+impl fmt::Display for EntityType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.plural())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum FilterExpression {
     Comparison {
@@ -202,9 +583,108 @@ pub enum FilterExpression {
     },
     IsNull(String),
     IsNotNull(String),
+    /// Matches if the array stored in `field` contains `value`.
+    Has {
+        field: String,
+        value: IqlValue,
+    },
 }
 
 impl FilterExpression {
+    /// Calls `f` with every field name referenced by the filter, stopping at the first error.
+    pub fn try_for_each_field_mut<E>(
+        &mut self,
+        f: &mut impl FnMut(&mut String) -> Result<(), E>,
+    ) -> Result<(), E> {
+        match self {
+            FilterExpression::Comparison {
+                field,
+                value: IqlValue::Field(other),
+                ..
+            } => {
+                f(field)?;
+                f(other)
+            }
+            FilterExpression::Comparison { field, .. }
+            | FilterExpression::In { field, .. }
+            | FilterExpression::IsNull(field)
+            | FilterExpression::IsNotNull(field)
+            | FilterExpression::Has { field, .. } => f(field),
+            FilterExpression::And(left, right) | FilterExpression::Or(left, right) => {
+                left.try_for_each_field_mut(f)?;
+                right.try_for_each_field_mut(f)
+            }
+            FilterExpression::Not(expr) => expr.try_for_each_field_mut(f),
+        }
+    }
+
+    /// Returns `true` if the filter can be shown to never match without looking at any data.
+    ///
+    /// This is a conservative check: `false` does not imply that the filter can match.
+    #[must_use]
+    pub fn is_trivially_false(&self) -> bool {
+        match self {
+            FilterExpression::In { values, .. } => values.is_empty(),
+            FilterExpression::And(left, right) => {
+                left.is_trivially_false()
+                    || right.is_trivially_false()
+                    || Self::contradicts(left, right)
+            }
+            FilterExpression::Or(left, right) => {
+                left.is_trivially_false() && right.is_trivially_false()
+            }
+            FilterExpression::Comparison { .. }
+            | FilterExpression::Not(_)
+            | FilterExpression::IsNull(_)
+            | FilterExpression::IsNotNull(_)
+            | FilterExpression::Has { .. } => false,
+        }
+    }
+
+    /// The id a row must have for the filter to match, if it requires `id = '<id>'` alongside
+    /// whatever else it checks. Backends can then fetch that row instead of scanning.
+    #[must_use]
+    pub fn required_id(&self) -> Option<&str> {
+        match self {
+            FilterExpression::Comparison {
+                field,
+                op: ComparisonOp::Equal,
+                value: IqlValue::String(id),
+            } if field == "id" => Some(id),
+            FilterExpression::And(left, right) => {
+                left.required_id().or_else(|| right.required_id())
+            }
+            _ => None,
+        }
+    }
+
+    fn contradicts(left: &FilterExpression, right: &FilterExpression) -> bool {
+        match (left, right) {
+            (
+                FilterExpression::Comparison {
+                    field: left_field,
+                    op: ComparisonOp::Equal,
+                    value: left_value,
+                },
+                FilterExpression::Comparison {
+                    field: right_field,
+                    op: ComparisonOp::Equal,
+                    value: right_value,
+                },
+            ) => {
+                left_field == right_field
+                    && !matches!(left_value, IqlValue::Field(_))
+                    && !matches!(right_value, IqlValue::Field(_))
+                    && left_value.to_facet() != right_value.to_facet()
+            }
+            (FilterExpression::IsNull(left_field), FilterExpression::IsNotNull(right_field))
+            | (FilterExpression::IsNotNull(left_field), FilterExpression::IsNull(right_field)) => {
+                left_field == right_field
+            }
+            _ => false,
+        }
+    }
+
     #[must_use]
     pub fn matches(&self, id: &str, value: &FacetValue) -> bool {
         match self {
@@ -216,17 +696,37 @@ impl FilterExpression {
                 let Some(obj) = value.as_object() else {
                     return false;
                 };
+                let id_value = facet_value::VString::new(id).into_value();
+                let lookup = |name: &str| {
+                    if name == "id" {
+                        Some(&id_value)
+                    } else {
+                        obj.get(name)
+                    }
+                };
 
-                if field == "id" {
-                    let id_value = facet_value::VString::new(id).into_value();
-                    return Self::compare_values(&id_value, op, filter_value);
-                }
+                // The right-hand side may name another field of the same row
+                let other_value = match filter_value {
+                    IqlValue::Field(other) => match lookup(other) {
+                        Some(other_value) => other_value.clone(),
+                        None => return false,
+                    },
+                    _ => filter_value.to_facet(),
+                };
 
-                let Some(field_value) = obj.get(field) else {
+                let Some(field_value) = lookup(field) else {
                     return false;
                 };
 
-                Self::compare_values(field_value, op, filter_value)
+                if let IqlValue::DateTime(timestamp) = filter_value
+                    && let Some(field_value) = field_value
+                        .as_string()
+                        .and_then(|value| normalize_timestamp(value.as_str()))
+                    && let Some(timestamp) = normalize_timestamp(timestamp)
+                {
+                    return Self::compare_values(&field_value, op, &timestamp);
+                }
+                Self::compare_values(field_value, op, &other_value)
             }
             FilterExpression::And(left, right) => {
                 left.matches(id, value) && right.matches(id, value)
@@ -240,12 +740,28 @@ impl FilterExpression {
                     return false;
                 };
 
-                let Some(field_value) = obj.get(field) else {
+                if field == "id" {
+                    let id_value = facet_value::VString::new(id).into_value();
+                    return values.iter().any(|filter_val| {
+                        Self::compare_values(
+                            &id_value,
+                            &ComparisonOp::Equal,
+                            &filter_val.to_facet(),
+                        )
+                    });
+                }
+
+                let field_value = obj.get(field);
+                // A NULL in the list also matches fields that are missing or null
+                if values.contains(&IqlValue::Null) && field_value.is_none_or(FacetValue::is_null) {
+                    return true;
+                }
+                let Some(field_value) = field_value else {
                     return false;
                 };
 
                 values.iter().any(|filter_val| {
-                    Self::compare_values(field_value, &ComparisonOp::Equal, filter_val)
+                    Self::compare_values(field_value, &ComparisonOp::Equal, &filter_val.to_facet())
                 })
             }
             FilterExpression::IsNull(field) => {
@@ -268,33 +784,71 @@ impl FilterExpression {
                     Some(v) => !v.is_null(),
                 }
             }
+            FilterExpression::Has {
+                field,
+                value: filter_value,
+            } => {
+                let Some(obj) = value.as_object() else {
+                    return false;
+                };
+
+                let Some(items) = obj.get(field).and_then(|v| v.as_array()) else {
+                    return false;
+                };
+
+                let filter_value = filter_value.to_facet();
+                items
+                    .iter()
+                    .any(|item| Self::compare_values(item, &ComparisonOp::Equal, &filter_value))
+            }
         }
     }
 
-    fn compare_values(
-        field_value: &FacetValue,
-        op: &ComparisonOp,
-        filter_value: &IqlValue,
-    ) -> bool {
-        match op {
-            ComparisonOp::Equal => field_value == &filter_value.to_facet(),
-            ComparisonOp::NotEqual => field_value != &filter_value.to_facet(),
-            ComparisonOp::GreaterThan => {
-                field_value.partial_cmp(&filter_value.to_facet())
-                    == Some(std::cmp::Ordering::Greater)
+    fn compare_values(field_value: &FacetValue, op: &ComparisonOp, other: &FacetValue) -> bool {
+        // Struct variants like `{"Closed": {"reason": ..}}` compare equal to their variant name
+        let field_value = match field_value.as_object() {
+            Some(object) if object.len() == 1 && other.as_string().is_some() => {
+                let (variant, _) = object.iter().next().expect("Object has one entry");
+                &facet_value::VString::new(variant.as_str()).into_value()
             }
-            ComparisonOp::LessThan => {
-                field_value.partial_cmp(&filter_value.to_facet()) == Some(std::cmp::Ordering::Less)
+            _ => field_value,
+        };
+        let coerced = Self::coerce_numbers(field_value, other);
+        // A number has no order against a value that doesn't hold one, like the string "many"
+        let ordered =
+            coerced.is_some() || field_value.as_number().is_some() == other.as_number().is_some();
+        let (field_value, other) = match &coerced {
+            Some((field_value, other)) => (field_value, other),
+            None => (field_value, other),
+        };
+        let ordering = if ordered {
+            field_value.partial_cmp(other)
+        } else {
+            None
+        };
+        match op {
+            ComparisonOp::Equal => field_value == other,
+            ComparisonOp::NotEqual => field_value != other,
+            ComparisonOp::EqualIgnoreCase => match (field_value.as_string(), other.as_string()) {
+                (Some(left), Some(right)) => {
+                    left.as_str().to_lowercase() == right.as_str().to_lowercase()
+                }
+                _ => field_value == other,
+            },
+            ComparisonOp::NotEqualIgnoreCase => {
+                !Self::compare_values(field_value, &ComparisonOp::EqualIgnoreCase, other)
             }
+            ComparisonOp::GreaterThan => ordering == Some(std::cmp::Ordering::Greater),
+            ComparisonOp::LessThan => ordering == Some(std::cmp::Ordering::Less),
             ComparisonOp::GreaterThanOrEqual => {
                 matches!(
-                    field_value.partial_cmp(&filter_value.to_facet()),
+                    ordering,
                     Some(std::cmp::Ordering::Greater | std::cmp::Ordering::Equal)
                 )
             }
             ComparisonOp::LessThanOrEqual => {
                 matches!(
-                    field_value.partial_cmp(&filter_value.to_facet()),
+                    ordering,
                     Some(std::cmp::Ordering::Less | std::cmp::Ordering::Equal)
                 )
             }
@@ -303,8 +857,8 @@ impl FilterExpression {
                     .as_string()
                     .map(facet_value::VString::as_str)
                     .unwrap_or_default();
-                if let IqlValue::String(pattern) = filter_value {
-                    let pattern = pattern.replace('%', ".*");
+                if let Some(pattern) = other.as_string() {
+                    let pattern = pattern.as_str().replace('%', ".*");
                     if let Ok(regex) = regex::Regex::new(&format!("^{pattern}$")) {
                         regex.is_match(field_str)
                     } else {
@@ -316,12 +870,127 @@ impl FilterExpression {
             }
         }
     }
+
+    /// Numbers that were serialized as strings, like `"12"`, compare as the number they hold
+    /// when the other side is a number. Two strings still compare as strings.
+    fn coerce_numbers(left: &FacetValue, right: &FacetValue) -> Option<(FacetValue, FacetValue)> {
+        fn number(value: &FacetValue) -> Option<FacetValue> {
+            let number = match value.as_number() {
+                Some(number) => number.to_f64()?,
+                None => value.as_string()?.as_str().trim().parse().ok()?,
+            };
+            facet_value::VNumber::from_f64(number).map(facet_value::VNumber::into_value)
+        }
+
+        if left.as_number().is_none() && right.as_number().is_none() {
+            return None;
+        }
+        Some((number(left)?, number(right)?))
+    }
+
+    #[must_use]
+    pub fn and(self, other: FilterExpression) -> FilterExpression {
+        FilterExpression::And(Box::new(self), Box::new(other))
+    }
+
+    #[must_use]
+    pub fn or(self, other: FilterExpression) -> FilterExpression {
+        FilterExpression::Or(Box::new(self), Box::new(other))
+    }
+}
+
+impl std::ops::Not for FilterExpression {
+    type Output = FilterExpression;
+
+    fn not(self) -> Self::Output {
+        FilterExpression::Not(Box::new(self))
+    }
+}
+
+/// Starts building a filter on the field `name`.
+///
+/// ```
+/// use issuecraft_ql::{FilterExpression, Priority, field};
+///
+/// let filter = field("status").eq("open").and(field("priority").gt(Priority::Medium));
+/// assert!(matches!(filter, FilterExpression::And(_, _)));
+/// ```
+#[must_use]
+pub fn field(name: &str) -> FieldFilter {
+    FieldFilter(name.to_string())
+}
+
+/// A field awaiting the condition that turns it into a [`FilterExpression`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldFilter(String);
+
+impl FieldFilter {
+    fn compare(self, op: ComparisonOp, value: impl Into<IqlValue>) -> FilterExpression {
+        FilterExpression::Comparison {
+            field: self.0,
+            op,
+            value: value.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn eq(self, value: impl Into<IqlValue>) -> FilterExpression {
+        self.compare(ComparisonOp::Equal, value)
+    }
+
+    #[must_use]
+    pub fn ne(self, value: impl Into<IqlValue>) -> FilterExpression {
+        self.compare(ComparisonOp::NotEqual, value)
+    }
+
+    #[must_use]
+    pub fn eq_ignore_case(self, value: impl Into<IqlValue>) -> FilterExpression {
+        self.compare(ComparisonOp::EqualIgnoreCase, value)
+    }
+
+    #[must_use]
+    pub fn ne_ignore_case(self, value: impl Into<IqlValue>) -> FilterExpression {
+        self.compare(ComparisonOp::NotEqualIgnoreCase, value)
+    }
+
+    #[must_use]
+    pub fn gt(self, value: impl Into<IqlValue>) -> FilterExpression {
+        self.compare(ComparisonOp::GreaterThan, value)
+    }
+
+    #[must_use]
+    pub fn lt(self, value: impl Into<IqlValue>) -> FilterExpression {
+        self.compare(ComparisonOp::LessThan, value)
+    }
+
+    #[must_use]
+    pub fn like(self, pattern: &str) -> FilterExpression {
+        self.compare(ComparisonOp::Like, pattern)
+    }
+
+    #[must_use]
+    pub fn in_<V: Into<IqlValue>>(self, values: impl IntoIterator<Item = V>) -> FilterExpression {
+        FilterExpression::In {
+            field: self.0,
+            values: values.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    #[must_use]
+    pub fn is_null(self) -> FilterExpression {
+        FilterExpression::IsNull(self.0)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ComparisonOp {
     Equal,
     NotEqual,
+    /// Equality that ignores the case of strings, written `=~`
+    EqualIgnoreCase,
+    /// Inequality that ignores the case of strings, written `!~`. Like `!=`, it never matches
+    /// rows that lack the field.
+    NotEqualIgnoreCase,
     GreaterThan,
     LessThan,
     GreaterThanOrEqual,
@@ -333,6 +1002,18 @@ pub enum ComparisonOp {
 pub struct OrderBy {
     pub field: String,
     pub direction: OrderDirection,
+    pub nulls: Option<NullsOrder>,
+}
+
+impl OrderBy {
+    /// Whether rows without a value sort before all others. Without an explicit
+    /// `NULLS FIRST`/`NULLS LAST` this follows SQL: last for ASC, first for DESC.
+    pub fn nulls_first(&self) -> bool {
+        match &self.nulls {
+            Some(nulls) => *nulls == NullsOrder::First,
+            None => self.direction == OrderDirection::Desc,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -341,6 +1022,109 @@ pub enum OrderDirection {
     Desc,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+/// An option of `SET`, given with the value it is set to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionOption {
+    /// `SET default_limit = <n>` limits selects without a `LIMIT`. `NULL` lifts the limit.
+    DefaultLimit(Option<u64>),
+    /// `SET nulls = FIRST | LAST` orders rows without a value when `ORDER BY` doesn't say
+    /// where they go. `DEFAULT` returns to the SQL ordering of [`OrderBy::nulls_first`].
+    Nulls(Option<NullsOrder>),
+    /// `SET case_sensitive = FALSE` makes `=` and `!=` ignore the case of strings.
+    CaseSensitive(bool),
+}
+
+/// The options of a session, applied to the selects it runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionOptions {
+    pub default_limit: Option<u64>,
+    pub nulls: Option<NullsOrder>,
+    pub case_sensitive: bool,
+}
+
+impl Default for SessionOptions {
+    fn default() -> Self {
+        Self {
+            default_limit: None,
+            nulls: None,
+            case_sensitive: true,
+        }
+    }
+}
+
+impl SessionOptions {
+    pub fn set(&mut self, option: &SessionOption) {
+        match option {
+            SessionOption::DefaultLimit(limit) => self.default_limit = *limit,
+            SessionOption::Nulls(nulls) => self.nulls.clone_from(nulls),
+            SessionOption::CaseSensitive(case_sensitive) => self.case_sensitive = *case_sensitive,
+        }
+    }
+
+    /// Returns `query` as it runs in the session: selects, also those behind `EXPLAIN`, get
+    /// the options their own clauses leave open. Other queries are returned unchanged.
+    #[must_use]
+    pub fn apply(&self, query: &IqlQuery) -> IqlQuery {
+        match query {
+            IqlQuery::Select(select) => {
+                let mut select = select.clone();
+                select.limit = select.limit.or(self.default_limit);
+                if let Some(order_by) = &mut select.order_by
+                    && order_by.nulls.is_none()
+                {
+                    order_by.nulls.clone_from(&self.nulls);
+                }
+                if !self.case_sensitive
+                    && let Some(filter) = &mut select.filter
+                {
+                    ignore_case(filter);
+                }
+                IqlQuery::Select(select)
+            }
+            IqlQuery::Explain(query) => IqlQuery::Explain(Box::new(self.apply(query))),
+            query => query.clone(),
+        }
+    }
+}
+
+/// Turns the `=` and `!=` comparisons of `filter` into ones that ignore case.
+fn ignore_case(filter: &mut FilterExpression) {
+    match filter {
+        FilterExpression::Comparison { op, .. } if *op == ComparisonOp::Equal => {
+            *op = ComparisonOp::EqualIgnoreCase;
+        }
+        FilterExpression::Comparison { op, .. } if *op == ComparisonOp::NotEqual => {
+            *op = ComparisonOp::NotEqualIgnoreCase;
+        }
+        FilterExpression::And(left, right) | FilterExpression::Or(left, right) => {
+            ignore_case(left);
+            ignore_case(right);
+        }
+        FilterExpression::Not(expr) => ignore_case(expr),
+        _ => {}
+    }
+}
+
+impl fmt::Display for SessionOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionOption::DefaultLimit(Some(limit)) => write!(f, "SET default_limit = {limit}"),
+            SessionOption::DefaultLimit(None) => write!(f, "SET default_limit = NULL"),
+            SessionOption::Nulls(Some(NullsOrder::First)) => write!(f, "SET nulls = FIRST"),
+            SessionOption::Nulls(Some(NullsOrder::Last)) => write!(f, "SET nulls = LAST"),
+            SessionOption::Nulls(None) => write!(f, "SET nulls = DEFAULT"),
+            SessionOption::CaseSensitive(true) => write!(f, "SET case_sensitive = TRUE"),
+            SessionOption::CaseSensitive(false) => write!(f, "SET case_sensitive = FALSE"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct UpdateStatement {
     pub entity: UpdateTarget,
@@ -353,6 +1137,24 @@ pub enum UpdateTarget {
     Project(ProjectId),
     Issue(IssueId),
     Comment(CommentId),
+    /// Every entity of a kind matching the filter.
+    Bulk {
+        entity: EntityType,
+        filter: Option<FilterExpression>,
+    },
+}
+
+impl UpdateTarget {
+    #[must_use]
+    pub fn entity_type(&self) -> EntityType {
+        match self {
+            UpdateTarget::User(_) => EntityType::Users,
+            UpdateTarget::Project(_) => EntityType::Projects,
+            UpdateTarget::Issue(_) => EntityType::Issues,
+            UpdateTarget::Comment(_) => EntityType::Comments,
+            UpdateTarget::Bulk { entity, .. } => *entity,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -387,6 +1189,24 @@ pub enum DeleteTarget {
     Project(ProjectId),
     Issue(IssueId),
     Comment(CommentId),
+    /// Every entity of a kind matching the filter. Without a filter, written `DELETE ALL`.
+    Bulk {
+        entity: EntityType,
+        filter: Option<FilterExpression>,
+    },
+}
+
+impl DeleteTarget {
+    #[must_use]
+    pub fn entity_type(&self) -> EntityType {
+        match self {
+            DeleteTarget::User(_) => EntityType::Users,
+            DeleteTarget::Project(_) => EntityType::Projects,
+            DeleteTarget::Issue(_) => EntityType::Issues,
+            DeleteTarget::Comment(_) => EntityType::Comments,
+            DeleteTarget::Bulk { entity, .. } => *entity,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Facet, PartialEq)]
@@ -398,6 +1218,17 @@ pub enum IssueKind {
     Task,
 }
 
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IssueKind::Epic => write!(f, "EPIC"),
+            IssueKind::Improvement => write!(f, "IMPROVEMENT"),
+            IssueKind::Bug => write!(f, "BUG"),
+            IssueKind::Task => write!(f, "TASK"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct AssignStatement {
     pub issue_id: IssueId,
@@ -409,35 +1240,501 @@ pub struct AssignStatement {
 pub enum CloseReason {
     #[default]
     Done,
-    Duplicate,
-    WontFix,
+    Duplicate {
+        of: Option<IssueId>,
+    },
+    WontFix {
+        reason: Option<String>,
+    },
 }
 
 impl fmt::Display for CloseReason {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             CloseReason::Done => write!(f, "DONE"),
-            CloseReason::Duplicate => write!(f, "DUPLICATE"),
-            CloseReason::WontFix => write!(f, "WONTFIX"),
+            CloseReason::Duplicate { of: None } => write!(f, "DUPLICATE"),
+            CloseReason::Duplicate { of: Some(of) } => write!(f, "DUPLICATE OF {}", &**of),
+            CloseReason::WontFix { reason: None } => write!(f, "WONTFIX"),
+            CloseReason::WontFix {
+                reason: Some(reason),
+            } => write!(f, "WONTFIX {}", quote(reason)),
         }
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct CloseStatement {
-    pub issue_id: IssueId,
+    pub target: CloseTarget,
     pub reason: Option<CloseReason>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum CloseTarget {
+    Issue(IssueId),
+    /// Every issue matching the filter, written `CLOSE ISSUES WHERE ...`
+    Bulk {
+        filter: FilterExpression,
+    },
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ReopenStatement {
     pub issue_id: IssueId,
+    /// Added as a comment on the issue when it is reopened, e.g. why it is not done after all.
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveStatement {
+    pub issue_id: IssueId,
+    pub project: ProjectId,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenameStatement {
+    pub project: ProjectId,
+    pub new_id: ProjectId,
+}
+
+/// Hides an issue or project from selects without deleting it, or shows it again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveStatement {
+    pub target: ArchiveTarget,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArchiveTarget {
+    Issue(IssueId),
+    Project(ProjectId),
+}
+
+impl ArchiveTarget {
+    #[must_use]
+    pub fn entity_type(&self) -> EntityType {
+        match self {
+            ArchiveTarget::Issue(_) => EntityType::Issues,
+            ArchiveTarget::Project(_) => EntityType::Projects,
+        }
+    }
+}
+
+/// `IMPORT '<dump>' [MERGE]`. Without `MERGE`, importing an entity that already exists fails;
+/// with it, the dumped entity replaces the stored one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportStatement {
+    pub dump: String,
+    pub merge: bool,
+}
+
+impl fmt::Display for ImportStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IMPORT {}", quote(&self.dump))?;
+        if self.merge {
+            write!(f, " MERGE")?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct CommentStatement {
     pub issue_id: IssueId,
     pub content: String,
+    pub author: Option<UserId>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReactionStatement {
+    pub comment_id: CommentId,
+    pub reaction: String,
+}
+
+/// Quotes `value` as a string literal that parses back to the same string.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+impl fmt::Display for ComparisonOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComparisonOp::Equal => write!(f, "="),
+            ComparisonOp::NotEqual => write!(f, "!="),
+            ComparisonOp::EqualIgnoreCase => write!(f, "=~"),
+            ComparisonOp::NotEqualIgnoreCase => write!(f, "!~"),
+            ComparisonOp::GreaterThan => write!(f, ">"),
+            ComparisonOp::LessThan => write!(f, "<"),
+            ComparisonOp::GreaterThanOrEqual => write!(f, ">="),
+            ComparisonOp::LessThanOrEqual => write!(f, "<="),
+            ComparisonOp::Like => write!(f, "LIKE"),
+        }
+    }
+}
+
+impl fmt::Display for FilterExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // AND binds tighter than OR and NOT applies to a single operand
+        let grouped = |expr: &FilterExpression, group_or: bool| match expr {
+            FilterExpression::Or(..) if group_or => format!("({expr})"),
+            FilterExpression::And(..) | FilterExpression::Or(..) if !group_or => {
+                format!("({expr})")
+            }
+            _ => expr.to_string(),
+        };
+        match self {
+            FilterExpression::Comparison { field, op, value } => {
+                write!(f, "{field} {op} {value}")
+            }
+            FilterExpression::And(left, right) => {
+                write!(f, "{} AND {}", grouped(left, true), grouped(right, true))
+            }
+            FilterExpression::Or(left, right) => write!(f, "{left} OR {right}"),
+            FilterExpression::Not(expr) => write!(f, "NOT {}", grouped(expr, false)),
+            FilterExpression::In { field, values } => {
+                let values = values.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "{field} IN ({})", values.join(", "))
+            }
+            FilterExpression::IsNull(field) => write!(f, "{field} IS NULL"),
+            FilterExpression::IsNotNull(field) => write!(f, "{field} IS NOT NULL"),
+            FilterExpression::Has { field, value } => write!(f, "{field} HAS {value}"),
+        }
+    }
+}
+
+impl fmt::Display for CreateStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CreateStatement::User {
+                username,
+                email,
+                name,
+            } => {
+                write!(f, "CREATE USER {username}")?;
+                if email.is_some() || name.is_some() {
+                    write!(f, " WITH")?;
+                }
+                if let Some(email) = email {
+                    write!(f, " EMAIL {}", quote(email))?;
+                }
+                if let Some(name) = name {
+                    write!(f, " NAME {}", quote(name))?;
+                }
+                Ok(())
+            }
+            CreateStatement::Project {
+                project_id,
+                name,
+                description,
+                owner,
+                on_conflict,
+            } => {
+                write!(f, "CREATE PROJECT {project_id}")?;
+                if name.is_some() || description.is_some() || owner.is_some() {
+                    write!(f, " WITH")?;
+                }
+                if let Some(name) = name {
+                    write!(f, " NAME {}", quote(name))?;
+                }
+                if let Some(description) = description {
+                    write!(f, " DESCRIPTION {}", quote(description))?;
+                }
+                if let Some(owner) = owner {
+                    write!(f, " OWNER {owner}")?;
+                }
+                match on_conflict {
+                    None => {}
+                    Some(OnConflict::DoNothing) => write!(f, " ON CONFLICT DO NOTHING")?,
+                    Some(OnConflict::Update(updates)) => {
+                        let updates = updates
+                            .iter()
+                            .map(|update| format!("{} = {}", update.field, update.value))
+                            .collect::<Vec<_>>();
+                        write!(f, " ON CONFLICT UPDATE SET {}", updates.join(", "))?;
+                    }
+                }
+                Ok(())
+            }
+            CreateStatement::Issue {
+                project,
+                title,
+                kind,
+                description,
+                priority,
+                assignee,
+                labels,
+            } => {
+                write!(
+                    f,
+                    "CREATE ISSUE OF KIND {kind} IN {project} WITH TITLE {}",
+                    quote(title)
+                )?;
+                if let Some(description) = description {
+                    write!(f, " DESCRIPTION {}", quote(description))?;
+                }
+                if let Some(priority) = priority {
+                    write!(f, " PRIORITY {priority}")?;
+                }
+                if let Some(assignee) = assignee {
+                    write!(f, " ASSIGNEE {assignee}")?;
+                }
+                if !labels.is_empty() {
+                    let labels = labels.iter().map(|label| quote(label)).collect::<Vec<_>>();
+                    write!(f, " LABELS ({})", labels.join(", "))?;
+                }
+                Ok(())
+            }
+            CreateStatement::Issues {
+                project,
+                kind,
+                titles,
+            } => {
+                let titles = titles.iter().map(|title| quote(title)).collect::<Vec<_>>();
+                write!(
+                    f,
+                    "CREATE ISSUES OF KIND {kind} IN {project} WITH TITLES [{}]",
+                    titles.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for UpdateStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.entity {
+            UpdateTarget::User(id) => write!(f, "UPDATE USER {id}")?,
+            UpdateTarget::Project(id) => write!(f, "UPDATE PROJECT {id}")?,
+            UpdateTarget::Issue(id) => write!(f, "UPDATE ISSUE {}", &**id)?,
+            UpdateTarget::Comment(id) => write!(f, "UPDATE COMMENT {}", &**id)?,
+            UpdateTarget::Bulk { entity, .. } => write!(f, "UPDATE {entity}")?,
+        }
+        let updates = self
+            .updates
+            .iter()
+            .map(|update| format!("{} = {}", update.field, update.value))
+            .collect::<Vec<_>>();
+        write!(f, " SET {}", updates.join(", "))?;
+        if let UpdateTarget::Bulk {
+            filter: Some(filter),
+            ..
+        } = &self.entity
+        {
+            write!(f, " WHERE {filter}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DeleteStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.entity {
+            DeleteTarget::User(id) => write!(f, "DELETE USER {id}"),
+            DeleteTarget::Project(id) => write!(f, "DELETE PROJECT {id}"),
+            DeleteTarget::Issue(id) => write!(f, "DELETE ISSUE {}", &**id),
+            DeleteTarget::Comment(id) => write!(f, "DELETE COMMENT {}", &**id),
+            DeleteTarget::Bulk {
+                entity,
+                filter: None,
+            } => write!(f, "DELETE ALL {entity}"),
+            DeleteTarget::Bulk {
+                entity,
+                filter: Some(filter),
+            } => write!(f, "DELETE {entity} WHERE {filter}"),
+        }
+    }
+}
+
+impl fmt::Display for AssignStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ASSIGN ISSUE {} TO {}", &*self.issue_id, self.assignee)
+    }
+}
+
+impl fmt::Display for CloseStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.target {
+            CloseTarget::Issue(id) => write!(f, "CLOSE ISSUE {}", &**id)?,
+            CloseTarget::Bulk { filter } => write!(f, "CLOSE ISSUES WHERE {filter}")?,
+        }
+        if let Some(reason) = &self.reason {
+            write!(f, " WITH {reason}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ReopenStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "REOPEN ISSUE {}", &*self.issue_id)?;
+        if let Some(comment) = &self.comment {
+            write!(f, " WITH {}", quote(comment))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for MoveStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MOVE ISSUE {} TO {}", &*self.issue_id, &*self.project)
+    }
+}
+
+impl fmt::Display for RenameStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RENAME PROJECT {} TO {}", &*self.project, &*self.new_id)
+    }
+}
+
+impl fmt::Display for ArchiveTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveTarget::Issue(id) => write!(f, "ISSUE {}", &**id),
+            ArchiveTarget::Project(id) => write!(f, "PROJECT {}", &**id),
+        }
+    }
+}
+
+impl fmt::Display for CommentStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "COMMENT ON ISSUE {} WITH {}",
+            &*self.issue_id,
+            quote(&self.content)
+        )?;
+        if let Some(author) = &self.author {
+            write!(f, " AUTHOR {author}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SelectStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SELECT ")?;
+        match &self.columns {
+            Columns::All => write!(f, "*")?,
+            Columns::Named(columns) => {
+                for (index, (column, alias)) in columns.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{column}")?;
+                    if let Some(alias) = alias {
+                        write!(f, " AS {alias}")?;
+                    }
+                }
+            }
+        }
+        write!(f, " FROM {}", self.from)?;
+        if let Some(alias) = &self.from_alias {
+            write!(f, " AS {alias}")?;
+        }
+        if self.include_archived {
+            write!(f, " INCLUDING ARCHIVED")?;
+        }
+        if let Some(join) = &self.join {
+            write!(f, " JOIN {}", join.entity)?;
+            if let Some(alias) = &join.alias {
+                write!(f, " AS {alias}")?;
+            }
+            write!(
+                f,
+                " ON {} = {}.{}",
+                join.left_field,
+                join.entity.to_string().to_lowercase(),
+                join.right_field
+            )?;
+        }
+        if let Some(filter) = &self.filter {
+            // Field references of the selected entity lose their qualifier while parsing, and
+            // would read back as plain identifiers without it
+            let mut filter = filter.clone();
+            qualify_field_values(&mut filter, &self.from.to_string().to_lowercase());
+            write!(f, " WHERE {filter}")?;
+        }
+        if let Some(group_by) = &self.group_by {
+            write!(f, " GROUP BY {group_by}")?;
+        }
+        if let Some(order_by) = &self.order_by {
+            // The direction is always spelled out so the default of the parser does not matter
+            let direction = match order_by.direction {
+                OrderDirection::Asc => "ASC",
+                OrderDirection::Desc => "DESC",
+            };
+            write!(f, " ORDER BY {} {direction}", order_by.field)?;
+            match order_by.nulls {
+                Some(NullsOrder::First) => write!(f, " NULLS FIRST")?,
+                Some(NullsOrder::Last) => write!(f, " NULLS LAST")?,
+                None => {}
+            }
+        }
+        if let Some(after) = &self.after {
+            write!(f, " AFTER {}", quote(after))?;
+        }
+        if let Some(limit) = self.limit {
+            write!(f, " LIMIT {limit}")?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " OFFSET {offset}")?;
+        }
+        Ok(())
+    }
+}
+
+fn qualify_field_values(filter: &mut FilterExpression, entity: &str) {
+    match filter {
+        FilterExpression::Comparison {
+            value: IqlValue::Field(name),
+            ..
+        } if !name.contains('.') => *name = format!("{entity}.{name}"),
+        FilterExpression::And(left, right) | FilterExpression::Or(left, right) => {
+            qualify_field_values(left, entity);
+            qualify_field_values(right, entity);
+        }
+        FilterExpression::Not(expr) => qualify_field_values(expr, entity),
+        _ => {}
+    }
+}
+
+/// Renders the query as IQL that parses back to an equal query.
+impl fmt::Display for IqlQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IqlQuery::Create(statement) => write!(f, "{statement}"),
+            IqlQuery::Select(statement) => write!(f, "{statement}"),
+            IqlQuery::Update(statement) => write!(f, "{statement}"),
+            IqlQuery::Delete(statement) => write!(f, "{statement}"),
+            IqlQuery::Assign(statement) => write!(f, "{statement}"),
+            IqlQuery::Close(statement) => write!(f, "{statement}"),
+            IqlQuery::Reopen(statement) => write!(f, "{statement}"),
+            IqlQuery::Move(statement) => write!(f, "{statement}"),
+            IqlQuery::Rename(statement) => write!(f, "{statement}"),
+            IqlQuery::Archive(statement) => write!(f, "ARCHIVE {}", statement.target),
+            IqlQuery::Unarchive(statement) => write!(f, "UNARCHIVE {}", statement.target),
+            IqlQuery::Comment(statement) => write!(f, "{statement}"),
+            IqlQuery::React(reaction) => write!(
+                f,
+                "REACT TO COMMENT {} WITH {}",
+                &*reaction.comment_id,
+                quote(&reaction.reaction)
+            ),
+            IqlQuery::Unreact(reaction) => write!(
+                f,
+                "UNREACT TO COMMENT {} WITH {}",
+                &*reaction.comment_id,
+                quote(&reaction.reaction)
+            ),
+            IqlQuery::Reindex => write!(f, "REINDEX"),
+            IqlQuery::Import(statement) => write!(f, "{statement}"),
+            IqlQuery::History(issue_id) => write!(f, "HISTORY ISSUE {}", &**issue_id),
+            IqlQuery::Describe(entity) => write!(f, "DESCRIBE {entity}"),
+            IqlQuery::Begin => write!(f, "BEGIN"),
+            IqlQuery::Commit => write!(f, "COMMIT"),
+            IqlQuery::Rollback => write!(f, "ROLLBACK"),
+            IqlQuery::Explain(query) => write!(f, "EXPLAIN {query}"),
+            IqlQuery::Set(option) => write!(f, "{option}"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -467,7 +1764,15 @@ pub enum IqlValue {
     Boolean(bool),
     Null,
     Priority(Priority),
+    Kind(IssueKind),
     Identifier(String),
+    /// A calendar date written `DATE 'YYYY-MM-DD'`
+    Date(String),
+    /// A point in time, any string in RFC 3339 format like `'2024-01-15T10:30:00Z'`
+    DateTime(String),
+    /// Another field of the same row, written qualified like `issues.updated_at`, or bare once
+    /// [`resolve_fields`](crate::resolve_fields) knows the name
+    Field(String),
 }
 
 impl IqlValue {
@@ -486,22 +1791,120 @@ impl IqlValue {
                 }
             }
             IqlValue::Null => facet_value::Value::NULL,
-            IqlValue::Priority(p) => facet_value::VString::new(&p.to_string()).into_value(),
+            // Stored priorities use the variant names of the entity's `Priority`
+            IqlValue::Priority(p) => facet_value::VString::new(match p {
+                Priority::Critical => "Critical",
+                Priority::High => "High",
+                Priority::Medium => "Medium",
+                Priority::Low => "Low",
+            })
+            .into_value(),
+            // Stored kinds use the variant names of `IssueKind`
+            IqlValue::Kind(kind) => facet_value::VString::new(match kind {
+                IssueKind::Epic => "Epic",
+                IssueKind::Improvement => "Improvement",
+                IssueKind::Bug => "Bug",
+                IssueKind::Task => "Task",
+            })
+            .into_value(),
             IqlValue::Identifier(id) => facet_value::VString::new(id).into_value(),
+            // Timestamps are stored as RFC 3339 strings, which order correctly against a bare date
+            IqlValue::Date(date) | IqlValue::DateTime(date) => {
+                facet_value::VString::new(date).into_value()
+            }
+            IqlValue::Field(name) => facet_value::VString::new(name).into_value(),
         }
     }
 }
 
+/// Whether `value` is a timestamp in RFC 3339 format.
+#[must_use]
+pub fn is_timestamp(value: &str) -> bool {
+    OffsetDateTime::parse(value, &Rfc3339).is_ok()
+}
+
+/// Orders two RFC 3339 timestamps in time, whatever their precision and offset. `None` unless
+/// both are timestamps.
+#[must_use]
+pub fn compare_timestamps(a: &str, b: &str) -> Option<std::cmp::Ordering> {
+    normalize_timestamp(a)?.partial_cmp(&normalize_timestamp(b)?)
+}
+
+/// Rewrites an RFC 3339 timestamp in UTC with nanosecond precision, so that timestamps with
+/// fractional seconds or other offsets compare chronologically as strings.
+fn normalize_timestamp(value: &str) -> Option<FacetValue> {
+    let timestamp = OffsetDateTime::parse(value, &Rfc3339)
+        .ok()?
+        .to_offset(UtcOffset::UTC);
+    let normalized = format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}Z",
+        timestamp.year(),
+        u8::from(timestamp.month()),
+        timestamp.day(),
+        timestamp.hour(),
+        timestamp.minute(),
+        timestamp.second(),
+        timestamp.nanosecond()
+    );
+    Some(facet_value::VString::new(&normalized).into_value())
+}
+
 impl fmt::Display for IqlValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            IqlValue::String(s) => write!(f, "'{s}'"),
+            IqlValue::String(s) => write!(f, "{}", quote(s)),
             IqlValue::UnsignedInteger(n) => write!(f, "{n}"),
             IqlValue::Float(fl) => write!(f, "{fl}"),
             IqlValue::Boolean(b) => write!(f, "{b}"),
             IqlValue::Null => write!(f, "NULL"),
             IqlValue::Priority(p) => write!(f, "{p}"),
+            IqlValue::Kind(kind) => write!(f, "{kind}"),
             IqlValue::Identifier(id) => write!(f, "{id}"),
+            IqlValue::Date(date) => write!(f, "DATE '{date}'"),
+            IqlValue::DateTime(timestamp) => write!(f, "{}", quote(timestamp)),
+            IqlValue::Field(name) => write!(f, "{name}"),
         }
     }
 }
+
+impl From<&str> for IqlValue {
+    fn from(value: &str) -> Self {
+        IqlValue::String(value.to_string())
+    }
+}
+
+impl From<String> for IqlValue {
+    fn from(value: String) -> Self {
+        IqlValue::String(value)
+    }
+}
+
+impl From<u64> for IqlValue {
+    fn from(value: u64) -> Self {
+        IqlValue::UnsignedInteger(value)
+    }
+}
+
+impl From<f64> for IqlValue {
+    fn from(value: f64) -> Self {
+        IqlValue::Float(value)
+    }
+}
+
+impl From<bool> for IqlValue {
+    fn from(value: bool) -> Self {
+        IqlValue::Boolean(value)
+    }
+}
+
+impl From<Priority> for IqlValue {
+    fn from(value: Priority) -> Self {
+        IqlValue::Priority(value)
+    }
+}
+
+impl From<IssueKind> for IqlValue {
+    fn from(value: IssueKind) -> Self {
+        IqlValue::Kind(value)
+    }
+}
@@ -1,5 +1,9 @@
+use crate::EntityType;
+
 pub type ParseResult<T> = Result<T, ParseError>;
 
+/// Why a query could not be parsed. Positions are character offsets into the query, starting
+/// at 0, and `found` is the offending text as it was written.
 #[derive(Debug, Clone, PartialEq, thiserror::Error)]
 pub enum ParseError {
     #[error("Unexpected end of input")]
@@ -21,6 +25,9 @@ pub enum ParseError {
     #[error("Invalid identifier '{value}' at position {position}")]
     InvalidIdentifier { value: String, position: usize },
 
+    #[error("Could not read the query: {message} at position {position}")]
+    LexError { message: String, position: usize },
+
     #[error("Unterminated string literal at position {position}")]
     UnterminatedString { position: usize },
 
@@ -33,15 +40,32 @@ pub enum ParseError {
     #[error("Invalid priority '{value}' at position {position}")]
     InvalidPriority { value: String, position: usize },
 
+    #[error("Invalid date '{value}' at position {position}")]
+    InvalidDate { value: String, position: usize },
+
     #[error("Invalid issue kind '{value}' at position {position}")]
     InvalidIssueKind { value: String, position: usize },
 
     #[error("Missing clause '{clause}' at position {position}")]
     MissingClause { clause: String, position: usize },
 
+    #[error("Duplicate clause '{clause}' at position {position}")]
+    DuplicateClause { clause: String, position: usize },
+
     #[error("Invalid issue ID '{value}' at position {position}")]
     InvalidIssueId { value: String, position: usize },
 
     #[error("General Error: {0}")]
     General(String),
 }
+
+/// A problem with a query that parses but cannot run against the schema.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ValidationError {
+    #[error("Unknown field '{field}' of {entity} in {clause}")]
+    UnknownField {
+        entity: EntityType,
+        field: String,
+        clause: String,
+    },
+}
@@ -1,5 +1,9 @@
+use std::ops::Range;
+
 use logos::Logos;
 
+use crate::error::{ParseError, ParseResult};
+
 #[derive(Logos, Debug, Clone, PartialEq)]
 #[logos(skip r"[ \t\n\f\r]+")] // Skip whitespace
 #[logos(error = String)]
@@ -26,9 +30,54 @@ pub enum Token {
     #[regex("(?i)reopen")]
     Reopen,
 
+    #[regex("(?i)move")]
+    Move,
+
+    #[regex("(?i)rename")]
+    Rename,
+
+    #[regex("(?i)archive")]
+    Archive,
+
+    #[regex("(?i)unarchive")]
+    Unarchive,
+
     #[regex("(?i)comment")]
     Comment,
 
+    #[regex("(?i)react")]
+    React,
+
+    #[regex("(?i)unreact")]
+    Unreact,
+
+    #[regex("(?i)reindex")]
+    Reindex,
+
+    #[regex("(?i)import")]
+    Import,
+
+    #[regex("(?i)history")]
+    History,
+
+    #[regex("(?i)describe")]
+    Describe,
+
+    #[regex("(?i)begin")]
+    Begin,
+
+    #[regex("(?i)commit")]
+    Commit,
+
+    #[regex("(?i)rollback")]
+    Rollback,
+
+    #[regex("(?i)explain")]
+    Explain,
+
+    #[regex("(?i)all")]
+    All,
+
     #[regex("(?i)from")]
     From,
 
@@ -71,6 +120,12 @@ pub enum Token {
     #[regex("(?i)order")]
     Order,
 
+    #[regex("(?i)group")]
+    Group,
+
+    #[regex("(?i)join")]
+    Join,
+
     #[regex("(?i)by")]
     By,
 
@@ -80,6 +135,9 @@ pub enum Token {
     #[regex("(?i)offset")]
     Offset,
 
+    #[regex("(?i)as")]
+    As,
+
     #[regex("(?i)asc")]
     Asc,
 
@@ -89,6 +147,9 @@ pub enum Token {
     #[regex("(?i)like")]
     Like,
 
+    #[regex("(?i)has")]
+    Has,
+
     // ========== Entity Types ==========
     #[regex("(?i)user")]
     User,
@@ -111,6 +172,9 @@ pub enum Token {
     #[regex("(?i)comments")]
     Comments,
 
+    #[regex("(?i)links")]
+    Links,
+
     // ========== Field Names (used in WITH clauses) ==========
     #[regex("(?i)email")]
     Email,
@@ -175,6 +239,7 @@ pub enum Token {
     // ========== Literals ==========
     #[regex(r#"'([^'\\]|\\.)*'"#, parse_single_quoted_string)]
     #[regex(r#""([^"\\]|\\.)*""#, parse_double_quoted_string)]
+    #[token("'''", parse_raw_string)]
     String(String),
 
     #[regex(r"[0-9]+", |lex| lex.slice().parse::<u64>().ok())]
@@ -199,18 +264,30 @@ pub enum Token {
     #[token(",")]
     Comma,
 
+    #[token(";")]
+    Semicolon,
+
     #[token(".")]
     Dot,
 
     #[token("#")]
     Hash,
 
+    #[token("/")]
+    Slash,
+
     #[token("=")]
     Equal,
 
     #[token("!=")]
     NotEqual,
 
+    #[token("=~")]
+    EqualIgnoreCase,
+
+    #[token("!~")]
+    NotEqualIgnoreCase,
+
     #[token(">")]
     GreaterThan,
 
@@ -251,6 +328,17 @@ fn parse_double_quoted_string(lex: &mut logos::Lexer<Token>) -> String {
     unescape_string(content)
 }
 
+fn parse_raw_string(lex: &mut logos::Lexer<Token>) -> Result<String, String> {
+    let remainder = lex.remainder();
+    match remainder.find("'''") {
+        Some(end) => {
+            lex.bump(end + 3);
+            Ok(remainder[..end].to_string())
+        }
+        None => Err("Unterminated raw string".to_string()),
+    }
+}
+
 fn unescape_string(s: &str) -> String {
     let mut result = String::with_capacity(s.len());
     let mut chars = s.chars();
@@ -279,21 +367,59 @@ fn unescape_string(s: &str) -> String {
     result
 }
 
-pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+#[cfg(test)]
+pub fn tokenize(input: &str) -> ParseResult<Vec<Token>> {
+    Ok(tokenize_spanned(input)?
+        .into_iter()
+        .map(|(token, _)| token)
+        .collect())
+}
+
+/// Tokenizes `input` like [`tokenize`], pairing each token with the bytes of `input` it was
+/// read from. The closing [`Token::Eof`] gets the empty range at the end of the input.
+pub fn tokenize_spanned(input: &str) -> ParseResult<Vec<(Token, Range<usize>)>> {
     let mut tokens = Vec::new();
-    let lexer = Token::lexer(input);
+    let mut lexer = Token::lexer(input);
 
-    for result in lexer {
+    while let Some(result) = lexer.next() {
         match result {
-            Ok(token) => tokens.push(token),
-            Err(err) => return Err(err),
+            Ok(token) => tokens.push((token, lexer.span())),
+            Err(message) => {
+                let position = char_offset(input, lexer.span().start);
+                let slice = lexer.slice();
+                return Err(if slice.starts_with(['\'', '"']) {
+                    ParseError::UnterminatedString { position }
+                } else if message.is_empty() {
+                    ParseError::LexError {
+                        message: format!("unexpected '{slice}'"),
+                        position,
+                    }
+                } else {
+                    ParseError::LexError { message, position }
+                });
+            }
         }
     }
 
-    tokens.push(Token::Eof);
+    tokens.push((Token::Eof, input.len()..input.len()));
     Ok(tokens)
 }
 
+/// The number of characters in `input` before the byte at `byte_offset`, which is where errors
+/// report their position.
+pub fn char_offset(input: &str, byte_offset: usize) -> usize {
+    input[..byte_offset].chars().count()
+}
+
+/// Tokenizes the start of `input`, stopping before the first token that can't be lexed or once
+/// `limit` tokens were read.
+pub fn leading_tokens(input: &str, limit: usize) -> Vec<Token> {
+    Token::lexer(input)
+        .map_while(Result::ok)
+        .take(limit)
+        .collect()
+}
+
 impl Token {
     #[cfg(test)]
     pub fn is_keyword(&self) -> bool {
@@ -306,7 +432,22 @@ impl Token {
                 | Token::Assign
                 | Token::Close
                 | Token::Reopen
+                | Token::Move
+                | Token::Rename
+                | Token::Archive
+                | Token::Unarchive
                 | Token::Comment
+                | Token::React
+                | Token::Unreact
+                | Token::Reindex
+                | Token::Import
+                | Token::History
+                | Token::Describe
+                | Token::Begin
+                | Token::Commit
+                | Token::Rollback
+                | Token::Explain
+                | Token::All
                 | Token::From
                 | Token::Where
                 | Token::And
@@ -320,12 +461,16 @@ impl Token {
                 | Token::On
                 | Token::With
                 | Token::Order
+                | Token::Group
+                | Token::Join
                 | Token::By
                 | Token::Limit
                 | Token::Offset
+                | Token::As
                 | Token::Asc
                 | Token::Desc
                 | Token::Like
+                | Token::Has
                 | Token::User
                 | Token::Project
                 | Token::Issue
@@ -333,6 +478,7 @@ impl Token {
                 | Token::Users
                 | Token::Projects
                 | Token::Comments
+                | Token::Links
                 | Token::Email
                 | Token::Name
                 | Token::Title
@@ -362,6 +508,7 @@ impl Token {
             Token::Title => Some("title".to_string()),
             Token::Description => Some("description".to_string()),
             Token::Priority => Some("priority".to_string()),
+            Token::Kind => Some("kind".to_string()),
             Token::Assignee => Some("assignee".to_string()),
             Token::Owner => Some("owner".to_string()),
             Token::User => Some("user".to_string()),
@@ -401,6 +548,53 @@ mod tests {
         insta::assert_debug_snapshot!(&tokens);
     }
 
+    #[test]
+    fn test_tokenize_multiline_string() {
+        let tokens = tokenize("'first line\nsecond line'\n\"third\n  fourth\"").unwrap();
+        insta::assert_debug_snapshot!(&tokens);
+    }
+
+    #[test]
+    fn test_tokenize_raw_string() {
+        let tokens = tokenize(r#"'''match "\n" or 'C:\path\' verbatim''' 'after'"#).unwrap();
+        insta::assert_debug_snapshot!(&tokens);
+    }
+
+    #[test]
+    fn test_tokenize_unterminated_raw_string() {
+        assert_eq!(
+            tokenize("'''never closed"),
+            Err(ParseError::UnterminatedString { position: 0 })
+        );
+    }
+
+    #[test]
+    fn test_tokenize_illegal_character() {
+        assert_eq!(
+            tokenize("SELECT * FROM issues WHERE assignee = @alice"),
+            Err(ParseError::LexError {
+                message: "unexpected '@'".to_string(),
+                position: 38,
+            })
+        );
+    }
+
+    #[test]
+    fn test_tokenize_unterminated_strings() {
+        assert_eq!(
+            tokenize("title = 'unterminated"),
+            Err(ParseError::UnterminatedString { position: 8 })
+        );
+        assert_eq!(
+            tokenize(r#"title = "unterminated"#),
+            Err(ParseError::UnterminatedString { position: 8 })
+        );
+        assert_eq!(
+            tokenize(r"title = 'escaped quote\'"),
+            Err(ParseError::UnterminatedString { position: 8 })
+        );
+    }
+
     #[test]
     fn test_tokenize_escaped_string() {
         let tokens = tokenize(r"'hello\nworld\t!'");
@@ -427,7 +621,7 @@ mod tests {
 
     #[test]
     fn test_tokenize_operators() {
-        let tokens = tokenize("= != > < >= <=").unwrap();
+        let tokens = tokenize("= != =~ > < >= <=").unwrap();
         insta::assert_debug_snapshot!(&tokens);
     }
 
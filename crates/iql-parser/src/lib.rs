@@ -2,16 +2,79 @@ mod ast;
 mod error;
 mod lexer;
 mod parser;
+mod validate;
 
 pub use ast::*;
-pub use error::{ParseError, ParseResult};
+pub use error::{ParseError, ParseResult, ValidationError};
 use parser::Parser;
+pub use validate::{Schema, resolve_fields, validate};
+
+/// Default upper bound for the length of a query in bytes.
+pub const DEFAULT_MAX_QUERY_LENGTH: usize = 64 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct ParserConfig {
+    /// Queries longer than this many bytes are rejected before tokenizing.
+    pub max_query_length: usize,
+    /// Direction used by `ORDER BY` when neither `ASC` nor `DESC` is given.
+    pub default_order_direction: OrderDirection,
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self {
+            max_query_length: DEFAULT_MAX_QUERY_LENGTH,
+            default_order_direction: OrderDirection::Asc,
+        }
+    }
+}
 
 pub fn parse_query(query: &str) -> ParseResult<IqlQuery> {
-    let mut parser = Parser::new(query);
+    parse_query_with_config(query, &ParserConfig::default())
+}
+
+pub fn parse_query_with_config(query: &str, config: &ParserConfig) -> ParseResult<IqlQuery> {
+    check_query_length(query, config)?;
+    let mut parser = Parser::with_config(query, config)?;
     parser.parse()
 }
 
+/// Parses a statement that may still be incomplete, like one being typed in an editor.
+///
+/// Next to the statement or the error it fails with, this reports the kind of statement from its
+/// leading keywords, which is known even when the rest doesn't parse yet.
+pub fn parse_partial(query: &str) -> (Option<IqlQuery>, Option<ParseError>, StatementKind) {
+    let kind = parser::statement_kind(query);
+    match parse_query(query) {
+        Ok(statement) => (Some(statement), None, kind),
+        Err(error) => (None, Some(error), kind),
+    }
+}
+
+/// Parses a script of statements separated by semicolons.
+pub fn parse_queries(queries: &str) -> ParseResult<Vec<IqlQuery>> {
+    parse_queries_with_config(queries, &ParserConfig::default())
+}
+
+pub fn parse_queries_with_config(
+    queries: &str,
+    config: &ParserConfig,
+) -> ParseResult<Vec<IqlQuery>> {
+    check_query_length(queries, config)?;
+    let mut parser = Parser::with_config(queries, config)?;
+    parser.parse_all()
+}
+
+fn check_query_length(query: &str, config: &ParserConfig) -> ParseResult<()> {
+    if query.len() > config.max_query_length {
+        return Err(ParseError::InvalidSyntax {
+            message: "query too long".to_string(),
+            position: config.max_query_length,
+        });
+    }
+    Ok(())
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum IqlError {
     #[error("IQL query could not be parsed: {0}")]
@@ -47,6 +110,101 @@ mod tests {
         insta::assert_debug_snapshot!(&result);
     }
 
+    #[test]
+    fn test_parse_create_issue_with_labels() {
+        let query = "CREATE ISSUE OF KIND bug IN my-project WITH TITLE 'Bug found' DESCRIPTION 'Something broke' PRIORITY high ASSIGNEE john_doe LABELS ('ui', 'regression')";
+        let result = parse_query(query).unwrap();
+        insta::assert_debug_snapshot!(&result);
+
+        let query = "CREATE ISSUE OF KIND bug IN test WITH TITLE 'T' LABELS ()";
+        match parse_query(query).unwrap() {
+            IqlQuery::Create(CreateStatement::Issue { labels, .. }) => assert!(labels.is_empty()),
+            _ => panic!("Expected a CREATE ISSUE"),
+        }
+    }
+
+    #[test]
+    fn test_parse_create_issues() {
+        assert_eq!(
+            parse_query("CREATE ISSUES IN my-project WITH TITLES ['a', 'b', 'c']").unwrap(),
+            IqlQuery::Create(CreateStatement::Issues {
+                project: ProjectId::new("my-project"),
+                kind: IssueKind::Task,
+                titles: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            })
+        );
+        match parse_query("CREATE ISSUES OF KIND bug IN test WITH TITLES ['Crash']").unwrap() {
+            IqlQuery::Create(CreateStatement::Issues { kind, .. }) => {
+                assert_eq!(kind, IssueKind::Bug);
+            }
+            _ => panic!("Expected a CREATE ISSUES"),
+        }
+        assert!(parse_query("CREATE ISSUES IN test WITH TITLES []").is_err());
+        assert!(parse_query("CREATE ISSUES IN test WITH TITLES ('a')").is_err());
+        assert!(parse_query("CREATE ISSUES IN test WITH TITLE 'a'").is_err());
+    }
+
+    #[test]
+    fn test_parse_comment_author() {
+        let author = |query: &str| match parse_query(query).unwrap() {
+            IqlQuery::Comment(CommentStatement { author, .. }) => author,
+            _ => panic!("Expected a COMMENT"),
+        };
+
+        assert_eq!(author("COMMENT ON ISSUE test#1 WITH 'Hello'"), None);
+        assert_eq!(
+            author("COMMENT ON ISSUE test#1 WITH 'Hello' AUTHOR alice"),
+            Some(UserId::new("alice"))
+        );
+        assert!(parse_query("COMMENT ON ISSUE test#1 WITH 'Hello' AUTHOR").is_err());
+    }
+
+    #[test]
+    fn test_parse_numbered_comment_ids() {
+        assert_eq!(
+            parse_query("DELETE COMMENT backend#1/c3").unwrap(),
+            IqlQuery::Delete(DeleteStatement {
+                entity: DeleteTarget::Comment(CommentId::new("backend#1/c3")),
+            })
+        );
+        assert_eq!(
+            parse_query("REACT TO COMMENT backend#1/c3 WITH '+1'").unwrap(),
+            IqlQuery::React(ReactionStatement {
+                comment_id: CommentId::new("backend#1/c3"),
+                reaction: "+1".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_query("DELETE COMMENT C123").unwrap(),
+            IqlQuery::Delete(DeleteStatement {
+                entity: DeleteTarget::Comment(CommentId::new("C123")),
+            })
+        );
+        assert!(parse_query("DELETE COMMENT backend#1").is_err());
+    }
+
+    #[test]
+    fn test_create_comment_variations() {
+        let expected = IqlQuery::Comment(CommentStatement {
+            issue_id: IssueId::new("backend#1"),
+            content: "Working on it".to_string(),
+            author: None,
+        });
+        assert_eq!(
+            parse_query("COMMENT ON ISSUE backend#1 WITH 'Working on it'").unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_query("CREATE COMMENT ON ISSUE backend#1 WITH 'Working on it'").unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_query("create comment on issue backend#1 with 'Working on it'").unwrap(),
+            expected
+        );
+        assert!(parse_query("CREATE COMMENT backend#1 WITH 'Working on it'").is_err());
+    }
+
     #[test]
     fn test_parse_select_all() {
         let query = "SELECT * FROM issues";
@@ -68,6 +226,42 @@ mod tests {
         insta::assert_debug_snapshot!(&result);
     }
 
+    #[test]
+    fn test_parse_bulk_delete() {
+        let query = "DELETE issues WHERE status = 'closed'";
+        let result = parse_query(query).unwrap();
+        insta::assert_debug_snapshot!(&result);
+        assert_eq!(
+            parse_query("DELETE ALL comments").unwrap(),
+            IqlQuery::Delete(DeleteStatement {
+                entity: DeleteTarget::Bulk {
+                    entity: EntityType::Comments,
+                    filter: None,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_bulk_delete_requires_where_or_all() {
+        assert!(matches!(
+            parse_query("DELETE issues"),
+            Err(ParseError::MissingClause { clause, .. }) if clause == "WHERE"
+        ));
+        assert!(matches!(
+            parse_query("DELETE ALL meta"),
+            Err(ParseError::InvalidEntityType { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_bulk_update() {
+        let query = "UPDATE issues SET priority = low WHERE status = 'closed'";
+        let result = parse_query(query).unwrap();
+        insta::assert_debug_snapshot!(&result);
+        assert!(parse_query("UPDATE meta SET value = '2'").is_err());
+    }
+
     #[test]
     fn test_parse_delete() {
         let query = "DELETE issue my-project#456";
@@ -96,6 +290,261 @@ mod tests {
         insta::assert_debug_snapshot!(&result);
     }
 
+    #[test]
+    fn test_parse_multiple_statements() {
+        let result = parse_queries(
+            "CREATE PROJECT backend WITH name 'Backend';\n\
+             SELECT * FROM projects WHERE name = 'a;b';;\n",
+        )
+        .unwrap();
+        insta::assert_debug_snapshot!(&result);
+        assert!(parse_queries("").unwrap().is_empty());
+        assert!(parse_queries("SELECT * FROM users SELECT * FROM issues").is_err());
+    }
+
+    #[test]
+    fn test_parse_explain() {
+        let result = parse_query("EXPLAIN DELETE project my-project").unwrap();
+        insta::assert_debug_snapshot!(&result);
+    }
+
+    #[test]
+    fn test_parse_reactions() {
+        let result = parse_query("REACT TO COMMENT C123 WITH '👍'").unwrap();
+        insta::assert_debug_snapshot!(&result);
+        assert_eq!(
+            parse_query("UNREACT TO COMMENT C123 WITH '👍'").unwrap(),
+            IqlQuery::Unreact(ReactionStatement {
+                comment_id: CommentId::new("C123"),
+                reaction: "👍".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_reopen_with_comment() {
+        assert_eq!(
+            parse_query("REOPEN ISSUE backend#1 WITH 'regression found'").unwrap(),
+            IqlQuery::Reopen(ReopenStatement {
+                issue_id: IssueId::new("backend#1"),
+                comment: Some("regression found".to_string()),
+            })
+        );
+        assert!(matches!(
+            parse_query("REOPEN ISSUE backend#1").unwrap(),
+            IqlQuery::Reopen(ReopenStatement { comment: None, .. })
+        ));
+        assert!(parse_query("REOPEN ISSUE backend#1 WITH").is_err());
+    }
+
+    #[test]
+    fn test_parse_create_project_on_conflict() {
+        let on_conflict = |query: &str| match parse_query(query).unwrap() {
+            IqlQuery::Create(CreateStatement::Project { on_conflict, .. }) => on_conflict,
+            _ => panic!("Expected a CREATE PROJECT"),
+        };
+        assert_eq!(on_conflict("CREATE PROJECT backend"), None);
+        assert_eq!(
+            on_conflict("CREATE PROJECT backend on conflict do nothing"),
+            Some(OnConflict::DoNothing)
+        );
+        assert_eq!(
+            on_conflict(
+                "CREATE PROJECT backend WITH NAME 'Backend' ON CONFLICT UPDATE SET name = 'Backend'"
+            ),
+            Some(OnConflict::Update(vec![FieldUpdate {
+                field: "name".to_string(),
+                value: IqlValue::String("Backend".to_string()),
+            }]))
+        );
+        assert!(parse_query("CREATE PROJECT backend ON CONFLICT").is_err());
+        assert!(parse_query("CREATE PROJECT backend ON CONFLICT DO").is_err());
+        assert!(parse_query("CREATE PROJECT backend ON CONFLICT UPDATE name = 'x'").is_err());
+    }
+
+    #[test]
+    fn test_parse_move() {
+        assert_eq!(
+            parse_query("MOVE issue backend#1 TO frontend").unwrap(),
+            IqlQuery::Move(MoveStatement {
+                issue_id: IssueId::new("backend#1"),
+                project: ProjectId::new("frontend"),
+            })
+        );
+        assert!(parse_query("MOVE ISSUE backend#1").is_err());
+        assert!(parse_query("MOVE ISSUE backend#1 TO").is_err());
+    }
+
+    #[test]
+    fn test_parse_rename() {
+        assert_eq!(
+            parse_query("RENAME project backend TO server").unwrap(),
+            IqlQuery::Rename(RenameStatement {
+                project: ProjectId::new("backend"),
+                new_id: ProjectId::new("server"),
+            })
+        );
+        assert!(parse_query("RENAME ISSUE backend#1 TO server").is_err());
+    }
+
+    #[test]
+    fn test_parse_import() {
+        assert_eq!(
+            parse_query(r#"IMPORT '{"version":1}'"#).unwrap(),
+            IqlQuery::Import(ImportStatement {
+                dump: r#"{"version":1}"#.to_string(),
+                merge: false,
+            })
+        );
+        assert_eq!(
+            parse_query(r#"import '''{"name":"O'Brien"}''' merge"#).unwrap(),
+            IqlQuery::Import(ImportStatement {
+                dump: r#"{"name":"O'Brien"}"#.to_string(),
+                merge: true,
+            })
+        );
+        assert!(parse_query("IMPORT").is_err());
+        assert!(parse_query("IMPORT dump").is_err());
+        assert!(parse_queries("IMPORT '{}' OVERWRITE").is_err());
+        assert!(!parse_query("IMPORT '{}'").unwrap().is_read_only());
+        assert_eq!(parse_partial("IMPORT ").2, StatementKind::Import);
+    }
+
+    #[test]
+    fn test_parse_set() {
+        assert_eq!(
+            parse_query("SET DEFAULT_LIMIT = 20").unwrap(),
+            IqlQuery::Set(SessionOption::DefaultLimit(Some(20)))
+        );
+        assert_eq!(
+            parse_query("set nulls = last").unwrap(),
+            IqlQuery::Set(SessionOption::Nulls(Some(NullsOrder::Last)))
+        );
+        assert_eq!(
+            parse_query("SET case_sensitive = false").unwrap(),
+            IqlQuery::Set(SessionOption::CaseSensitive(false))
+        );
+        assert!(parse_query("SET default_limit = 'ten'").is_err());
+        assert!(parse_query("SET case_sensitive = 1").is_err());
+        assert!(parse_query("SET colour = blue").is_err());
+        // `SET` is read-only, so it works on read-only databases too
+        assert!(parse_query("SET nulls = FIRST").unwrap().is_read_only());
+    }
+
+    #[test]
+    fn test_session_options_fill_in_what_selects_leave_open() {
+        let mut session = SessionOptions::default();
+        for option in [
+            SessionOption::DefaultLimit(Some(10)),
+            SessionOption::Nulls(Some(NullsOrder::First)),
+            SessionOption::CaseSensitive(false),
+        ] {
+            session.set(&option);
+        }
+        let apply = |query: &str| session.apply(&parse_query(query).unwrap());
+        let parsed = |query: &str| parse_query(query).unwrap();
+
+        assert_eq!(
+            apply("SELECT * FROM issues WHERE title = 'Crash' AND kind != bug ORDER BY priority"),
+            parsed(
+                "SELECT * FROM issues WHERE title =~ 'Crash' AND kind !~ bug \
+                 ORDER BY priority NULLS FIRST LIMIT 10"
+            )
+        );
+        assert_eq!(
+            apply("SELECT * FROM issues ORDER BY priority NULLS LAST LIMIT 3"),
+            parsed("SELECT * FROM issues ORDER BY priority NULLS LAST LIMIT 3")
+        );
+        assert_eq!(
+            apply("EXPLAIN SELECT * FROM users"),
+            parsed("EXPLAIN SELECT * FROM users LIMIT 10")
+        );
+        assert_eq!(apply("DELETE ALL users"), parsed("DELETE ALL users"));
+    }
+
+    #[test]
+    fn test_parse_archive() {
+        assert_eq!(
+            parse_query("ARCHIVE ISSUE backend#1").unwrap(),
+            IqlQuery::Archive(ArchiveStatement {
+                target: ArchiveTarget::Issue(IssueId::new("backend#1")),
+            })
+        );
+        assert_eq!(
+            parse_query("unarchive project backend").unwrap(),
+            IqlQuery::Unarchive(ArchiveStatement {
+                target: ArchiveTarget::Project(ProjectId::new("backend")),
+            })
+        );
+        assert!(parse_query("ARCHIVE COMMENT C1").is_err());
+
+        let include_archived = |query: &str| match parse_query(query).unwrap() {
+            IqlQuery::Select(select) => (select.from_alias, select.include_archived),
+            _ => panic!("Expected a SELECT"),
+        };
+        assert_eq!(include_archived("SELECT * FROM issues"), (None, false));
+        assert_eq!(
+            include_archived("SELECT * FROM issues including archived WHERE OPEN"),
+            (None, true)
+        );
+        assert_eq!(
+            include_archived("SELECT * FROM issues i INCLUDING ARCHIVED"),
+            (Some("i".to_string()), true)
+        );
+    }
+
+    #[test]
+    fn test_parse_reindex() {
+        assert_eq!(parse_query("REINDEX").unwrap(), IqlQuery::Reindex);
+        assert_eq!(parse_query("reindex").unwrap(), IqlQuery::Reindex);
+    }
+
+    #[test]
+    fn test_parse_transaction_blocks() {
+        assert_eq!(
+            parse_queries("BEGIN; CREATE USER alice; commit;").unwrap(),
+            vec![
+                IqlQuery::Begin,
+                IqlQuery::Create(CreateStatement::User {
+                    username: "alice".to_string(),
+                    email: None,
+                    name: None,
+                }),
+                IqlQuery::Commit,
+            ]
+        );
+        assert_eq!(parse_query("ROLLBACK").unwrap(), IqlQuery::Rollback);
+    }
+
+    #[test]
+    fn test_parse_history() {
+        assert_eq!(
+            parse_query("HISTORY ISSUE backend#1").unwrap(),
+            IqlQuery::History(IssueId::new("backend#1"))
+        );
+        assert!(parse_query("HISTORY PROJECT backend").is_err());
+    }
+
+    #[test]
+    fn test_issue_ids_round_trip() {
+        for id in ["backend#1", "backend-api#42", "my_project#1000"] {
+            let query = parse_query(&format!("HISTORY ISSUE {id}")).unwrap();
+            assert_eq!(query, IqlQuery::History(IssueId::new(id)));
+            assert_eq!(parse_query(&query.to_string()).unwrap(), query);
+        }
+        // A project id cannot contain the separator, so the number is never ambiguous
+        assert!(parse_query("HISTORY ISSUE a#b#1").is_err());
+    }
+
+    #[test]
+    fn test_parse_describe() {
+        let query = parse_query("describe issues").unwrap();
+        assert_eq!(query, IqlQuery::Describe(EntityType::Issues));
+        assert!(query.is_read_only());
+        assert_eq!(query.to_string(), "DESCRIBE ISSUES");
+        assert!(parse_query("DESCRIBE ISSUE").is_err());
+    }
+
     #[test]
     fn test_parse_complex_query() {
         let query = "SELECT title, status, assignee FROM issues WHERE project = 'backend' AND (priority = high OR status = 'critical') ORDER BY created_at DESC LIMIT 10";
@@ -166,6 +615,60 @@ mod tests {
         insta::assert_debug_snapshot!(&result);
     }
 
+    #[test]
+    fn test_limit_and_offset_in_either_order() {
+        let limit_first = parse_query("SELECT * FROM issues LIMIT 10 OFFSET 20").unwrap();
+        let offset_first = parse_query("SELECT * FROM issues OFFSET 20 LIMIT 10").unwrap();
+        assert_eq!(limit_first, offset_first);
+        assert!(matches!(
+            offset_first,
+            IqlQuery::Select(SelectStatement {
+                limit: Some(10),
+                offset: Some(20),
+                ..
+            })
+        ));
+
+        assert!(matches!(
+            parse_query("SELECT * FROM issues LIMIT 10 OFFSET 20 LIMIT 5"),
+            Err(ParseError::DuplicateClause { clause, position: 40 }) if clause == "LIMIT"
+        ));
+        assert!(matches!(
+            parse_query("SELECT * FROM issues OFFSET 1 OFFSET 2"),
+            Err(ParseError::DuplicateClause { clause, .. }) if clause == "OFFSET"
+        ));
+    }
+
+    #[test]
+    fn test_after_pages_from_a_key() {
+        let after = |query: &str| match parse_query(query).unwrap() {
+            IqlQuery::Select(SelectStatement { after, limit, .. }) => (after, limit),
+            _ => panic!("Expected a SELECT"),
+        };
+
+        assert_eq!(
+            after("SELECT * FROM issues AFTER 'backend#20' LIMIT 10"),
+            (Some("backend#20".to_string()), Some(10))
+        );
+        assert_eq!(
+            after("SELECT * FROM issues WHERE OPEN LIMIT 10 after 'backend#20'"),
+            (Some("backend#20".to_string()), Some(10))
+        );
+        // An identifier named `after` is still an alias
+        assert!(matches!(
+            parse_query("SELECT after.title FROM issues after"),
+            Ok(IqlQuery::Select(SelectStatement { after: None, .. }))
+        ));
+        assert!(matches!(
+            parse_query("SELECT * FROM issues AFTER 'a' AFTER 'b'"),
+            Err(ParseError::DuplicateClause { clause, .. }) if clause == "AFTER"
+        ));
+        assert!(matches!(
+            parse_query("SELECT * FROM issues ORDER BY title AFTER 'a'"),
+            Err(ParseError::InvalidSyntax { .. })
+        ));
+    }
+
     #[test]
     fn test_parse_all_priorities() {
         let queries = vec![
@@ -188,6 +691,8 @@ mod tests {
             "SELECT * FROM projects",
             "SELECT * FROM issues",
             "SELECT * FROM comments",
+            "SELECT * FROM meta",
+            "SELECT * FROM links WHERE type = 'blocks'",
         ];
 
         for query in queries {
@@ -419,6 +924,171 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_errors_point_at_the_offending_text() {
+        let query = "SELECT *\nFROM issues\nWHERE title = 'Crème brûlée'\nORDER priority";
+        let Err(ParseError::UnexpectedToken {
+            found, position, ..
+        }) = parse_query(query)
+        else {
+            panic!("Expected an unexpected token");
+        };
+        assert_eq!(found, "priority");
+        // Positions count characters, so the accents before the error don't shift it
+        let at_position = query.chars().skip(position).collect::<String>();
+        assert_eq!(at_position, "priority");
+
+        let Err(ParseError::UnexpectedToken {
+            found, position, ..
+        }) = parse_query("ASSIGN ISSUE test#1 TO")
+        else {
+            panic!("Expected an unexpected token");
+        };
+        assert_eq!(found, "end of input");
+        assert_eq!(position, 22);
+    }
+
+    #[test]
+    fn test_close_reason_payloads() {
+        let reason = |query: &str| match parse_query(query).unwrap() {
+            IqlQuery::Close(CloseStatement { reason, .. }) => reason.unwrap(),
+            _ => panic!("Expected a CLOSE"),
+        };
+
+        assert_eq!(
+            reason("CLOSE issue backend#1 WITH WONTFIX 'not planned'"),
+            CloseReason::WontFix {
+                reason: Some("not planned".to_string())
+            }
+        );
+        assert_eq!(
+            reason("CLOSE issue backend#1 WITH wontfix"),
+            CloseReason::WontFix { reason: None }
+        );
+        assert_eq!(
+            reason("CLOSE issue backend#1 WITH DUPLICATE OF backend#2"),
+            CloseReason::Duplicate {
+                of: Some(IssueId::new("backend#2"))
+            }
+        );
+        assert_eq!(
+            reason("CLOSE issue backend#1 WITH DUPLICATE OF backend#2").to_string(),
+            "DUPLICATE OF backend#2"
+        );
+        assert!(parse_query("CLOSE issue backend#1 WITH DUPLICATE OF").is_err());
+    }
+
+    #[test]
+    fn test_bulk_close_with_date() {
+        let query = "CLOSE issues WHERE updated_at < DATE '2023-01-01' WITH wontfix";
+        let result = parse_query(query).unwrap();
+        insta::assert_debug_snapshot!(&result);
+
+        assert!(matches!(
+            parse_query("CLOSE issues WITH done"),
+            Err(ParseError::MissingClause { .. })
+        ));
+        assert!(matches!(
+            parse_query("SELECT * FROM issues WHERE updated_at < DATE '2023-13-01'"),
+            Err(ParseError::InvalidDate { .. })
+        ));
+    }
+
+    #[test]
+    fn test_queries_display_as_equivalent_iql() {
+        let queries = [
+            "CREATE USER alice WITH EMAIL 'alice@example.com' NAME 'Alice'",
+            "CREATE PROJECT backend WITH NAME 'Back\\end' OWNER alice",
+            "CREATE PROJECT backend WITH OWNER alice",
+            "CREATE PROJECT backend ON CONFLICT DO NOTHING",
+            "CREATE PROJECT backend WITH NAME 'Backend' ON CONFLICT UPDATE SET name = 'Backend', owner = alice",
+            "CREATE ISSUE OF KIND bug IN backend WITH TITLE 'It\\'s broken' PRIORITY high LABELS ('ui', 'crash')",
+            "CREATE ISSUES IN backend WITH TITLES ['Write docs', 'It\\'s done']",
+            "UPDATE issue backend#1 SET priority = low, assignee = bob",
+            "UPDATE issues SET priority = low WHERE status = 'Open' AND (assignee = bob OR assignee IS NULL)",
+            "DELETE ALL comments",
+            "DELETE issues WHERE NOT (labels HAS 'ui' AND priority IN (high, critical))",
+            "DELETE COMMENT backend#1/c2",
+            "ASSIGN ISSUE backend#1 TO bob",
+            "CLOSE ISSUE backend#1 WITH WONTFIX 'not planned'",
+            "CLOSE issues WHERE updated_at < DATE '2023-01-01' WITH DUPLICATE OF backend#2",
+            "REOPEN ISSUE backend#1",
+            "REOPEN ISSUE backend#1 WITH 'regression found'",
+            "MOVE ISSUE backend#1 TO frontend",
+            "RENAME PROJECT backend TO server",
+            "SELECT * FROM comments WHERE created_at >= '2024-01-15T10:30:00Z'",
+            "COMMENT ON ISSUE backend#1 WITH 'Same here' AUTHOR bob",
+            "SELECT * FROM issues",
+            "SELECT i.title AS name, COUNT(*) FROM issues AS i WHERE i.status = 'Open' AND i.votes > i.watchers GROUP BY i.title ORDER BY i.title DESC NULLS LAST LIMIT 5 OFFSET 10",
+            "SELECT title, p.name FROM issues JOIN projects p ON project = p.id WHERE p.owner = alice ORDER BY title",
+            "REACT TO COMMENT backend#1/c2 WITH '+1'",
+            "UNREACT TO COMMENT backend#1/c2 WITH '+1'",
+            "SELECT title FROM issues WHERE OPEN AFTER 'backend#2' LIMIT 20",
+            "REINDEX",
+            "EXPLAIN SELECT * FROM users WHERE name =~ 'alice'",
+            "SET default_limit = 50",
+            "SET default_limit = NULL",
+            "SET nulls = FIRST",
+            "SET nulls = DEFAULT",
+            "SET case_sensitive = FALSE",
+            r#"IMPORT '{"version":1,"tables":[{"name":"users","entries":[]}]}' MERGE"#,
+            r#"IMPORT '{"name":"O\'Brien"}'"#,
+        ];
+        for query in queries {
+            let parsed = parse_query(query).unwrap();
+            let rendered = parsed.to_string();
+            assert_eq!(parse_query(&rendered).unwrap(), parsed, "{rendered}");
+        }
+    }
+
+    #[test]
+    fn test_parse_partial_reports_the_statement_kind() {
+        let (statement, error, kind) = parse_partial("CREATE ISSUE OF KIND bug IN ");
+        assert!(statement.is_none());
+        assert!(error.is_some());
+        assert_eq!(kind, StatementKind::Create(Some(EntityType::Issues)));
+
+        let (statement, error, kind) = parse_partial("SELECT * FROM issues");
+        assert!(statement.is_some());
+        assert!(error.is_none());
+        assert_eq!(kind, StatementKind::Select);
+
+        assert_eq!(parse_partial("create").2, StatementKind::Create(None));
+        assert_eq!(
+            parse_partial("EXPLAIN CLOSE issue ").2,
+            StatementKind::Close
+        );
+        assert_eq!(
+            parse_partial("COMMENT ON ISSUE a#1 WITH 'unterminated").2,
+            StatementKind::Comment
+        );
+        assert_eq!(parse_partial("").2, StatementKind::Unknown);
+    }
+
+    #[test]
+    fn test_illegal_characters_are_reported() {
+        assert!(matches!(
+            parse_query("SELECT * FROM issues WHERE assignee = @alice"),
+            Err(ParseError::LexError { position: 38, .. })
+        ));
+        assert!(matches!(
+            parse_queries("REINDEX; REINDEX $"),
+            Err(ParseError::LexError { position: 17, .. })
+        ));
+    }
+
+    #[test]
+    fn test_unterminated_strings_are_reported() {
+        assert_eq!(
+            parse_query("SELECT * FROM issues WHERE title = 'unterminated"),
+            Err(ParseError::UnterminatedString { position: 35 })
+        );
+        assert_eq!(
+            parse_query(r#"SELECT * FROM issues WHERE title = "unterminated"#),
+            Err(ParseError::UnterminatedString { position: 35 })
+        );
+    }
+
     #[test]
     fn test_empty_string_value() {
         let query = "UPDATE issue backend#1 SET description = ''";
@@ -540,4 +1210,710 @@ mod tests {
         let result = parse_query(query).unwrap();
         insta::assert_debug_snapshot!(&result);
     }
+
+    #[test]
+    fn test_query_over_max_length_is_rejected() {
+        let config = ParserConfig {
+            max_query_length: 32,
+            ..ParserConfig::default()
+        };
+        let query = format!(
+            "SELECT * FROM issues WHERE title = '{}'",
+            "a".repeat(1_000_000)
+        );
+        let result = parse_query_with_config(&query, &config);
+        assert_eq!(
+            result,
+            Err(ParseError::InvalidSyntax {
+                message: "query too long".to_string(),
+                position: 32,
+            })
+        );
+    }
+
+    #[test]
+    fn test_query_under_max_length_is_parsed() {
+        let config = ParserConfig {
+            max_query_length: 32,
+            ..ParserConfig::default()
+        };
+        let result = parse_query_with_config("SELECT * FROM issues", &config);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_default_order_direction_is_configurable() {
+        let direction =
+            |query: &str, config: &ParserConfig| match parse_query_with_config(query, config)
+                .unwrap()
+            {
+                IqlQuery::Select(SelectStatement {
+                    order_by: Some(order_by),
+                    ..
+                }) => order_by.direction,
+                _ => panic!("Expected a SELECT with an ORDER BY"),
+            };
+        let ascending = ParserConfig::default();
+        let descending = ParserConfig {
+            default_order_direction: OrderDirection::Desc,
+            ..ParserConfig::default()
+        };
+
+        let query = "SELECT * FROM issues ORDER BY created_at";
+        assert_eq!(direction(query, &ascending), OrderDirection::Asc);
+        assert_eq!(direction(query, &descending), OrderDirection::Desc);
+        for config in [&ascending, &descending] {
+            assert_eq!(
+                direction("SELECT * FROM issues ORDER BY created_at ASC", config),
+                OrderDirection::Asc
+            );
+            assert_eq!(
+                direction("SELECT * FROM issues ORDER BY created_at DESC", config),
+                OrderDirection::Desc
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_nulls_order() {
+        let order_by = |query: &str| match parse_query(query).unwrap() {
+            IqlQuery::Select(SelectStatement {
+                order_by: Some(order_by),
+                ..
+            }) => order_by,
+            _ => panic!("Expected a SELECT with an ORDER BY"),
+        };
+
+        let ascending = order_by("SELECT * FROM issues ORDER BY priority ASC");
+        assert_eq!(ascending.nulls, None);
+        assert!(!ascending.nulls_first());
+        let descending = order_by("SELECT * FROM issues ORDER BY priority DESC");
+        assert_eq!(descending.nulls, None);
+        assert!(descending.nulls_first());
+
+        let first = order_by("SELECT * FROM issues ORDER BY priority ASC NULLS FIRST");
+        assert_eq!(first.nulls, Some(NullsOrder::First));
+        assert!(first.nulls_first());
+        let last = order_by("SELECT * FROM issues ORDER BY priority desc nulls last LIMIT 3");
+        assert_eq!(last.nulls, Some(NullsOrder::Last));
+        assert!(!last.nulls_first());
+
+        assert!(parse_query("SELECT * FROM issues ORDER BY priority NULLS").is_err());
+        assert!(parse_query("SELECT * FROM issues ORDER BY priority NULLS MIDDLE").is_err());
+    }
+
+    #[test]
+    fn test_visitor_collects_values() {
+        #[derive(Default)]
+        struct ValueCollector(Vec<IqlValue>);
+
+        impl Visitor for ValueCollector {
+            fn visit_value(&mut self, value: &IqlValue) {
+                self.0.push(value.clone());
+            }
+        }
+
+        let query = r#"
+            SELECT title, status
+            FROM issues
+            WHERE (priority = critical OR priority = high)
+              AND status IN ('open', 'in-progress')
+              AND NOT assignee = 'bob'
+              AND assignee IS NOT NULL
+              AND estimate >= 1.5
+        "#;
+        let result = parse_query(query).unwrap();
+        let mut collector = ValueCollector::default();
+        walk_query(&mut collector, &result);
+        insta::assert_debug_snapshot!(&collector.0);
+    }
+
+    #[test]
+    fn test_referenced_fields_and_entities() {
+        let query = r#"
+            SELECT title
+            FROM issues
+            WHERE (priority = critical OR priority = high)
+              AND status IN ('open', 'in-progress')
+              AND assignee IS NOT NULL
+              AND project = 'backend'
+            ORDER BY created_at DESC
+        "#;
+        let result = parse_query(query).unwrap();
+
+        let fields = result.referenced_fields();
+        for field in [
+            "title",
+            "priority",
+            "status",
+            "assignee",
+            "project",
+            "created_at",
+        ] {
+            assert!(fields.contains(field), "missing field {field}");
+        }
+        assert_eq!(fields.len(), 6);
+        assert_eq!(
+            result.referenced_entities(),
+            std::collections::HashSet::from([EntityType::Issues])
+        );
+    }
+
+    #[test]
+    fn test_referenced_fields_of_update() {
+        let query = "UPDATE issue backend#1 SET priority = critical, status = 'open'";
+        let result = parse_query(query).unwrap();
+        assert_eq!(
+            result.referenced_fields(),
+            std::collections::HashSet::from(["priority".to_string(), "status".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_empty_in_list_never_matches() {
+        let query = "SELECT * FROM issues WHERE status IN ()";
+        let result = parse_query(query).unwrap();
+        insta::assert_debug_snapshot!(&result);
+        let IqlQuery::Select(SelectStatement {
+            filter: Some(filter),
+            ..
+        }) = result
+        else {
+            panic!("Expected a SELECT with a filter");
+        };
+        assert!(filter.is_trivially_false());
+    }
+
+    #[test]
+    fn test_in_matches_id() {
+        let filter = match parse_query("SELECT * FROM issues WHERE id IN ('a#1', 'a#2')").unwrap() {
+            IqlQuery::Select(SelectStatement {
+                filter: Some(filter),
+                ..
+            }) => filter,
+            _ => panic!("Expected a SELECT with a filter"),
+        };
+        let issue = facet_value::VObject::new().into_value();
+
+        assert!(filter.matches("a#1", &issue));
+        assert!(filter.matches("a#2", &issue));
+        assert!(!filter.matches("a#3", &issue));
+    }
+
+    #[test]
+    fn test_numbers_stored_as_strings_compare_as_numbers() {
+        let filter = |query: &str| match parse_query(query).unwrap() {
+            IqlQuery::Select(SelectStatement {
+                filter: Some(filter),
+                ..
+            }) => filter,
+            _ => panic!("Expected a SELECT with a filter"),
+        };
+        let row = |count: &str| {
+            let mut object = facet_value::VObject::new();
+            object.insert("count", facet_value::VString::new(count).into_value());
+            object.into_value()
+        };
+
+        let greater = filter("SELECT * FROM issues WHERE count > 10");
+        assert!(greater.matches("a#1", &row("12")));
+        // Compared as strings, "9" would sort after "10"
+        assert!(!greater.matches("a#2", &row("9")));
+        assert!(!greater.matches("a#3", &row("many")));
+        assert!(filter("SELECT * FROM issues WHERE count = 10").matches("a#4", &row("10")));
+        assert!(filter("SELECT * FROM issues WHERE count <= 2.5").matches("a#5", &row(" 2 ")));
+        // Without a number on either side the values stay strings
+        assert!(filter("SELECT * FROM issues WHERE count > '10'").matches("a#6", &row("9")));
+    }
+
+    #[test]
+    fn test_timestamps_compare_chronologically() {
+        let query = "SELECT * FROM comments WHERE created_at > '2024-01-01T00:00:00Z'";
+        let filter = match parse_query(query).unwrap() {
+            IqlQuery::Select(SelectStatement {
+                filter: Some(filter),
+                ..
+            }) => filter,
+            _ => panic!("Expected a SELECT with a filter"),
+        };
+        assert!(matches!(
+            &filter,
+            FilterExpression::Comparison {
+                value: IqlValue::DateTime(_),
+                ..
+            }
+        ));
+        let row = |created_at: &str| {
+            let mut object = facet_value::VObject::new();
+            object.insert(
+                "created_at",
+                facet_value::VString::new(created_at).into_value(),
+            );
+            object.into_value()
+        };
+
+        assert!(filter.matches("a#1/c1", &row("2024-01-15T10:30:00Z")));
+        assert!(!filter.matches("a#1/c2", &row("2023-12-31T23:59:59Z")));
+        assert!(!filter.matches("a#1/c3", &row("2024-01-01T00:00:00Z")));
+        // As strings, '.' sorts before 'Z' and the offset would be ignored
+        assert!(filter.matches("a#1/c4", &row("2024-01-01T00:00:00.5Z")));
+        assert!(!filter.matches("a#1/c5", &row("2024-01-01T01:00:00+02:00")));
+
+        assert!(matches!(
+            parse_query("SELECT * FROM issues WHERE title = '2024-01-01'").unwrap(),
+            IqlQuery::Select(SelectStatement {
+                filter: Some(FilterExpression::Comparison {
+                    value: IqlValue::String(_),
+                    ..
+                }),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_identifiers_as_values() {
+        let filter = |query: &str| match parse_query(query).unwrap() {
+            IqlQuery::Select(SelectStatement {
+                filter: Some(filter),
+                ..
+            }) => filter,
+            _ => panic!("Expected a SELECT with a filter"),
+        };
+        let issue = |assignee: &str| {
+            let mut object = facet_value::VObject::new();
+            object.insert("assignee", facet_value::VString::new(assignee).into_value());
+            object.into_value()
+        };
+
+        let equal = filter("SELECT * FROM issues WHERE assignee = alice");
+        assert_eq!(
+            equal,
+            FilterExpression::Comparison {
+                field: "assignee".to_string(),
+                op: ComparisonOp::Equal,
+                value: IqlValue::Identifier("alice".to_string()),
+            }
+        );
+        assert!(equal.matches("a#1", &issue("alice")));
+        assert!(!equal.matches("a#2", &issue("bob")));
+
+        let list = filter("SELECT * FROM issues WHERE assignee IN (alice, bob)");
+        assert_eq!(
+            list,
+            FilterExpression::In {
+                field: "assignee".to_string(),
+                values: vec![
+                    IqlValue::Identifier("alice".to_string()),
+                    IqlValue::Identifier("bob".to_string()),
+                ],
+            }
+        );
+        assert!(list.matches("a#1", &issue("alice")));
+        assert!(list.matches("a#2", &issue("bob")));
+        assert!(!list.matches("a#3", &issue("carol")));
+        // Identifiers mix with other literals
+        assert!(
+            filter("SELECT * FROM issues WHERE assignee IN ('alice', my-user)")
+                .matches("a#4", &issue("my-user"))
+        );
+    }
+
+    #[test]
+    fn test_in_with_null_matches_missing_fields() {
+        let issue = |assignee: Option<&str>| {
+            let mut object = facet_value::VObject::new();
+            object.insert("title", facet_value::VString::new("Issue").into_value());
+            if let Some(assignee) = assignee {
+                object.insert("assignee", facet_value::VString::new(assignee).into_value());
+            }
+            object.into_value()
+        };
+        let filter =
+            match parse_query("SELECT * FROM issues WHERE assignee IN ('alice', NULL)").unwrap() {
+                IqlQuery::Select(SelectStatement {
+                    filter: Some(filter),
+                    ..
+                }) => filter,
+                _ => panic!("Expected a SELECT with a filter"),
+            };
+
+        assert!(filter.matches("a#1", &issue(Some("alice"))));
+        assert!(filter.matches("a#2", &issue(None)));
+        let mut null_assignee = facet_value::VObject::new();
+        null_assignee.insert("assignee", facet_value::Value::NULL);
+        assert!(filter.matches("a#3", &null_assignee.into_value()));
+        assert!(!filter.matches("a#4", &issue(Some("bob"))));
+    }
+
+    #[test]
+    fn test_compare_fields_of_the_same_row() {
+        let schema = Schema::default().with_fields(
+            EntityType::Issues,
+            ["created_at", "updated_at", "watchers", "votes"],
+        );
+        let filter = |query: &str| {
+            let mut query = parse_query(query).unwrap();
+            resolve_fields(&mut query, &schema);
+            match query {
+                IqlQuery::Select(SelectStatement {
+                    filter: Some(filter),
+                    ..
+                }) => filter,
+                _ => panic!("Expected a SELECT with a filter"),
+            }
+        };
+        let issue = |created_at: &str, updated_at: &str, watchers: u64, votes: u64| {
+            let mut object = facet_value::VObject::new();
+            object.insert(
+                "created_at",
+                facet_value::VString::new(created_at).into_value(),
+            );
+            object.insert(
+                "updated_at",
+                facet_value::VString::new(updated_at).into_value(),
+            );
+            object.insert(
+                "watchers",
+                facet_value::VNumber::from_u64(watchers).into_value(),
+            );
+            object.insert("votes", facet_value::VNumber::from_u64(votes).into_value());
+            object.into_value()
+        };
+        let edited = issue("2024-01-01T10:00:00Z", "2024-03-01T10:00:00Z", 2, 5);
+        let untouched = issue("2024-01-01T10:00:00Z", "2024-01-01T10:00:00Z", 5, 2);
+
+        let dates = filter("SELECT * FROM issues WHERE created_at < updated_at");
+        assert!(dates.matches("a#1", &edited));
+        assert!(!dates.matches("a#2", &untouched));
+        assert!(
+            filter("SELECT * FROM issues WHERE created_at = updated_at").matches("a#2", &untouched)
+        );
+
+        let numbers = filter("SELECT * FROM issues i WHERE i.votes > i.watchers");
+        assert_eq!(
+            numbers,
+            FilterExpression::Comparison {
+                field: "votes".to_string(),
+                op: ComparisonOp::GreaterThan,
+                value: IqlValue::Field("watchers".to_string()),
+            }
+        );
+        assert!(numbers.matches("a#1", &edited));
+        assert!(!numbers.matches("a#2", &untouched));
+
+        // A qualified field the row lacks never matches, a bare name the schema does not know
+        // is a string, even where the row happens to have such a field
+        assert!(
+            !filter("SELECT * FROM issues WHERE issues.votes > issues.likes")
+                .matches("a#1", &edited)
+        );
+        assert!(filter("SELECT * FROM issues WHERE updated_at < stars").matches("a#1", &edited));
+        let mut query = parse_query("SELECT * FROM issues WHERE created_at = updated_at").unwrap();
+        resolve_fields(&mut query, &Schema::default());
+        let IqlQuery::Select(SelectStatement {
+            filter: Some(unresolved),
+            ..
+        }) = query
+        else {
+            panic!("Expected a SELECT with a filter");
+        };
+        assert!(!unresolved.matches("a#2", &untouched));
+    }
+
+    #[test]
+    fn test_equality_ignoring_case() {
+        let filter = match parse_query("SELECT * FROM issues WHERE assignee =~ 'Alice'").unwrap() {
+            IqlQuery::Select(SelectStatement {
+                filter: Some(filter),
+                ..
+            }) => filter,
+            _ => panic!("Expected a SELECT with a filter"),
+        };
+        assert_eq!(filter, field("assignee").eq_ignore_case("Alice"));
+        let issue = |assignee: &str| {
+            let mut object = facet_value::VObject::new();
+            object.insert("assignee", facet_value::VString::new(assignee).into_value());
+            object.into_value()
+        };
+
+        assert!(filter.matches("a#1", &issue("alice")));
+        assert!(filter.matches("a#2", &issue("ALICE")));
+        assert!(!filter.matches("a#3", &issue("alicia")));
+        assert!(
+            !field("assignee")
+                .eq("Alice")
+                .matches("a#1", &issue("alice"))
+        );
+
+        let filter = match parse_query("SELECT * FROM issues WHERE assignee !~ 'Alice'").unwrap() {
+            IqlQuery::Select(SelectStatement {
+                filter: Some(filter),
+                ..
+            }) => filter,
+            _ => panic!("Expected a SELECT with a filter"),
+        };
+        assert_eq!(filter, field("assignee").ne_ignore_case("Alice"));
+        assert!(!filter.matches("a#1", &issue("ALICE")));
+        assert!(filter.matches("a#2", &issue("alicia")));
+        // Like `!=`, rows without the field don't match
+        let unassigned = facet_value::VObject::new().into_value();
+        assert!(!filter.matches("a#3", &unassigned));
+        assert!(!field("assignee").ne("Alice").matches("a#3", &unassigned));
+    }
+
+    #[test]
+    fn test_filter_by_issue_kind() {
+        let filter = match parse_query("SELECT kind FROM issues WHERE kind = bug").unwrap() {
+            IqlQuery::Select(SelectStatement {
+                filter: Some(filter),
+                ..
+            }) => filter,
+            _ => panic!("Expected a SELECT with a filter"),
+        };
+        assert_eq!(filter, field("kind").eq(IssueKind::Bug));
+        assert_eq!(filter.to_string(), "kind = BUG");
+        let issue = |kind: &str| {
+            let mut object = facet_value::VObject::new();
+            object.insert("kind", facet_value::VString::new(kind).into_value());
+            object.into_value()
+        };
+
+        assert!(filter.matches("a#1", &issue("Bug")));
+        assert!(!filter.matches("a#2", &issue("Task")));
+    }
+
+    #[test]
+    fn test_validate_reports_unknown_fields() {
+        let schema = Schema::default()
+            .with_fields(
+                EntityType::Issues,
+                ["title", "status", "project", "priority"],
+            )
+            .with_fields(EntityType::Projects, ["name", "owner"]);
+        let validate = |query: &str| validate(&parse_query(query).unwrap(), &schema);
+        let unknown = |entity, field: &str, clause: &str| ValidationError::UnknownField {
+            entity,
+            field: field.to_string(),
+            clause: clause.to_string(),
+        };
+
+        assert_eq!(
+            validate(
+                "SELECT id, title, p.name FROM issues JOIN projects p ON project = p.id WHERE status = 'Open' ORDER BY title"
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            validate("SELECT title AS name, COUNT(*) FROM issues GROUP BY title ORDER BY name"),
+            Ok(())
+        );
+        assert_eq!(
+            validate("SELECT titel FROM issues WHERE priority > statuss ORDER BY created"),
+            Err(vec![
+                unknown(EntityType::Issues, "titel", "SELECT"),
+                unknown(EntityType::Issues, "created", "ORDER BY"),
+            ])
+        );
+        assert_eq!(
+            validate("SELECT * FROM issues WHERE title = issues.titel"),
+            Err(vec![unknown(EntityType::Issues, "titel", "WHERE")])
+        );
+        assert_eq!(
+            validate(
+                "SELECT * FROM issues JOIN projects ON project = projects.id WHERE projects.onwer = alice"
+            ),
+            Err(vec![unknown(EntityType::Projects, "onwer", "WHERE")])
+        );
+        assert_eq!(
+            validate("UPDATE issues SET severity = high WHERE prio = high"),
+            Err(vec![
+                unknown(EntityType::Issues, "severity", "SET"),
+                unknown(EntityType::Issues, "prio", "WHERE"),
+            ])
+        );
+        // Entities the schema does not know are not checked
+        assert_eq!(validate("SELECT anything FROM users"), Ok(()));
+    }
+
+    #[test]
+    fn test_open_and_closed_shorthands() {
+        let filter = |query: &str| match parse_query(query).unwrap() {
+            IqlQuery::Select(SelectStatement {
+                filter: Some(filter),
+                ..
+            }) => filter,
+            _ => panic!("Expected a SELECT with a filter"),
+        };
+        let issue = |status: facet_value::Value| {
+            let mut object = facet_value::VObject::new();
+            object.insert("status", status);
+            object.into_value()
+        };
+        let open = issue(facet_value::VString::new("Open").into_value());
+        let assigned = issue(facet_value::VString::new("Assigned").into_value());
+        let mut reason = facet_value::VObject::new();
+        reason.insert("reason", facet_value::VString::new("Done").into_value());
+        let mut closed = facet_value::VObject::new();
+        closed.insert("Closed", reason.into_value());
+        let closed = issue(closed.into_value());
+
+        let open_filter = filter("SELECT * FROM issues WHERE OPEN");
+        assert_eq!(open_filter, field("status").eq("Open"));
+        assert!(open_filter.matches("a#1", &open));
+        assert!(!open_filter.matches("a#2", &assigned));
+        assert!(!open_filter.matches("a#3", &closed));
+
+        let closed_filter = filter("SELECT * FROM issues WHERE closed ORDER BY title");
+        assert_eq!(closed_filter, field("status").eq("Closed"));
+        assert!(closed_filter.matches("a#3", &closed));
+        assert!(!closed_filter.matches("a#1", &open));
+
+        assert_eq!(
+            filter("SELECT * FROM issues WHERE NOT CLOSED AND assignee = 'alice'"),
+            (!field("status").eq("Closed")).and(field("assignee").eq("alice"))
+        );
+        // A field that happens to be called `open` still compares as usual
+        assert_eq!(
+            filter("SELECT * FROM issues WHERE open = true"),
+            field("open").eq(true)
+        );
+    }
+
+    #[test]
+    fn test_built_filter_matches_parsed_filter() {
+        let parsed = match parse_query(
+            "SELECT * FROM issues WHERE (status = 'open' AND priority > medium) \
+             OR (assignee IN ('alice', 'bob') AND NOT title LIKE 'WIP%') OR description IS NULL",
+        )
+        .unwrap()
+        {
+            IqlQuery::Select(SelectStatement {
+                filter: Some(filter),
+                ..
+            }) => filter,
+            _ => panic!("Expected a SELECT with a filter"),
+        };
+        let built = field("status")
+            .eq("open")
+            .and(field("priority").gt(Priority::Medium))
+            .or(field("assignee")
+                .in_(["alice", "bob"])
+                .and(!field("title").like("WIP%")))
+            .or(field("description").is_null());
+        assert_eq!(built, parsed);
+
+        let issue = |status: &str, assignee: &str, title: &str| {
+            let mut object = facet_value::VObject::new();
+            object.insert("status", facet_value::VString::new(status).into_value());
+            object.insert("assignee", facet_value::VString::new(assignee).into_value());
+            object.insert("title", facet_value::VString::new(title).into_value());
+            object.insert("description", facet_value::VString::new("").into_value());
+            object.into_value()
+        };
+        for issue in [
+            issue("closed", "alice", "Fix login"),
+            issue("closed", "alice", "WIP login"),
+            issue("closed", "carol", "Fix login"),
+        ] {
+            assert_eq!(built.matches("a#1", &issue), parsed.matches("a#1", &issue));
+        }
+        assert!(built.matches("a#1", &issue("closed", "alice", "Fix login")));
+        assert!(!built.matches("a#1", &issue("closed", "alice", "WIP login")));
+    }
+
+    #[test]
+    fn test_trivially_false_filters() {
+        let filter_of = |query: &str| match parse_query(query).unwrap() {
+            IqlQuery::Select(SelectStatement {
+                filter: Some(filter),
+                ..
+            }) => filter,
+            _ => panic!("Expected a SELECT with a filter"),
+        };
+
+        for query in [
+            "SELECT * FROM issues WHERE status = 'open' AND status = 'closed'",
+            "SELECT * FROM issues WHERE assignee IS NULL AND assignee IS NOT NULL",
+            "SELECT * FROM issues WHERE (status = 'a' AND status = 'b') OR (title IS NULL AND title IS NOT NULL)",
+            "SELECT * FROM issues WHERE priority = high AND (status = 'a' AND status = 'b')",
+        ] {
+            assert!(filter_of(query).is_trivially_false(), "{query}");
+        }
+        for query in [
+            "SELECT * FROM issues WHERE status = 'open' AND status = 'open'",
+            "SELECT * FROM issues WHERE status = 'open' OR status = 'closed'",
+            "SELECT * FROM issues WHERE status = 'open' AND title = 'closed'",
+        ] {
+            assert!(!filter_of(query).is_trivially_false(), "{query}");
+        }
+
+        let empty_in = FilterExpression::In {
+            field: "status".to_string(),
+            values: vec![],
+        };
+        assert!(empty_in.is_trivially_false());
+    }
+
+    #[test]
+    fn test_has_matches_array_membership() {
+        let issue_with_labels = |labels: &[&str]| {
+            let mut array = facet_value::VArray::new();
+            for label in labels {
+                array.push(facet_value::VString::new(label).into_value());
+            }
+            let mut object = facet_value::VObject::new();
+            object.insert("labels", array.into_value());
+            object.into_value()
+        };
+        let filter = match parse_query("SELECT * FROM issues WHERE labels HAS 'bug'").unwrap() {
+            IqlQuery::Select(SelectStatement {
+                filter: Some(filter),
+                ..
+            }) => filter,
+            _ => panic!("Expected a SELECT with a filter"),
+        };
+
+        assert!(filter.matches("test#1", &issue_with_labels(&["ui", "bug"])));
+        assert!(!filter.matches("test#2", &issue_with_labels(&["ui", "feature"])));
+        assert!(!filter.matches("test#3", &issue_with_labels(&[])));
+    }
+
+    #[test]
+    fn test_update_sets_absent_optional_field() {
+        #[derive(facet::Facet)]
+        #[allow(dead_code)]
+        struct Info {
+            name: String,
+            #[facet(skip_serializing_if = Option::is_none)]
+            priority: Option<String>,
+        }
+
+        let mut object = facet_value::VObject::new();
+        object.insert("name", facet_value::VString::new("test").into_value());
+        let mut value = object.into_value();
+        let updates = match parse_query("UPDATE issue test#1 SET priority = high").unwrap() {
+            IqlQuery::Update(UpdateStatement { updates, .. }) => updates,
+            _ => panic!("Expected an UPDATE"),
+        };
+
+        updates[0].apply_to::<Info>(&mut value).unwrap();
+        assert_eq!(
+            value
+                .as_object()
+                .and_then(|object| object.get("priority"))
+                .and_then(|priority| priority.as_string())
+                .map(|priority| priority.as_str()),
+            Some("High")
+        );
+
+        let unknown = FieldUpdate {
+            field: "unknown".to_string(),
+            value: IqlValue::Null,
+        };
+        assert!(matches!(
+            unknown.apply_to::<Info>(&mut value),
+            Err(IqlError::FieldNotFound(field)) if field == "unknown"
+        ));
+    }
 }
@@ -53,7 +53,7 @@ fn print_help() {
         "  CREATE PROJECT <project-id> [WITH NAME '<name>' DESCRIPTION '<desc>' OWNER <username>]"
     );
     println!(
-        "  CREATE ISSUE IN <project> WITH TITLE '<title>' [DESCRIPTION '<desc>'] [PRIORITY <level>] [ASSIGNEE <user>]"
+        "  CREATE ISSUE IN <project> WITH TITLE '<title>' [DESCRIPTION '<desc>'] [PRIORITY <level>] [ASSIGNEE <user>] [LABELS (<label>, ...)]"
     );
     println!("  CREATE COMMENT ON ISSUE <id> WITH '<content>'");
     println!();
@@ -61,7 +61,7 @@ fn print_help() {
     println!("  SELECT * FROM <entity>");
     println!("  SELECT <col1>, <col2>, ... FROM <entity>");
     println!("  SELECT ... WHERE <condition>");
-    println!("  SELECT ... ORDER BY <field> [ASC|DESC]");
+    println!("  SELECT ... ORDER BY <field> [ASC|DESC] [NULLS FIRST|NULLS LAST]");
     println!("  SELECT ... LIMIT <n> [OFFSET <n>]");
     println!();
     println!("UPDATE Statements:");
@@ -72,10 +72,10 @@ fn print_help() {
     println!();
     println!("Other Statements:");
     println!("  ASSIGN ISSUE <id> TO <username>");
-    println!("  CLOSE ISSUE <id> [WITH '<reason>']");
-    println!("  COMMENT ON ISSUE <id> WITH '<content>'");
+    println!("  CLOSE ISSUE <id> [WITH DONE | WONTFIX ['<reason>'] | DUPLICATE [OF <issue_id>]]");
+    println!("  COMMENT ON ISSUE <id> WITH '<content>' [AUTHOR <user>]");
     println!();
-    println!("Entity Types: USER, PROJECT, ISSUE, USERS, PROJECTS, ISSUES, COMMENTS");
+    println!("Entity Types: USER, PROJECT, ISSUE, USERS, PROJECTS, ISSUES, COMMENTS, LINKS");
     println!("Priority Levels: critical, high, medium, low");
     println!("Issue ID format: <project#number> (e.g., 'PROJ#123')");
     println!();
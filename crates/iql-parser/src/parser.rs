@@ -1,34 +1,111 @@
+use std::ops::Range;
+
+use crate::ParserConfig;
 use crate::ast::{
-    AssignStatement, CloseReason, CloseStatement, Columns, CommentId, CommentStatement,
-    ComparisonOp, CreateStatement, DeleteStatement, DeleteTarget, EntityType, FieldUpdate,
-    FilterExpression, IqlQuery, IqlValue, IssueId, IssueKind, OrderBy, OrderDirection, Priority,
-    ProjectId, ReopenStatement, SelectStatement, UpdateStatement, UpdateTarget, UserId,
+    ArchiveStatement, ArchiveTarget, AssignStatement, COUNT_COLUMN, CloseReason, CloseStatement,
+    CloseTarget, Columns, CommentId, CommentStatement, ComparisonOp, CreateStatement,
+    DeleteStatement, DeleteTarget, EntityType, FieldUpdate, FilterExpression, ImportStatement,
+    IqlQuery, IqlValue, IssueId, IssueKind, Join, MoveStatement, NullsOrder, OnConflict, OrderBy,
+    OrderDirection, Priority, ProjectId, ReactionStatement, RenameStatement, ReopenStatement,
+    SelectStatement, SessionOption, StatementKind, UpdateStatement, UpdateTarget, UserId,
+    is_timestamp,
 };
 use crate::error::{ParseError, ParseResult};
-use crate::lexer::{Token, tokenize};
+use crate::lexer::{Token, char_offset, leading_tokens, tokenize_spanned};
+
+/// Tells the kind of `query` from its leading keywords, without parsing the rest. A statement
+/// behind `EXPLAIN` reports its own kind.
+pub fn statement_kind(query: &str) -> StatementKind {
+    let tokens = leading_tokens(query, 3);
+    let tokens = match tokens.as_slice() {
+        [Token::Explain, rest @ ..] => rest,
+        tokens => tokens,
+    };
+    match tokens {
+        [Token::Create, entity, ..] => StatementKind::Create(match entity {
+            Token::User => Some(EntityType::Users),
+            Token::Project => Some(EntityType::Projects),
+            Token::Issue | Token::Issues => Some(EntityType::Issues),
+            Token::Comment => Some(EntityType::Comments),
+            _ => None,
+        }),
+        [Token::Create] => StatementKind::Create(None),
+        [Token::Select, ..] => StatementKind::Select,
+        [Token::Update, ..] => StatementKind::Update,
+        [Token::Delete, ..] => StatementKind::Delete,
+        [Token::Assign, ..] => StatementKind::Assign,
+        [Token::Close, ..] => StatementKind::Close,
+        [Token::Reopen, ..] => StatementKind::Reopen,
+        [Token::Move, ..] => StatementKind::Move,
+        [Token::Rename, ..] => StatementKind::Rename,
+        [Token::Archive, ..] => StatementKind::Archive,
+        [Token::Unarchive, ..] => StatementKind::Unarchive,
+        [Token::Comment, ..] => StatementKind::Comment,
+        [Token::React, ..] => StatementKind::React,
+        [Token::Unreact, ..] => StatementKind::Unreact,
+        [Token::Reindex, ..] => StatementKind::Reindex,
+        [Token::Import, ..] => StatementKind::Import,
+        [Token::History, ..] => StatementKind::History,
+        [Token::Describe, ..] => StatementKind::Describe,
+        [Token::Begin, ..] => StatementKind::Begin,
+        [Token::Commit, ..] => StatementKind::Commit,
+        [Token::Rollback, ..] => StatementKind::Rollback,
+        [Token::Set, ..] => StatementKind::Set,
+        _ => StatementKind::Unknown,
+    }
+}
 
 pub struct Parser {
+    input: String,
     tokens: Vec<Token>,
+    /// The bytes of `input` each of `tokens` was read from.
+    spans: Vec<Range<usize>>,
     position: usize,
+    default_order_direction: OrderDirection,
 }
 
 impl Parser {
+    #[cfg(test)]
     pub fn new(input: &str) -> Self {
-        let tokens = tokenize(input).unwrap_or_else(|_| vec![Token::Eof]);
-        Parser {
+        Self::with_config(input, &ParserConfig::default()).unwrap()
+    }
+
+    pub fn with_config(input: &str, config: &ParserConfig) -> ParseResult<Self> {
+        let (tokens, spans) = tokenize_spanned(input)?.into_iter().unzip();
+        Ok(Parser {
+            input: input.to_string(),
             tokens,
+            spans,
             position: 0,
-        }
+            default_order_direction: config.default_order_direction.clone(),
+        })
     }
 
+    /// The character offset in the input of the current token.
     fn get_position_for_error(&self) -> usize {
-        self.position + 1
+        let start = self
+            .spans
+            .get(self.position)
+            .map_or(self.input.len(), |span| span.start);
+        char_offset(&self.input, start)
+    }
+
+    /// The current token as it was written in the input, for error messages.
+    fn current_text(&self) -> String {
+        match self.spans.get(self.position) {
+            Some(span) if !span.is_empty() => self.input[span.clone()].to_string(),
+            _ => "end of input".to_string(),
+        }
     }
 
     fn current(&self) -> &Token {
         self.tokens.get(self.position).unwrap_or(&Token::Eof)
     }
 
+    fn peek(&self) -> &Token {
+        self.tokens.get(self.position + 1).unwrap_or(&Token::Eof)
+    }
+
     fn advance(&mut self) {
         if self.position < self.tokens.len() {
             self.position += 1;
@@ -42,7 +119,7 @@ impl Parser {
         } else {
             Err(ParseError::UnexpectedToken {
                 expected: format!("{expected:?}"),
-                found: format!("{:?}", self.current()),
+                found: self.current_text(),
                 position: self.get_position_for_error(),
             })
         }
@@ -66,16 +143,70 @@ impl Parser {
             Token::Assign => self.parse_assign(),
             Token::Close => self.parse_close(),
             Token::Reopen => self.parse_reopen(),
+            Token::Move => self.parse_move(),
+            Token::Rename => self.parse_rename(),
+            Token::Archive => self.parse_archive(&Token::Archive).map(IqlQuery::Archive),
+            Token::Unarchive => self
+                .parse_archive(&Token::Unarchive)
+                .map(IqlQuery::Unarchive),
             Token::Comment => self.parse_comment(),
+            Token::React => self.parse_reaction(&Token::React).map(IqlQuery::React),
+            Token::Unreact => self.parse_reaction(&Token::Unreact).map(IqlQuery::Unreact),
+            Token::Reindex => {
+                self.advance();
+                Ok(IqlQuery::Reindex)
+            }
+            Token::Import => self.parse_import(),
+            Token::Begin => {
+                self.advance();
+                Ok(IqlQuery::Begin)
+            }
+            Token::Commit => {
+                self.advance();
+                Ok(IqlQuery::Commit)
+            }
+            Token::Rollback => {
+                self.advance();
+                Ok(IqlQuery::Rollback)
+            }
+            Token::History => {
+                self.advance();
+                self.expect(&Token::Issue)?;
+                Ok(IqlQuery::History(self.parse_issue_id()?))
+            }
+            Token::Describe => {
+                self.advance();
+                Ok(IqlQuery::Describe(self.parse_entity_type()?))
+            }
+            Token::Explain => {
+                self.advance();
+                Ok(IqlQuery::Explain(Box::new(self.parse()?)))
+            }
+            Token::Set => self.parse_set(),
             Token::Eof => Err(ParseError::UnexpectedEof),
             _ => Err(ParseError::UnexpectedToken {
                 expected: "statement keyword".to_string(),
-                found: format!("{:?}", self.current()),
+                found: self.current_text(),
                 position: self.get_position_for_error(),
             }),
         }
     }
 
+    /// Parses any number of statements separated by semicolons.
+    pub fn parse_all(&mut self) -> ParseResult<Vec<IqlQuery>> {
+        let mut queries = Vec::new();
+        loop {
+            while self.match_token(&Token::Semicolon) {}
+            if matches!(self.current(), Token::Eof) {
+                return Ok(queries);
+            }
+            queries.push(self.parse()?);
+            if !matches!(self.current(), Token::Eof) {
+                self.expect(&Token::Semicolon)?;
+            }
+        }
+    }
+
     fn parse_create(&mut self) -> ParseResult<IqlQuery> {
         self.expect(&Token::Create)?;
 
@@ -83,9 +214,12 @@ impl Parser {
             Token::User => self.parse_create_user(),
             Token::Project => self.parse_create_project(),
             Token::Issue => self.parse_create_issue(),
+            Token::Issues => self.parse_create_issues(),
+            // `CREATE COMMENT ON ISSUE ...` is an alias for `COMMENT ON ISSUE ...`
+            Token::Comment => self.parse_comment(),
             _ => Err(ParseError::UnexpectedToken {
-                expected: "USER, PROJECT or ISSUE".to_string(),
-                found: format!("{:?}", self.current()),
+                expected: "USER, PROJECT, ISSUE, ISSUES or COMMENT".to_string(),
+                found: self.current_text(),
                 position: self.get_position_for_error(),
             }),
         }
@@ -165,6 +299,7 @@ impl Parser {
                     Token::Owner => {
                         self.advance();
                         owner = Some(UserId::new(&self.parse_identifier("OWNER")?));
+                        started = false;
                     }
                     Token::Identifier(id) if id.eq_ignore_ascii_case("name") => {
                         self.advance();
@@ -192,14 +327,32 @@ impl Parser {
             }
         }
 
+        let on_conflict = if self.match_token(&Token::On) {
+            Some(self.parse_on_conflict()?)
+        } else {
+            None
+        };
+
         Ok(IqlQuery::Create(CreateStatement::Project {
             project_id,
             name,
             description,
             owner,
+            on_conflict,
         }))
     }
 
+    fn parse_on_conflict(&mut self) -> ParseResult<OnConflict> {
+        self.expect_word("CONFLICT")?;
+        if self.match_token(&Token::Update) {
+            self.expect(&Token::Set)?;
+            return Ok(OnConflict::Update(self.parse_field_updates()?));
+        }
+        self.expect_word("DO")?;
+        self.expect_word("NOTHING")?;
+        Ok(OnConflict::DoNothing)
+    }
+
     fn parse_create_issue(&mut self) -> ParseResult<IqlQuery> {
         self.expect(&Token::Issue)?;
         self.expect(&Token::Of)?;
@@ -221,6 +374,7 @@ impl Parser {
         let mut description = None;
         let mut priority = None;
         let mut assignee = None;
+        let mut labels = Vec::new();
 
         loop {
             match self.current() {
@@ -256,6 +410,10 @@ impl Parser {
                     self.advance();
                     assignee = Some(UserId::new(&self.parse_identifier("ASSIGNEE_ID")?));
                 }
+                Token::Identifier(id) if id.eq_ignore_ascii_case("labels") => {
+                    self.advance();
+                    labels = self.parse_string_list("LABEL")?;
+                }
                 _ => break,
             }
         }
@@ -272,6 +430,7 @@ impl Parser {
             priority,
             assignee,
             kind,
+            labels,
         }))
     }
 
@@ -284,39 +443,246 @@ impl Parser {
 
         let from = self.parse_entity_type()?;
 
-        let filter = if self.match_token(&Token::Where) {
-            Some(self.parse_filter_expression()?)
+        let from_alias = self.parse_entity_alias()?;
+
+        let include_archived = self.match_including_archived()?;
+
+        let join = if self.match_token(&Token::Join) {
+            let entity = self.parse_entity_type()?;
+            let alias = self.parse_entity_alias()?;
+            self.expect(&Token::On)?;
+            let left_field = self.parse_field_reference(|p| p.parse_identifier("FIELD"))?;
+            self.expect(&Token::Equal)?;
+            let right_field = self.parse_field_reference(|p| p.parse_identifier("FIELD"))?;
+            Some(Join {
+                entity,
+                alias,
+                left_field,
+                right_field,
+            })
         } else {
             None
         };
 
-        let order_by = if self.match_token(&Token::Order) {
-            self.expect(&Token::By)?;
-            Some(self.parse_order_by()?)
+        let filter = if self.match_token(&Token::Where) {
+            Some(self.parse_filter_expression()?)
         } else {
             None
         };
 
-        let limit = if self.match_token(&Token::Limit) {
-            Some(self.parse_unsigned_integer()?)
+        let group_by = if self.match_token(&Token::Group) {
+            self.expect(&Token::By)?;
+            Some(self.parse_field_reference(|p| p.parse_identifier("FIELD"))?)
         } else {
             None
         };
 
-        let offset = if self.match_token(&Token::Offset) {
-            Some(self.parse_unsigned_integer()?)
+        let order_by = if self.match_token(&Token::Order) {
+            self.expect(&Token::By)?;
+            Some(self.parse_order_by()?)
         } else {
             None
         };
 
-        Ok(IqlQuery::Select(SelectStatement {
+        // `AFTER`, `LIMIT` and `OFFSET` may come in any order, but each at most once
+        let mut after = None;
+        let mut limit = None;
+        let mut offset = None;
+        loop {
+            match self.current() {
+                Token::Identifier(id) if id.eq_ignore_ascii_case("after") => {
+                    self.reject_duplicate_clause(after.is_some(), "AFTER")?;
+                    self.advance();
+                    after = Some(self.parse_string_value("AFTER")?);
+                }
+                Token::Limit => {
+                    self.reject_duplicate_clause(limit.is_some(), "LIMIT")?;
+                    self.advance();
+                    limit = Some(self.parse_unsigned_integer()?);
+                }
+                Token::Offset => {
+                    self.reject_duplicate_clause(offset.is_some(), "OFFSET")?;
+                    self.advance();
+                    offset = Some(self.parse_unsigned_integer()?);
+                }
+                _ => break,
+            }
+        }
+        if after.is_some() && order_by.is_some() {
+            return Err(ParseError::InvalidSyntax {
+                message: "AFTER pages in key order and cannot be combined with ORDER BY"
+                    .to_string(),
+                position: self.get_position_for_error(),
+            });
+        }
+
+        let mut select = SelectStatement {
             columns,
             from,
+            from_alias,
+            join,
             filter,
+            group_by,
             order_by,
+            after,
+            include_archived,
             limit,
             offset,
-        }))
+        };
+        self.resolve_qualified_fields(&mut select)?;
+        Ok(IqlQuery::Select(select))
+    }
+
+    /// Consumes `word`, a keyword that only has a meaning in one clause and is therefore lexed
+    /// as an identifier.
+    fn expect_word(&mut self, word: &str) -> ParseResult<()> {
+        match self.current() {
+            Token::Identifier(id) if id.eq_ignore_ascii_case(word) => {
+                self.advance();
+                Ok(())
+            }
+            _ => Err(ParseError::UnexpectedToken {
+                expected: word.to_string(),
+                found: self.current_text(),
+                position: self.get_position_for_error(),
+            }),
+        }
+    }
+
+    /// Consumes `INCLUDING ARCHIVED` if it comes next.
+    fn match_including_archived(&mut self) -> ParseResult<bool> {
+        match self.current() {
+            Token::Identifier(id) if id.eq_ignore_ascii_case("including") => {
+                self.advance();
+                match self.current() {
+                    Token::Identifier(id) if id.eq_ignore_ascii_case("archived") => {
+                        self.advance();
+                        Ok(true)
+                    }
+                    _ => Err(ParseError::UnexpectedToken {
+                        expected: "ARCHIVED".to_string(),
+                        found: self.current_text(),
+                        position: self.get_position_for_error(),
+                    }),
+                }
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn reject_duplicate_clause(&self, seen: bool, clause: &str) -> ParseResult<()> {
+        if seen {
+            return Err(ParseError::DuplicateClause {
+                clause: clause.to_string(),
+                position: self.get_position_for_error(),
+            });
+        }
+        Ok(())
+    }
+
+    fn parse_entity_alias(&mut self) -> ParseResult<Option<String>> {
+        if self.match_token(&Token::As) {
+            return Ok(Some(self.parse_identifier("ALIAS")?));
+        }
+        // `AFTER '<id>'` and `INCLUDING ARCHIVED` directly after the entity aren't names of it
+        if let Token::Identifier(alias) = self.current()
+            && !(alias.eq_ignore_ascii_case("after") && matches!(self.peek(), Token::String(_)))
+            && !(alias.eq_ignore_ascii_case("including")
+                && matches!(self.peek(), Token::Identifier(next) if next.eq_ignore_ascii_case("archived")))
+        {
+            let alias = alias.clone();
+            self.advance();
+            return Ok(Some(alias));
+        }
+        Ok(None)
+    }
+
+    /// Strips `entity.` or `alias.` qualifiers that refer to the selected entity.
+    ///
+    /// Fields of a joined entity are normalized to `<entity>.<field>`, regardless of whether
+    /// they were qualified by the entity name or its alias.
+    fn resolve_qualified_fields(&self, select: &mut SelectStatement) -> ParseResult<()> {
+        let matches_entity = |qualifier: &str, entity: EntityType, alias: Option<&str>| {
+            qualifier.eq_ignore_ascii_case(&entity.to_string()) || Some(qualifier) == alias
+        };
+        let from = select.from;
+        let from_alias = select.from_alias.clone();
+        let join = select
+            .join
+            .as_ref()
+            .map(|join| (join.entity, join.alias.clone()));
+        let resolve = |field: &mut String| -> ParseResult<()> {
+            if let Some((qualifier, name)) = field.split_once('.') {
+                if matches_entity(qualifier, from, from_alias.as_deref()) {
+                    *field = name.to_string();
+                } else if let Some((entity, alias)) = &join
+                    && matches_entity(qualifier, *entity, alias.as_deref())
+                {
+                    *field = format!("{}.{name}", entity.to_string().to_lowercase());
+                } else {
+                    return Err(ParseError::InvalidIdentifier {
+                        value: field.clone(),
+                        position: self.get_position_for_error(),
+                    });
+                }
+            }
+            Ok(())
+        };
+
+        if let Columns::Named(columns) = &mut select.columns {
+            for (column, _) in columns {
+                resolve(column)?;
+            }
+        }
+        if let Some(join) = &mut select.join {
+            resolve(&mut join.left_field)?;
+            resolve(&mut join.right_field)?;
+            let prefix = format!("{}.", join.entity.to_string().to_lowercase());
+            if join.left_field.starts_with(&prefix) {
+                std::mem::swap(&mut join.left_field, &mut join.right_field);
+            }
+            match join.right_field.strip_prefix(&prefix) {
+                Some(right_field) if !join.left_field.contains('.') => {
+                    join.right_field = right_field.to_string();
+                }
+                _ => {
+                    return Err(ParseError::InvalidSyntax {
+                        message: "JOIN condition must compare a field of each entity".to_string(),
+                        position: self.get_position_for_error(),
+                    });
+                }
+            }
+        }
+        if let Some(filter) = &mut select.filter {
+            filter.try_for_each_field_mut(&mut |field| resolve(field))?;
+        }
+        if let Some(group_by) = &mut select.group_by {
+            resolve(group_by)?;
+        }
+        if let Some(order_by) = &mut select.order_by {
+            resolve(&mut order_by.field)?;
+        }
+        Ok(())
+    }
+
+    /// Parses a field that may be qualified by an entity name or alias, like `issues.title`.
+    fn parse_field_reference(
+        &mut self,
+        parse_field: fn(&mut Self) -> ParseResult<String>,
+    ) -> ParseResult<String> {
+        if self.peek() != &Token::Dot {
+            return parse_field(self);
+        }
+        let qualifier = match self.current() {
+            Token::Users | Token::Projects | Token::Issues | Token::Comments => {
+                let entity = self.parse_entity_type()?;
+                entity.to_string().to_lowercase()
+            }
+            _ => self.parse_identifier("QUALIFIER")?,
+        };
+        self.expect(&Token::Dot)?;
+        let field = self.parse_identifier("FIELD")?;
+        Ok(format!("{qualifier}.{field}"))
     }
 
     fn parse_columns(&mut self) -> ParseResult<Columns> {
@@ -326,8 +692,24 @@ impl Parser {
 
         let mut columns = Vec::new();
         loop {
-            let col = self.parse_identifier("COLUMN")?;
-            columns.push(col);
+            let col = if let Token::Identifier(name) = self.current()
+                && name.eq_ignore_ascii_case("count")
+                && self.peek() == &Token::LeftParen
+            {
+                self.advance();
+                self.expect(&Token::LeftParen)?;
+                self.expect(&Token::Star)?;
+                self.expect(&Token::RightParen)?;
+                COUNT_COLUMN.to_string()
+            } else {
+                self.parse_field_reference(|p| p.parse_identifier("COLUMN"))?
+            };
+            let alias = if self.match_token(&Token::As) {
+                Some(self.parse_identifier("ALIAS")?)
+            } else {
+                None
+            };
+            columns.push((col, alias));
 
             if !self.match_token(&Token::Comma) {
                 break;
@@ -343,9 +725,12 @@ impl Parser {
             Token::Projects => EntityType::Projects,
             Token::Issues => EntityType::Issues,
             Token::Comments => EntityType::Comments,
+            Token::Links => EntityType::Links,
+            Token::Identifier(name) if name.eq_ignore_ascii_case("meta") => EntityType::Meta,
+            Token::Identifier(name) if name.eq_ignore_ascii_case("audit") => EntityType::Audit,
             _ => {
                 return Err(ParseError::InvalidEntityType {
-                    value: format!("{:?}", self.current()),
+                    value: self.current_text(),
                     position: self.get_position_for_error(),
                 });
             }
@@ -406,7 +791,33 @@ impl Parser {
             return Ok(expr);
         }
 
-        let field = self.parse_field_name()?;
+        // `OPEN` and `CLOSED` on their own are shorthands for filtering on the status
+        if let Token::Identifier(id) = self.current()
+            && let Some(status) = ["Open", "Closed"]
+                .into_iter()
+                .find(|status| status.eq_ignore_ascii_case(id))
+            && (matches!(
+                self.peek(),
+                Token::And
+                    | Token::Or
+                    | Token::RightParen
+                    | Token::Order
+                    | Token::Group
+                    | Token::Limit
+                    | Token::Offset
+                    | Token::Semicolon
+                    | Token::Eof
+            ) || matches!(self.peek(), Token::Identifier(next) if next.eq_ignore_ascii_case("after")))
+        {
+            self.advance();
+            return Ok(FilterExpression::Comparison {
+                field: "status".to_string(),
+                op: ComparisonOp::Equal,
+                value: IqlValue::String(status.to_string()),
+            });
+        }
+
+        let field = self.parse_field_reference(Self::parse_field_name)?;
 
         if self.match_token(&Token::Is) {
             if self.match_token(&Token::Not) {
@@ -417,18 +828,28 @@ impl Parser {
             }
             return Err(ParseError::UnexpectedToken {
                 expected: "NULL or NOT NULL".to_string(),
-                found: format!("{:?}", self.current()),
+                found: self.current_text(),
                 position: self.get_position_for_error(),
             });
         }
 
         if self.match_token(&Token::In) {
             self.expect(&Token::LeftParen)?;
-            let values = self.parse_value_list()?;
+            // An empty list is allowed and never matches
+            let values = if self.current() == &Token::RightParen {
+                Vec::new()
+            } else {
+                self.parse_value_list()?
+            };
             self.expect(&Token::RightParen)?;
             return Ok(FilterExpression::In { field, values });
         }
 
+        if self.match_token(&Token::Has) {
+            let value = self.parse_value()?;
+            return Ok(FilterExpression::Has { field, value });
+        }
+
         let op = self.parse_comparison_op()?;
         let value = self.parse_value()?;
         Ok(FilterExpression::Comparison { field, op, value })
@@ -441,7 +862,7 @@ impl Parser {
         } else {
             Err(ParseError::UnexpectedToken {
                 expected: "field name".to_string(),
-                found: format!("{:?}", self.current()),
+                found: self.current_text(),
                 position: self.get_position_for_error(),
             })
         }
@@ -451,6 +872,8 @@ impl Parser {
         let op = match self.current() {
             Token::Equal => ComparisonOp::Equal,
             Token::NotEqual => ComparisonOp::NotEqual,
+            Token::EqualIgnoreCase => ComparisonOp::EqualIgnoreCase,
+            Token::NotEqualIgnoreCase => ComparisonOp::NotEqualIgnoreCase,
             Token::GreaterThan => ComparisonOp::GreaterThan,
             Token::LessThan => ComparisonOp::LessThan,
             Token::GreaterOrEqual => ComparisonOp::GreaterThanOrEqual,
@@ -459,7 +882,7 @@ impl Parser {
             _ => {
                 return Err(ParseError::UnexpectedToken {
                     expected: "comparison operator".to_string(),
-                    found: format!("{:?}", self.current()),
+                    found: self.current_text(),
                     position: self.get_position_for_error(),
                 });
             }
@@ -469,27 +892,159 @@ impl Parser {
     }
 
     fn parse_order_by(&mut self) -> ParseResult<OrderBy> {
-        let field = self.parse_identifier("FIELD")?;
+        let field = self.parse_field_reference(|p| p.parse_identifier("FIELD"))?;
 
         let direction = if self.match_token(&Token::Desc) {
             OrderDirection::Desc
-        } else {
-            self.match_token(&Token::Asc);
+        } else if self.match_token(&Token::Asc) {
             OrderDirection::Asc
+        } else {
+            self.default_order_direction.clone()
+        };
+
+        let nulls = match self.current() {
+            Token::Identifier(id) if id.eq_ignore_ascii_case("nulls") => {
+                self.advance();
+                Some(self.parse_nulls_order()?)
+            }
+            _ => None,
+        };
+
+        Ok(OrderBy {
+            field,
+            direction,
+            nulls,
+        })
+    }
+
+    fn parse_nulls_order(&mut self) -> ParseResult<NullsOrder> {
+        let nulls = match self.current() {
+            Token::Identifier(id) if id.eq_ignore_ascii_case("first") => NullsOrder::First,
+            Token::Identifier(id) if id.eq_ignore_ascii_case("last") => NullsOrder::Last,
+            Token::Eof => return Err(ParseError::UnexpectedEof),
+            _ => {
+                return Err(ParseError::UnexpectedToken {
+                    expected: "FIRST or LAST".to_string(),
+                    found: self.current_text(),
+                    position: self.get_position_for_error(),
+                });
+            }
+        };
+        self.advance();
+        Ok(nulls)
+    }
+
+    /// `CREATE ISSUES [OF KIND <kind>] IN <project> WITH TITLES ['<title>', ...]`. Issues
+    /// without a kind are tasks, as is common for the items of a checklist.
+    fn parse_create_issues(&mut self) -> ParseResult<IqlQuery> {
+        self.expect(&Token::Issues)?;
+        let kind = if self.match_token(&Token::Of) {
+            self.expect(&Token::Kind)?;
+            self.parse_issue_kind()?
+        } else {
+            IssueKind::Task
+        };
+        self.expect(&Token::In)?;
+        let project = ProjectId::new(&self.parse_identifier("PROJECT_ID")?);
+        if !self.match_token(&Token::With) {
+            return Err(ParseError::MissingClause {
+                clause: "WITH".to_string(),
+                position: self.get_position_for_error(),
+            });
+        }
+        self.expect_word("titles")?;
+        let titles =
+            self.parse_delimited_strings("TITLE", &Token::LeftBracket, &Token::RightBracket)?;
+        if titles.is_empty() {
+            return Err(ParseError::MissingClause {
+                clause: "TITLE".to_string(),
+                position: self.get_position_for_error(),
+            });
+        }
+        Ok(IqlQuery::Create(CreateStatement::Issues {
+            project,
+            kind,
+            titles,
+        }))
+    }
+
+    fn parse_set(&mut self) -> ParseResult<IqlQuery> {
+        self.expect(&Token::Set)?;
+        let position = self.get_position_for_error();
+        let option = self.parse_identifier("OPTION")?.to_lowercase();
+        self.expect(&Token::Equal)?;
+        let option = match option.as_str() {
+            "default_limit" if self.match_token(&Token::Null) => SessionOption::DefaultLimit(None),
+            "default_limit" => SessionOption::DefaultLimit(Some(self.parse_unsigned_integer()?)),
+            "nulls" => match self.current() {
+                Token::Identifier(id) if id.eq_ignore_ascii_case("default") => {
+                    self.advance();
+                    SessionOption::Nulls(None)
+                }
+                _ => SessionOption::Nulls(Some(self.parse_nulls_order()?)),
+            },
+            "case_sensitive" => {
+                let case_sensitive = match self.current() {
+                    Token::True => true,
+                    Token::False => false,
+                    _ => {
+                        return Err(ParseError::UnexpectedToken {
+                            expected: "TRUE or FALSE".to_string(),
+                            found: self.current_text(),
+                            position: self.get_position_for_error(),
+                        });
+                    }
+                };
+                self.advance();
+                SessionOption::CaseSensitive(case_sensitive)
+            }
+            _ => {
+                return Err(ParseError::UnexpectedToken {
+                    expected: "default_limit, nulls or case_sensitive".to_string(),
+                    found: option,
+                    position,
+                });
+            }
         };
+        Ok(IqlQuery::Set(option))
+    }
 
-        Ok(OrderBy { field, direction })
+    fn parse_import(&mut self) -> ParseResult<IqlQuery> {
+        self.expect(&Token::Import)?;
+        let Token::String(dump) = self.current() else {
+            return Err(ParseError::UnexpectedToken {
+                expected: "string literal for <DUMP>".to_string(),
+                found: self.current_text(),
+                position: self.get_position_for_error(),
+            });
+        };
+        let dump = dump.clone();
+        self.advance();
+        let merge = match self.current() {
+            Token::Identifier(id) if id.eq_ignore_ascii_case("merge") => {
+                self.advance();
+                true
+            }
+            _ => false,
+        };
+        Ok(IqlQuery::Import(ImportStatement { dump, merge }))
     }
 
     fn parse_update(&mut self) -> ParseResult<IqlQuery> {
         self.expect(&Token::Update)?;
 
-        let entity = self.parse_update_target()?;
+        let mut entity = self.parse_update_target()?;
 
         self.expect(&Token::Set)?;
 
         let updates = self.parse_field_updates()?;
 
+        if let UpdateTarget::Bulk { filter, .. } = &mut entity
+            && self.match_token(&Token::Where)
+        {
+            *filter = Some(self.parse_filter_expression()?);
+        }
+
         Ok(IqlQuery::Update(UpdateStatement { entity, updates }))
     }
 
@@ -512,13 +1067,18 @@ impl Parser {
             }
             Token::Comment => {
                 self.advance();
-                let comment_id = self.parse_identifier("COMMENT")?;
-                UpdateTarget::Comment(CommentId::new(&comment_id))
+                UpdateTarget::Comment(self.parse_comment_id()?)
+            }
+            Token::Users | Token::Projects | Token::Issues | Token::Comments => {
+                UpdateTarget::Bulk {
+                    entity: self.parse_entity_type()?,
+                    filter: None,
+                }
             }
             _ => {
                 return Err(ParseError::UnexpectedToken {
-                    expected: "USER, PROJECT, ISSUE, or COMMENT".to_string(),
-                    found: format!("{:?}", self.current()),
+                    expected: "USER, PROJECT, ISSUE, COMMENT or an entity type".to_string(),
+                    found: self.current_text(),
                     position: self.get_position_for_error(),
                 });
             }
@@ -572,13 +1132,41 @@ impl Parser {
             }
             Token::Comment => {
                 self.advance();
-                let id = self.parse_identifier("COMMENT")?;
-                DeleteTarget::Comment(CommentId::new(&id))
+                DeleteTarget::Comment(self.parse_comment_id()?)
+            }
+            // Wiping a whole table has to be asked for explicitly
+            Token::All => {
+                self.advance();
+                let position = self.get_position_for_error();
+                let entity = self.parse_entity_type()?;
+                if matches!(entity, EntityType::Meta | EntityType::Audit) {
+                    return Err(ParseError::InvalidEntityType {
+                        value: entity.to_string(),
+                        position,
+                    });
+                }
+                DeleteTarget::Bulk {
+                    entity,
+                    filter: None,
+                }
+            }
+            Token::Users | Token::Projects | Token::Issues | Token::Comments => {
+                let entity = self.parse_entity_type()?;
+                if !self.match_token(&Token::Where) {
+                    return Err(ParseError::MissingClause {
+                        clause: "WHERE".to_string(),
+                        position: self.get_position_for_error(),
+                    });
+                }
+                DeleteTarget::Bulk {
+                    entity,
+                    filter: Some(self.parse_filter_expression()?),
+                }
             }
             _ => {
                 return Err(ParseError::UnexpectedToken {
-                    expected: "USER, PROJECT, ISSUE, or COMMENT".to_string(),
-                    found: format!("{:?}", self.current()),
+                    expected: "USER, PROJECT, ISSUE, COMMENT, ALL or an entity type".to_string(),
+                    found: self.current_text(),
                     position: self.get_position_for_error(),
                 });
             }
@@ -602,9 +1190,21 @@ impl Parser {
 
     fn parse_close(&mut self) -> ParseResult<IqlQuery> {
         self.expect(&Token::Close)?;
-        self.expect(&Token::Issue)?;
 
-        let issue_id = self.parse_issue_id()?;
+        let target = if self.match_token(&Token::Issues) {
+            if !self.match_token(&Token::Where) {
+                return Err(ParseError::MissingClause {
+                    clause: "WHERE".to_string(),
+                    position: self.get_position_for_error(),
+                });
+            }
+            CloseTarget::Bulk {
+                filter: self.parse_filter_expression()?,
+            }
+        } else {
+            self.expect(&Token::Issue)?;
+            CloseTarget::Issue(self.parse_issue_id()?)
+        };
 
         let reason = if self.match_token(&Token::With) {
             Some(self.parse_close_reason()?)
@@ -612,7 +1212,7 @@ impl Parser {
             None
         };
 
-        Ok(IqlQuery::Close(CloseStatement { issue_id, reason }))
+        Ok(IqlQuery::Close(CloseStatement { target, reason }))
     }
 
     fn parse_reopen(&mut self) -> ParseResult<IqlQuery> {
@@ -620,8 +1220,62 @@ impl Parser {
         self.expect(&Token::Issue)?;
 
         let issue_id = self.parse_issue_id()?;
+        let comment = if self.match_token(&Token::With) {
+            Some(self.parse_string_value("COMMENT")?)
+        } else {
+            None
+        };
+
+        Ok(IqlQuery::Reopen(ReopenStatement { issue_id, comment }))
+    }
+
+    fn parse_move(&mut self) -> ParseResult<IqlQuery> {
+        self.expect(&Token::Move)?;
+        self.expect(&Token::Issue)?;
+
+        let issue_id = self.parse_issue_id()?;
+
+        self.expect(&Token::To)?;
+
+        let project = ProjectId::new(&self.parse_identifier("PROJECT_ID")?);
+
+        Ok(IqlQuery::Move(MoveStatement { issue_id, project }))
+    }
+
+    fn parse_rename(&mut self) -> ParseResult<IqlQuery> {
+        self.expect(&Token::Rename)?;
+        self.expect(&Token::Project)?;
+
+        let project = ProjectId::new(&self.parse_identifier("PROJECT_ID")?);
+
+        self.expect(&Token::To)?;
+
+        let new_id = ProjectId::new(&self.parse_identifier("PROJECT_ID")?);
 
-        Ok(IqlQuery::Reopen(ReopenStatement { issue_id }))
+        Ok(IqlQuery::Rename(RenameStatement { project, new_id }))
+    }
+
+    fn parse_archive(&mut self, keyword: &Token) -> ParseResult<ArchiveStatement> {
+        self.expect(keyword)?;
+        let target = match self.current() {
+            Token::Issue => {
+                self.advance();
+                ArchiveTarget::Issue(self.parse_issue_id()?)
+            }
+            Token::Project => {
+                self.advance();
+                let project = self.parse_identifier("PROJECT")?;
+                ArchiveTarget::Project(ProjectId::new(&project))
+            }
+            _ => {
+                return Err(ParseError::UnexpectedToken {
+                    expected: "ISSUE or PROJECT".to_string(),
+                    found: self.current_text(),
+                    position: self.get_position_for_error(),
+                });
+            }
+        };
+        Ok(ArchiveStatement { target })
     }
 
     fn parse_comment(&mut self) -> ParseResult<IqlQuery> {
@@ -635,23 +1289,72 @@ impl Parser {
 
         let content = self.parse_string_value("CONTENT")?;
 
-        Ok(IqlQuery::Comment(CommentStatement { issue_id, content }))
+        let author = match self.current() {
+            Token::Identifier(id) if id.eq_ignore_ascii_case("author") => {
+                self.advance();
+                Some(UserId::new(&self.parse_identifier("AUTHOR")?))
+            }
+            _ => None,
+        };
+
+        Ok(IqlQuery::Comment(CommentStatement {
+            issue_id,
+            content,
+            author,
+        }))
+    }
+
+    fn parse_reaction(&mut self, keyword: &Token) -> ParseResult<ReactionStatement> {
+        self.expect(keyword)?;
+        self.expect(&Token::To)?;
+        self.expect(&Token::Comment)?;
+
+        let comment_id = self.parse_comment_id()?;
+
+        self.expect(&Token::With)?;
+
+        let reaction = self.parse_string_value("REACTION")?;
+
+        Ok(ReactionStatement {
+            comment_id,
+            reaction,
+        })
     }
 
     fn parse_close_reason(&mut self) -> ParseResult<CloseReason> {
-        let priority = match self.current() {
-            Token::Duplicate => CloseReason::Duplicate,
-            Token::WontFix => CloseReason::WontFix,
-            Token::Done => CloseReason::Done,
+        let reason = match self.current() {
+            Token::Duplicate => {
+                self.advance();
+                let of = if self.match_token(&Token::Of) {
+                    Some(self.parse_issue_id()?)
+                } else {
+                    None
+                };
+                CloseReason::Duplicate { of }
+            }
+            Token::WontFix => {
+                self.advance();
+                let reason = if let Token::String(reason) = self.current() {
+                    let reason = reason.clone();
+                    self.advance();
+                    Some(reason)
+                } else {
+                    None
+                };
+                CloseReason::WontFix { reason }
+            }
+            Token::Done => {
+                self.advance();
+                CloseReason::Done
+            }
             _ => {
                 return Err(ParseError::InvalidCloseReason {
-                    value: format!("{:?}", self.current()),
+                    value: self.current_text(),
                     position: self.get_position_for_error(),
                 });
             }
         };
-        self.advance();
-        Ok(priority)
+        Ok(reason)
     }
 
     fn parse_issue_id(&mut self) -> ParseResult<IssueId> {
@@ -671,11 +1374,23 @@ impl Parser {
 
         Err(ParseError::UnexpectedToken {
             expected: "issue ID (project#number)".to_string(),
-            found: format!("{:?}", self.current()),
+            found: self.current_text(),
             position: self.get_position_for_error(),
         })
     }
 
+    /// Comments are numbered within their issue (`project#1/c3`). Plain identifiers are still
+    /// accepted for comments created before the numbering was introduced.
+    fn parse_comment_id(&mut self) -> ParseResult<CommentId> {
+        if self.peek() == &Token::Hash {
+            let issue_id = self.parse_issue_id()?;
+            self.expect(&Token::Slash)?;
+            let number = self.parse_identifier("COMMENT_NUMBER")?;
+            return Ok(CommentId::new(&format!("{}/{number}", &*issue_id)));
+        }
+        Ok(CommentId::new(&self.parse_identifier("COMMENT")?))
+    }
+
     fn parse_issue_kind(&mut self) -> ParseResult<IssueKind> {
         let kind = match self.current() {
             Token::Epic => IssueKind::Epic,
@@ -684,7 +1399,7 @@ impl Parser {
             Token::Task => IssueKind::Task,
             _ => {
                 return Err(ParseError::InvalidIssueKind {
-                    value: format!("{:?}", self.current()),
+                    value: self.current_text(),
                     position: self.get_position_for_error(),
                 });
             }
@@ -701,7 +1416,7 @@ impl Parser {
             Token::Low => Priority::Low,
             _ => {
                 return Err(ParseError::InvalidPriority {
-                    value: format!("{:?}", self.current()),
+                    value: self.current_text(),
                     position: self.get_position_for_error(),
                 });
             }
@@ -713,7 +1428,11 @@ impl Parser {
     fn parse_value(&mut self) -> ParseResult<IqlValue> {
         match self.current() {
             Token::String(s) => {
-                let value = IqlValue::String(s.clone());
+                let value = if is_timestamp(s) {
+                    IqlValue::DateTime(s.clone())
+                } else {
+                    IqlValue::String(s.clone())
+                };
                 self.advance();
                 Ok(value)
             }
@@ -755,14 +1474,66 @@ impl Parser {
                 self.advance();
                 Ok(IqlValue::Priority(Priority::Low))
             }
+            Token::Epic | Token::Improvement | Token::Bug | Token::Task => {
+                Ok(IqlValue::Kind(self.parse_issue_kind()?))
+            }
+            Token::Identifier(_)
+            | Token::Users
+            | Token::Projects
+            | Token::Issues
+            | Token::Comments
+                if self.peek() == &Token::Dot =>
+            {
+                let field = self.parse_field_reference(|p| p.parse_identifier("FIELD"))?;
+                Ok(IqlValue::Field(field))
+            }
+            Token::Identifier(id)
+                if id.eq_ignore_ascii_case("date") && matches!(self.peek(), Token::String(_)) =>
+            {
+                self.advance();
+                self.parse_date()
+            }
+            // Bare identifiers like user ids compare as strings, unless `resolve_fields` finds
+            // that they name a field
+            Token::Identifier(id) => {
+                let value = IqlValue::Identifier(id.clone());
+                self.advance();
+                Ok(value)
+            }
             _ => Err(ParseError::UnexpectedToken {
                 expected: "literal".to_string(),
-                found: format!("{:?}", self.current()),
+                found: self.current_text(),
                 position: self.get_position_for_error(),
             }),
         }
     }
 
+    fn parse_date(&mut self) -> ParseResult<IqlValue> {
+        let position = self.get_position_for_error();
+        let date = self.parse_string_value("DATE")?;
+        let valid = match date.split('-').collect::<Vec<_>>().as_slice() {
+            [year, month, day]
+                if [year, month, day]
+                    .iter()
+                    .all(|part| part.bytes().all(|b| b.is_ascii_digit())) =>
+            {
+                year.len() == 4
+                    && month.len() == 2
+                    && day.len() == 2
+                    && month.parse::<u8>().is_ok_and(|m| (1..=12).contains(&m))
+                    && day.parse::<u8>().is_ok_and(|d| (1..=31).contains(&d))
+            }
+            _ => false,
+        };
+        if !valid {
+            return Err(ParseError::InvalidDate {
+                value: date,
+                position,
+            });
+        }
+        Ok(IqlValue::Date(date))
+    }
+
     fn parse_value_list(&mut self) -> ParseResult<Vec<IqlValue>> {
         let mut values = Vec::new();
 
@@ -777,6 +1548,32 @@ impl Parser {
         Ok(values)
     }
 
+    fn parse_string_list(&mut self, expected_name: &str) -> ParseResult<Vec<String>> {
+        self.parse_delimited_strings(expected_name, &Token::LeftParen, &Token::RightParen)
+    }
+
+    fn parse_delimited_strings(
+        &mut self,
+        expected_name: &str,
+        open: &Token,
+        close: &Token,
+    ) -> ParseResult<Vec<String>> {
+        self.expect(open)?;
+        let mut values = Vec::new();
+        if self.current() != close {
+            loop {
+                values.push(self.parse_string_value(expected_name)?);
+
+                if !self.match_token(&Token::Comma) {
+                    break;
+                }
+            }
+        }
+        self.expect(close)?;
+
+        Ok(values)
+    }
+
     fn parse_string_value(&mut self, expected_name: &str) -> ParseResult<String> {
         if let Token::String(s) | Token::Identifier(s) = self.current() {
             let value = s.clone();
@@ -785,7 +1582,7 @@ impl Parser {
         } else {
             Err(ParseError::UnexpectedToken {
                 expected: format!("string literal for <{expected_name}>"),
-                found: format!("{:?}", self.current()),
+                found: self.current_text(),
                 position: self.get_position_for_error(),
             })
         }
@@ -802,7 +1599,7 @@ impl Parser {
         } else {
             Err(ParseError::UnexpectedToken {
                 expected: format!("identifier for <{expected_name}>"),
-                found: format!("{:?}", self.current()),
+                found: self.current_text(),
                 position: self.get_position_for_error(),
             })
         }
@@ -816,7 +1613,7 @@ impl Parser {
         } else {
             Err(ParseError::UnexpectedToken {
                 expected: "positive number".to_string(),
-                found: format!("{:?}", self.current()),
+                found: self.current_text(),
                 position: self.get_position_for_error(),
             })
         }
@@ -870,6 +1667,132 @@ mod tests {
         insta::assert_debug_snapshot!(&result);
     }
 
+    #[test]
+    fn test_parse_select_group_by_count() {
+        let mut parser = Parser::new(
+            "SELECT status, COUNT(*) FROM issues WHERE project = 'backend' GROUP BY status",
+        );
+        let result = parser.parse().unwrap();
+        insta::assert_debug_snapshot!(&result);
+    }
+
+    #[test]
+    fn test_parse_select_count_column_name() {
+        let mut parser = Parser::new("SELECT count FROM issues GROUP BY count");
+        let result = parser.parse().unwrap();
+        insta::assert_debug_snapshot!(&result);
+    }
+
+    #[test]
+    fn test_parse_select_column_aliases() {
+        let mut parser =
+            Parser::new("SELECT title AS summary, assignee AS owner, status FROM issues");
+        let result = parser.parse().unwrap();
+        insta::assert_debug_snapshot!(&result);
+    }
+
+    #[test]
+    fn test_parse_select_has() {
+        let mut parser = Parser::new("SELECT * FROM issues WHERE labels HAS 'bug'");
+        let result = parser.parse().unwrap();
+        insta::assert_debug_snapshot!(&result);
+    }
+
+    #[test]
+    fn test_parse_select_from_alias() {
+        let mut parser = Parser::new("SELECT i.title FROM issues i WHERE i.status = 'open'");
+        let result = parser.parse().unwrap();
+        insta::assert_debug_snapshot!(&result);
+    }
+
+    #[test]
+    fn test_parse_select_aliased_fields_resolve_to_bare_fields() {
+        let aliased = Parser::new(
+            "SELECT i.title, COUNT(*) FROM issues AS i WHERE i.status = 'open' AND i.assignee IS NOT NULL GROUP BY i.title ORDER BY i.title",
+        )
+        .parse()
+        .unwrap();
+        let bare = Parser::new(
+            "SELECT title, COUNT(*) FROM issues WHERE status = 'open' AND assignee IS NOT NULL GROUP BY title ORDER BY title",
+        )
+        .parse()
+        .unwrap();
+        let (IqlQuery::Select(aliased), IqlQuery::Select(bare)) = (aliased, bare) else {
+            panic!("Expected two SELECT statements");
+        };
+        assert_eq!(aliased.from_alias.as_deref(), Some("i"));
+        assert_eq!(
+            SelectStatement {
+                from_alias: None,
+                ..aliased
+            },
+            bare
+        );
+    }
+
+    #[test]
+    fn test_parse_select_entity_qualified_fields() {
+        let qualified = Parser::new("SELECT issues.title FROM issues")
+            .parse()
+            .unwrap();
+        let bare = Parser::new("SELECT title FROM issues").parse().unwrap();
+        assert_eq!(qualified, bare);
+    }
+
+    #[test]
+    fn test_parse_select_unknown_qualifier() {
+        let result = Parser::new("SELECT x.title FROM issues i").parse();
+        assert!(matches!(
+            result,
+            Err(ParseError::InvalidIdentifier { value, .. }) if value == "x.title"
+        ));
+    }
+
+    #[test]
+    fn test_parse_select_join() {
+        let mut parser = Parser::new(
+            "SELECT issues.title, projects.name FROM issues JOIN projects ON issues.project = projects.id",
+        );
+        let result = parser.parse().unwrap();
+        insta::assert_debug_snapshot!(&result);
+    }
+
+    #[test]
+    fn test_parse_select_join_with_aliases() {
+        let aliased = Parser::new(
+            "SELECT i.title, p.name FROM issues i JOIN projects p ON p.id = i.project WHERE p.name = 'Backend'",
+        )
+        .parse()
+        .unwrap();
+        let qualified = Parser::new(
+            "SELECT issues.title, projects.name FROM issues JOIN projects ON issues.project = projects.id WHERE projects.name = 'Backend'",
+        )
+        .parse()
+        .unwrap();
+        let (IqlQuery::Select(aliased), IqlQuery::Select(qualified)) = (aliased, qualified) else {
+            panic!("Expected two SELECT statements");
+        };
+        assert_eq!(
+            SelectStatement {
+                from_alias: None,
+                join: aliased.join.clone().map(|join| Join {
+                    alias: None,
+                    ..join
+                }),
+                ..aliased
+            },
+            qualified
+        );
+    }
+
+    #[test]
+    fn test_parse_select_join_requires_both_entities() {
+        let result =
+            Parser::new("SELECT title FROM issues JOIN projects ON issues.project = issues.title")
+                .parse();
+        assert!(matches!(result, Err(ParseError::InvalidSyntax { .. })));
+    }
+
     #[test]
     fn test_parse_update_issue() {
         let mut parser = Parser::new("UPDATE issue backend#123 SET status = 'closed'");
@@ -0,0 +1,262 @@
+use std::collections::{HashMap, HashSet};
+
+use facet::{Facet, Type};
+
+use crate::{
+    COUNT_COLUMN, CloseTarget, Columns, CreateStatement, DeleteTarget, EntityType,
+    FilterExpression, IqlQuery, IqlValue, OnConflict, SelectStatement, UpdateTarget,
+    ValidationError, Visitor, column_output_name, walk_filter,
+};
+
+/// The fields each kind of entity has, as far as [`validate`] should know.
+///
+/// Entities without fields in the schema are not checked.
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    fields: HashMap<EntityType, HashSet<String>>,
+}
+
+impl Schema {
+    /// Takes the fields of `entity` from the struct it is stored as.
+    #[must_use]
+    pub fn with_entity<'a, S: Facet<'a>>(self, entity: EntityType) -> Self {
+        let Type::User(facet::UserType::Struct(s)) = S::SHAPE.ty else {
+            return self;
+        };
+        self.with_fields(entity, s.fields.iter().map(|f| f.name))
+    }
+
+    #[must_use]
+    pub fn with_fields(
+        mut self,
+        entity: EntityType,
+        fields: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.fields
+            .entry(entity)
+            .or_default()
+            .extend(fields.into_iter().map(Into::into));
+        self
+    }
+
+    fn has_field(&self, entity: EntityType, field: &str) -> bool {
+        field == "id"
+            || self
+                .fields
+                .get(&entity)
+                .is_none_or(|fields| fields.contains(field))
+    }
+
+    /// Whether the schema lists `field` for `entity`, unlike [`Schema::has_field`] not
+    /// assuming it for entities it does not know.
+    fn knows_field(&self, entity: EntityType, field: &str) -> bool {
+        self.fields
+            .get(&entity)
+            .is_some_and(|fields| fields.contains(field))
+    }
+}
+
+/// Turns bare identifiers on the right of a comparison into references to the field of that
+/// name, where `schema` lists one for the filtered entity.
+///
+/// The parser cannot tell `created_at < updated_at` from `assignee = alice`, so it leaves both
+/// right-hand sides as identifiers, which compare as strings.
+pub fn resolve_fields(query: &mut IqlQuery, schema: &Schema) {
+    let (entity, filter) = match query {
+        IqlQuery::Select(select) => (select.from, &mut select.filter),
+        IqlQuery::Update(update) => match &mut update.entity {
+            UpdateTarget::Bulk { entity, filter } => (*entity, filter),
+            _ => return,
+        },
+        IqlQuery::Delete(delete) => match &mut delete.entity {
+            DeleteTarget::Bulk { entity, filter } => (*entity, filter),
+            _ => return,
+        },
+        IqlQuery::Close(close) => match &mut close.target {
+            CloseTarget::Bulk { filter } => {
+                resolve_filter_fields(filter, EntityType::Issues, schema);
+                return;
+            }
+            CloseTarget::Issue(_) => return,
+        },
+        IqlQuery::Explain(query) => return resolve_fields(query, schema),
+        _ => return,
+    };
+    if let Some(filter) = filter {
+        resolve_filter_fields(filter, entity, schema);
+    }
+}
+
+fn resolve_filter_fields(filter: &mut FilterExpression, entity: EntityType, schema: &Schema) {
+    match filter {
+        FilterExpression::Comparison { value, .. } => {
+            if let IqlValue::Identifier(name) = value
+                && schema.knows_field(entity, name)
+            {
+                *value = IqlValue::Field(name.clone());
+            }
+        }
+        FilterExpression::And(left, right) | FilterExpression::Or(left, right) => {
+            resolve_filter_fields(left, entity, schema);
+            resolve_filter_fields(right, entity, schema);
+        }
+        FilterExpression::Not(expr) => resolve_filter_fields(expr, entity, schema),
+        _ => {}
+    }
+}
+
+/// Checks the fields a query refers to against `schema`, without running it.
+///
+/// Every unknown field is reported, in the order it appears in the query.
+pub fn validate(query: &IqlQuery, schema: &Schema) -> Result<(), Vec<ValidationError>> {
+    let mut validator = Validator {
+        schema,
+        errors: Vec::new(),
+    };
+    validator.query(query);
+    if validator.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.errors)
+    }
+}
+
+struct Validator<'a> {
+    schema: &'a Schema,
+    errors: Vec<ValidationError>,
+}
+
+impl Validator<'_> {
+    fn query(&mut self, query: &IqlQuery) {
+        match query {
+            IqlQuery::Select(select) => self.select(select),
+            IqlQuery::Update(update) => {
+                let entity = update.entity.entity_type();
+                for field_update in &update.updates {
+                    self.field(entity, None, &field_update.field, "SET");
+                }
+                if let UpdateTarget::Bulk {
+                    filter: Some(filter),
+                    ..
+                } = &update.entity
+                {
+                    self.filter(entity, None, filter);
+                }
+            }
+            IqlQuery::Delete(delete) => {
+                if let DeleteTarget::Bulk {
+                    entity,
+                    filter: Some(filter),
+                } = &delete.entity
+                {
+                    self.filter(*entity, None, filter);
+                }
+            }
+            IqlQuery::Close(close) => {
+                if let CloseTarget::Bulk { filter } = &close.target {
+                    self.filter(EntityType::Issues, None, filter);
+                }
+            }
+            IqlQuery::Explain(query) => self.query(query),
+            IqlQuery::Create(CreateStatement::Project {
+                on_conflict: Some(OnConflict::Update(updates)),
+                ..
+            }) => {
+                for field_update in updates {
+                    self.field(EntityType::Projects, None, &field_update.field, "SET");
+                }
+            }
+            IqlQuery::Create(_)
+            | IqlQuery::Assign(_)
+            | IqlQuery::Reopen(_)
+            | IqlQuery::Move(_)
+            | IqlQuery::Rename(_)
+            | IqlQuery::Archive(_)
+            | IqlQuery::Unarchive(_)
+            | IqlQuery::Comment(_)
+            | IqlQuery::React(_)
+            | IqlQuery::Unreact(_)
+            | IqlQuery::Reindex
+            | IqlQuery::Import(_)
+            | IqlQuery::History(_)
+            | IqlQuery::Describe(_)
+            | IqlQuery::Begin
+            | IqlQuery::Commit
+            | IqlQuery::Rollback
+            | IqlQuery::Set(_) => {}
+        }
+    }
+
+    fn select(&mut self, select: &SelectStatement) {
+        let join = select.join.as_ref().map(|join| join.entity);
+        if let Columns::Named(columns) = &select.columns {
+            for (column, _) in columns.iter().filter(|(column, _)| column != COUNT_COLUMN) {
+                self.field(select.from, join, column, "SELECT");
+            }
+        }
+        if let Some(join) = &select.join {
+            self.field(select.from, None, &join.left_field, "JOIN");
+            self.field(join.entity, None, &join.right_field, "JOIN");
+        }
+        if let Some(filter) = &select.filter {
+            self.filter(select.from, join, filter);
+        }
+        if let Some(group_by) = &select.group_by {
+            self.field(select.from, join, group_by, "GROUP BY");
+        }
+        if let Some(order_by) = &select.order_by {
+            // Selected columns can also be ordered by their output name
+            let is_output_name = match &select.columns {
+                Columns::Named(columns) => columns.iter().any(|(column, alias)| {
+                    column_output_name(column, alias.as_deref()) == order_by.field
+                }),
+                Columns::All => false,
+            };
+            if !is_output_name {
+                self.field(select.from, join, &order_by.field, "ORDER BY");
+            }
+        }
+    }
+
+    fn filter(&mut self, entity: EntityType, join: Option<EntityType>, filter: &FilterExpression) {
+        struct FieldCollector(Vec<String>);
+
+        impl Visitor for FieldCollector {
+            fn visit_field(&mut self, field: &str) {
+                self.0.push(field.to_string());
+            }
+
+            fn visit_value(&mut self, value: &IqlValue) {
+                if let IqlValue::Field(field) = value {
+                    self.0.push(field.clone());
+                }
+            }
+        }
+
+        let mut collector = FieldCollector(Vec::new());
+        walk_filter(&mut collector, filter);
+        for field in collector.0 {
+            self.field(entity, join, &field, "WHERE");
+        }
+    }
+
+    /// Checks `field` of `entity`, or of the joined entity when qualified with its name.
+    fn field(&mut self, entity: EntityType, join: Option<EntityType>, field: &str, clause: &str) {
+        let (entity, name) = match join {
+            Some(join) => match field.split_once('.') {
+                Some((qualifier, name)) if qualifier == join.to_string().to_lowercase() => {
+                    (join, name)
+                }
+                _ => (entity, field),
+            },
+            None => (entity, field),
+        };
+        if !self.schema.has_field(entity, name) {
+            self.errors.push(ValidationError::UnknownField {
+                entity,
+                field: name.to_string(),
+                clause: clause.to_string(),
+            });
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use async_trait::async_trait;
+use facet_value::{VObject, VString};
+use issuecraft_core::{AuthenticationInfo, Client, ClientError, ExecutionResult, LoginInfo};
+use issuecraft_ql::IqlQuery;
+use reqwest::{StatusCode, header};
+
+/// A [`Client`] that sends queries to an IssueCraft server as JSON over HTTP.
+///
+/// Every query is rendered back to IQL and posted to `<base_url>/query`, and the
+/// [`ExecutionResult`] is read from the JSON response. Logging in with a token sends it
+/// as a bearer token with every following query.
+pub struct HttpClient {
+    base_url: String,
+    http: reqwest::Client,
+    token: Option<String>,
+}
+
+impl HttpClient {
+    #[must_use]
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+            token: None,
+        }
+    }
+}
+
+#[async_trait]
+impl Client for HttpClient {
+    async fn login(&mut self, login: LoginInfo) -> Result<(), ClientError> {
+        match login.auth {
+            AuthenticationInfo::Token { token } => {
+                self.token = Some(token);
+                Ok(())
+            }
+            AuthenticationInfo::Password { .. } | AuthenticationInfo::Certificate { .. } => {
+                Err(ClientError::NotSupported)
+            }
+        }
+    }
+
+    async fn logout(&mut self) -> Result<(), ClientError> {
+        self.token = None;
+        Ok(())
+    }
+
+    async fn query(&mut self, query: &IqlQuery) -> Result<ExecutionResult, ClientError> {
+        let mut body = VObject::new();
+        body.insert("query", VString::new(&query.to_string()).into_value());
+        let body = facet_json::to_string(&body.into_value())
+            .map_err(|e| ClientError::ClientSpecific(e.to_string()))?;
+
+        let mut request = self
+            .http
+            .post(format!("{}/query", self.base_url))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ClientError::ClientSpecific(e.to_string()))?;
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| ClientError::ClientSpecific(e.to_string()))?;
+        match status {
+            StatusCode::UNAUTHORIZED => Err(ClientError::Unauthorized),
+            status if status.is_success() => Ok(facet_json::from_str(&text)?),
+            status => Err(ClientError::ClientSpecific(format!("{status}: {text}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use issuecraft_ql::parse_query;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_string, header, method, path},
+    };
+
+    use super::*;
+
+    fn token(token: &str) -> LoginInfo {
+        LoginInfo {
+            user: "alice".to_string(),
+            auth: AuthenticationInfo::Token {
+                token: token.to_string(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_posts_iql_and_reads_the_result() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/query"))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_string(r#"{"query":"SELECT * FROM ISSUES LIMIT 1"}"#))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(r#"{"rows":1,"info":null,"data":"[]"}"#),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut client = HttpClient::new(format!("{}/", server.uri()));
+        client.login(token("secret")).await.unwrap();
+        let result = client
+            .query(&parse_query("SELECT * FROM issues LIMIT 1").unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(result.rows, 1);
+        assert_eq!(result.info, None);
+        assert_eq!(result.data.as_deref(), Some("[]"));
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_response_is_reported() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/query"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        let mut client = HttpClient::new(server.uri());
+        let error = client
+            .query(&parse_query("SELECT * FROM issues").unwrap())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, ClientError::Unauthorized));
+    }
+}
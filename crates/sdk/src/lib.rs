@@ -1,3 +1,7 @@
+mod http;
+
+pub use http::HttpClient;
+
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
@@ -0,0 +1,263 @@
+use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+use async_trait::async_trait;
+use axum::{
+    Router,
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use facet_value::Value as FacetValue;
+use issuecraft_core::{
+    Action, AnyUserAuthorizationProvider, AuthorizationProvider, AuthorizationResult,
+    AuthorizationStatus, BackendError, ExecutionEngine, Resource, SingleUserAuthorizationProvider,
+    UserProvider,
+};
+use issuecraft_ql::{IqlError, IqlQuery, SessionOptions, UserId};
+use issuecraft_redb::Database;
+use tokio::sync::Mutex;
+
+/// Errors of a request, each answered with its own status code.
+#[derive(thiserror::Error, Debug)]
+pub enum ServerError {
+    #[error("Missing or unknown bearer token")]
+    Unauthorized,
+    #[error("Malformed request: {0}")]
+    BadRequest(String),
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+}
+
+impl ServerError {
+    #[must_use]
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ServerError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ServerError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ServerError::Backend(error) => match error {
+                BackendError::IqlError(_)
+                | BackendError::FieldNotFound(_)
+                | BackendError::MissingRequiredField { .. }
+                | BackendError::InvalidId(_)
+                | BackendError::InvalidField { .. } => StatusCode::BAD_REQUEST,
+                BackendError::PermissionDenied(_) | BackendError::ReadOnly => StatusCode::FORBIDDEN,
+                BackendError::UserNotFound { .. } | BackendError::ItemNotFound { .. } => {
+                    StatusCode::NOT_FOUND
+                }
+                BackendError::ProjectAlreadyExists(_)
+                | BackendError::DuplicateDisplayName(_)
+                | BackendError::ItemAlreadyExists { .. }
+                | BackendError::IssueAlreadyClosed(..)
+                | BackendError::TransactionAlreadyOpen
+                | BackendError::NoOpenTransaction => StatusCode::CONFLICT,
+                BackendError::NotImplemented | BackendError::NotSupported => {
+                    StatusCode::NOT_IMPLEMENTED
+                }
+                BackendError::IncompatibleSchemaVersion { .. }
+                | BackendError::ImplementationSpecific(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+        }
+    }
+}
+
+impl IntoResponse for ServerError {
+    fn into_response(self) -> Response {
+        (self.status(), self.to_string()).into_response()
+    }
+}
+
+/// Hands out users for a fixed set of tokens.
+///
+/// Parses from lines of `<token> <user>`; blank lines and lines starting with `#` are skipped.
+#[derive(Debug, Default)]
+pub struct StaticUserProvider {
+    tokens: HashMap<String, UserId>,
+}
+
+impl StaticUserProvider {
+    #[must_use]
+    pub fn with_token(mut self, token: &str, user: &str) -> Self {
+        self.tokens.insert(token.to_string(), UserId::new(user));
+        self
+    }
+}
+
+impl FromStr for StaticUserProvider {
+    type Err = String;
+
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        let mut provider = Self::default();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+                [token, user] => provider = provider.with_token(token, user),
+                _ => {
+                    return Err(format!(
+                        "line {} is not of the form '<token> <user>'",
+                        index + 1
+                    ));
+                }
+            }
+        }
+        Ok(provider)
+    }
+}
+
+#[async_trait]
+impl UserProvider for StaticUserProvider {
+    async fn user_for_token(&self, token: &str) -> Result<Option<UserId>, BackendError> {
+        Ok(self.tokens.get(token).cloned())
+    }
+}
+
+/// Which of the authorization providers a server checks queries with, picked when it is started.
+///
+/// Parses from `deny`, `single-user` or `any-user`. Denies every query by default, so a server
+/// only lets users in once it is told to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ServerAuthorization {
+    #[default]
+    Deny,
+    /// [`SingleUserAuthorizationProvider`]
+    SingleUser,
+    /// [`AnyUserAuthorizationProvider`]
+    AnyUser,
+}
+
+impl FromStr for ServerAuthorization {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "deny" => Ok(Self::Deny),
+            "single-user" => Ok(Self::SingleUser),
+            "any-user" => Ok(Self::AnyUser),
+            _ => Err(format!(
+                "'{name}' is not one of 'deny', 'single-user' or 'any-user'"
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl AuthorizationProvider for ServerAuthorization {
+    async fn check_authorization(
+        &self,
+        principal: &UserId,
+        action: &Action,
+        resource: &Resource,
+        context: Option<FacetValue>,
+    ) -> Result<AuthorizationResult, BackendError> {
+        match self {
+            Self::Deny => Ok(AuthorizationResult {
+                user: principal.clone(),
+                action: action.clone(),
+                resource: resource.clone(),
+                status: AuthorizationStatus::Denied,
+            }),
+            Self::SingleUser => {
+                SingleUserAuthorizationProvider
+                    .check_authorization(principal, action, resource, context)
+                    .await
+            }
+            Self::AnyUser => {
+                AnyUserAuthorizationProvider
+                    .check_authorization(principal, action, resource, context)
+                    .await
+            }
+        }
+    }
+}
+
+struct ServerState<A> {
+    db: Mutex<Database>,
+    users: Box<dyn UserProvider + Send + Sync>,
+    authorization_provider: A,
+}
+
+/// Builds the routes of the server.
+///
+/// `POST /query` takes a JSON object with the IQL statement under `query`, runs it as the
+/// user the bearer token belongs to and answers with the JSON of the `ExecutionResult`.
+/// Whether that user may run it is up to `authorization_provider`.
+pub fn router<A: AuthorizationProvider + Send + Sync + 'static>(
+    db: Database,
+    users: impl UserProvider + Send + Sync + 'static,
+    authorization_provider: A,
+) -> Router {
+    let state = ServerState {
+        db: Mutex::new(db),
+        users: Box::new(users),
+        authorization_provider,
+    };
+    Router::new()
+        .route("/query", post(query::<A>))
+        .with_state(Arc::new(state))
+}
+
+async fn query<A: AuthorizationProvider + Send + Sync>(
+    State(state): State<Arc<ServerState<A>>>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    match run_query(&state, &headers, &body).await {
+        Ok(result) => ([(header::CONTENT_TYPE, "application/json")], result).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+async fn run_query<A: AuthorizationProvider + Sync>(
+    state: &ServerState<A>,
+    headers: &HeaderMap,
+    body: &str,
+) -> Result<String, ServerError> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(ServerError::Unauthorized)?;
+    let user = state
+        .users
+        .user_for_token(token)
+        .await?
+        .ok_or(ServerError::Unauthorized)?;
+
+    let request = facet_json::from_str::<FacetValue>(body)
+        .map_err(|e| ServerError::BadRequest(e.to_string()))?;
+    let query = request
+        .as_object()
+        .and_then(|object| object.get("query"))
+        .and_then(FacetValue::as_string)
+        .ok_or_else(|| ServerError::BadRequest("expected an object with a query".to_string()))?;
+    let query = issuecraft_ql::parse_query(query.as_str())
+        .map_err(|e| BackendError::from(IqlError::from(e)))?;
+    // Every request runs on the same database, so a transaction or a session option would carry
+    // over into the requests of everyone else
+    if matches!(
+        query,
+        IqlQuery::Begin | IqlQuery::Commit | IqlQuery::Rollback | IqlQuery::Set(_)
+    ) {
+        return Err(ServerError::BadRequest(format!(
+            "'{query}' is not supported over HTTP"
+        )));
+    }
+
+    let result = state
+        .db
+        .lock()
+        .await
+        .execute(
+            &state.authorization_provider,
+            user,
+            &query,
+            // Session options can't be set over HTTP, so every request starts from the defaults
+            &mut SessionOptions::default(),
+        )
+        .await?;
+    facet_json::to_string(&result)
+        .map_err(|e| BackendError::ImplementationSpecific(e.to_string()).into())
+}
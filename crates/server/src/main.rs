@@ -0,0 +1,56 @@
+use std::{net::SocketAddr, path::PathBuf};
+
+use anyhow::Context;
+use clap::Parser;
+use issuecraft_server::{ServerAuthorization, StaticUserProvider};
+
+#[derive(Debug, Parser)]
+#[clap(author, version, about, long_about = None)]
+struct Cli {
+    #[arg(short, long, alias = "db", env = "ISSUECRAFT_DB")]
+    database: PathBuf,
+    /// File with a `<token> <user>` pair per line
+    #[arg(short, long, env = "ISSUECRAFT_TOKENS")]
+    tokens: PathBuf,
+    #[arg(
+        short,
+        long,
+        default_value = "127.0.0.1:7878",
+        env = "ISSUECRAFT_LISTEN"
+    )]
+    listen: SocketAddr,
+    /// Rejects every query that could write
+    #[arg(long)]
+    read_only: bool,
+    /// Who may run queries: `deny`, `single-user` or `any-user`
+    #[arg(long, default_value = "deny", env = "ISSUECRAFT_AUTHORIZATION")]
+    authorization: ServerAuthorization,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let Cli {
+        database,
+        tokens,
+        listen,
+        read_only,
+        authorization,
+    } = Cli::parse();
+
+    let users = tokio::fs::read_to_string(&tokens)
+        .await
+        .with_context(|| format!("Could not read {}", tokens.display()))?
+        .parse::<StaticUserProvider>()
+        .map_err(anyhow::Error::msg)?;
+    let db =
+        issuecraft_redb::Database::open_checked(issuecraft_redb::DatabaseType::File(database))?
+            .with_read_only(read_only);
+
+    let listener = tokio::net::TcpListener::bind(listen).await?;
+    axum::serve(
+        listener,
+        issuecraft_server::router(db, users, authorization),
+    )
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{Request, StatusCode, header},
+};
+use issuecraft_core::{
+    AnyUserAuthorizationProvider, AuthenticationInfo, BackendError, Client, ClientError, LoginInfo,
+    SingleUserAuthorizationProvider,
+};
+use issuecraft_ql::parse_query;
+use issuecraft_redb::{Database, DatabaseType};
+use issuecraft_sdk::HttpClient;
+use issuecraft_server::{ServerAuthorization, ServerError, StaticUserProvider, router};
+use tower::ServiceExt;
+
+fn app() -> Router {
+    let db = Database::new(DatabaseType::InMemory).unwrap();
+    router(
+        db,
+        StaticUserProvider::default().with_token("secret", "alice"),
+        AnyUserAuthorizationProvider,
+    )
+}
+
+async fn post_query(app: Router, token: Option<&str>, query: &str) -> (StatusCode, String) {
+    let mut request = Request::post("/query").header(header::CONTENT_TYPE, "application/json");
+    if let Some(token) = token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+    }
+    let body = format!(r#"{{"query":"{query}"}}"#);
+    let response = app
+        .oneshot(request.body(Body::from(body)).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn runs_a_query_as_the_user_of_the_token() {
+    let (status, body) = post_query(app(), Some("secret"), "CREATE USER bob").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains(r#""rows":1"#), "{body}");
+}
+
+#[tokio::test]
+async fn concurrent_writes_both_succeed() {
+    let app = app();
+    let (first, second) = tokio::join!(
+        post_query(app.clone(), Some("secret"), "CREATE USER bob"),
+        post_query(app.clone(), Some("secret"), "CREATE USER carol"),
+    );
+    assert_eq!(first.0, StatusCode::OK);
+    assert_eq!(second.0, StatusCode::OK);
+
+    let (_, body) = post_query(app, Some("secret"), "SELECT * FROM users").await;
+    assert!(body.contains("bob") && body.contains("carol"), "{body}");
+}
+
+#[tokio::test]
+async fn rejects_session_statements() {
+    let app = app();
+    for query in ["BEGIN", "COMMIT", "ROLLBACK", "SET default_limit = 1"] {
+        let (status, _) = post_query(app.clone(), Some("secret"), query).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{query}");
+    }
+}
+
+#[tokio::test]
+async fn serves_the_sdk_client() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app()).await });
+
+    let mut client = HttpClient::new(format!("http://{address}"));
+    let error = client
+        .query(&parse_query("SELECT * FROM users").unwrap())
+        .await
+        .unwrap_err();
+    assert!(matches!(error, ClientError::Unauthorized));
+
+    client
+        .login(LoginInfo {
+            user: "alice".to_string(),
+            auth: AuthenticationInfo::Token {
+                token: "secret".to_string(),
+            },
+        })
+        .await
+        .unwrap();
+    let created = client
+        .query(&parse_query("CREATE USER bob").unwrap())
+        .await
+        .unwrap();
+    assert_eq!(created.rows, 1);
+
+    let selected = client
+        .query(&parse_query("SELECT * FROM users WHERE id = 'bob'").unwrap())
+        .await
+        .unwrap();
+    let (_, rows) = selected.table().unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0][0], "bob");
+}
+
+#[tokio::test]
+async fn rejects_missing_and_unknown_tokens() {
+    let (status, _) = post_query(app(), None, "SELECT * FROM users").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = post_query(app(), Some("guess"), "SELECT * FROM users").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn maps_errors_to_status_codes() {
+    let (status, body) = post_query(app(), Some("secret"), "SELEKT * FROM users").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.contains("could not be parsed"), "{body}");
+
+    let (status, _) = post_query(app(), Some("secret"), "REOPEN ISSUE backend#1").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    assert_eq!(
+        ServerError::from(BackendError::PermissionDenied("alice".to_string())).status(),
+        StatusCode::FORBIDDEN
+    );
+}
+
+#[tokio::test]
+async fn checks_queries_with_the_given_authorization_provider() {
+    let app = router(
+        Database::new(DatabaseType::InMemory).unwrap(),
+        StaticUserProvider::default()
+            .with_token("secret", "alice")
+            .with_token("admin", "default"),
+        SingleUserAuthorizationProvider,
+    );
+
+    let (status, _) = post_query(app.clone(), Some("secret"), "CREATE USER bob").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = post_query(app, Some("admin"), "CREATE USER bob").await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn denies_every_query_unless_configured_otherwise() {
+    let app = router(
+        Database::new(DatabaseType::InMemory).unwrap(),
+        StaticUserProvider::default().with_token("admin", "default"),
+        ServerAuthorization::default(),
+    );
+    let (status, _) = post_query(app, Some("admin"), "CREATE USER bob").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    assert_eq!(
+        "any-user".parse::<ServerAuthorization>(),
+        Ok(ServerAuthorization::AnyUser)
+    );
+    assert!("everyone".parse::<ServerAuthorization>().is_err());
+}
+
+#[test]
+fn parses_token_files() {
+    let provider = "# token user\nsecret alice\n\nother bob\n".parse::<StaticUserProvider>();
+    assert!(provider.is_ok());
+
+    let error = "secret alice\nbroken\n"
+        .parse::<StaticUserProvider>()
+        .unwrap_err();
+    assert_eq!(error, "line 2 is not of the form '<token> <user>'");
+}
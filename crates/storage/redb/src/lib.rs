@@ -1,29 +1,272 @@
-use std::{fmt::Display, path::PathBuf};
+use std::{
+    cell::Cell,
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+    ops::{Bound, RangeBounds},
+    path::PathBuf,
+    sync::LazyLock,
+};
 
 use async_trait::async_trait;
 use facet::Facet;
-use facet_value::{Value, from_value, value};
+use facet_value::{VNumber, VObject, VString, Value, from_value, value};
 use issuecraft_core::{
-    Action, AuthorizationProvider, BackendError, CommentInfo, EntityId, Entry, ExecutionEngine,
-    ExecutionResult, IssueInfo, IssueStatus, Priority, ProjectInfo, Resource, UserInfo,
+    Action, AuditAction, AuditEntry, AuthorizationProvider, BackendError, CommentInfo, EntityId,
+    Entry, ExecutionEngine, ExecutionResult, FieldChange, FieldDescription, IssueField, IssueInfo,
+    IssueStatus, IssueTemplates, LinkInfo, Priority, ProjectInfo, Resource, SelectData,
+    UntypedEntry, UserInfo,
 };
 use issuecraft_ql::{
-    AssignStatement, CloseStatement, CommentId, CommentStatement, DeleteStatement, DeleteTarget,
-    EntityType, FieldUpdate, IqlQuery, IssueId, ProjectId, ReopenStatement, SelectStatement,
-    UpdateStatement, UserId,
+    ArchiveStatement, ArchiveTarget, AssignStatement, COMMENT_COUNT_COLUMN, COUNT_COLUMN,
+    CloseReason, CloseStatement, CloseTarget, Columns, CommentId, CommentStatement,
+    DeleteStatement, DeleteTarget, EntityType, FieldUpdate, FilterExpression, IqlQuery, IssueId,
+    Join, LinkId, MoveStatement, OnConflict, OrderBy, OrderDirection, ProjectId, ReactionStatement,
+    RenameStatement, ReopenStatement, Schema, SelectStatement, SessionOptions, UpdateStatement,
+    UpdateTarget, UserId, column_output_name, compare_timestamps, resolve_fields,
 };
-use nanoid::nanoid;
 use redb::{
-    ReadableDatabase, ReadableTable, TableDefinition, TableHandle, backends::InMemoryBackend,
+    MultimapTableDefinition, MultimapTableHandle, ReadableDatabase, ReadableMultimapTable,
+    ReadableTable, TableDefinition, TableError, TableHandle, backends::InMemoryBackend,
 };
 
 const TABLE_USERS: TableDefinition<&str, String> = TableDefinition::new("users");
 const TABLE_PROJECTS: TableDefinition<&str, String> = TableDefinition::new("projects");
 const TABLE_ISSUES: TableDefinition<&str, String> = TableDefinition::new("issues");
 const TABLE_COMMENTS: TableDefinition<&str, String> = TableDefinition::new("comments");
+const TABLE_LINKS: TableDefinition<&str, String> = TableDefinition::new("links");
+const TABLE_META: TableDefinition<&str, String> = TableDefinition::new("meta");
+const TABLE_ISSUE_HISTORY: TableDefinition<&str, String> = TableDefinition::new("issue_history");
+const TABLE_AUDIT: TableDefinition<&str, String> = TableDefinition::new("audit");
+
+const INDEX_ISSUES_BY_ASSIGNEE: MultimapTableDefinition<&str, &str> =
+    MultimapTableDefinition::new("issues_by_assignee");
+
+/// Every table besides the indexes.
+const TABLES: [TableDefinition<&str, String>; 8] = [
+    TABLE_USERS,
+    TABLE_PROJECTS,
+    TABLE_ISSUES,
+    TABLE_COMMENTS,
+    TABLE_LINKS,
+    TABLE_META,
+    TABLE_ISSUE_HISTORY,
+    TABLE_AUDIT,
+];
+
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_support;
+
+const ENTITY_TYPES: [EntityType; 5] = [
+    EntityType::Users,
+    EntityType::Projects,
+    EntityType::Issues,
+    EntityType::Comments,
+    EntityType::Links,
+];
+
+const DUMP_VERSION: u64 = 1;
+
+/// The user every database is created with.
+const DEFAULT_USER: &str = "default";
+
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+const SCHEMA_VERSION: u64 = 1;
+
+/// Which bare names in a filter refer to fields rather than being strings.
+static SCHEMA: LazyLock<Schema> = LazyLock::new(issuecraft_core::schema);
 
 pub struct Database {
     db: redb::Database,
+    issue_templates: IssueTemplates,
+    unique_project_names: bool,
+    read_only: bool,
+    /// The audit entry of the mutation being executed, written by its first entity write.
+    pending_audit: Cell<Option<PendingAudit>>,
+    /// The open transaction, which every read and write goes through until it is committed or
+    /// rolled back. Transactions that are still open when the database is dropped are rolled
+    /// back.
+    transaction: Option<redb::WriteTransaction>,
+}
+
+/// An audit entry waiting for the write that carries out its mutation. Creates only learn the
+/// id of their entity once it is written.
+struct PendingAudit {
+    timestamp: time::UtcDateTime,
+    user: UserId,
+    action: AuditAction,
+    resource: Resource,
+    id: Option<String>,
+    query: String,
+}
+
+impl PendingAudit {
+    fn for_query(user: &UserId, query: &IqlQuery) -> Option<Self> {
+        let (action, resource, id, query) = match query {
+            IqlQuery::Create(statement) => {
+                let (resource, id) = match statement {
+                    issuecraft_ql::CreateStatement::User { username, .. } => {
+                        (Resource::User, Some(username.clone()))
+                    }
+                    issuecraft_ql::CreateStatement::Project { project_id, .. } => {
+                        (Resource::Project, Some(project_id.to_string()))
+                    }
+                    issuecraft_ql::CreateStatement::Issue { .. } => (Resource::Issue, None),
+                    // Each of the issues is audited as a create of its own
+                    issuecraft_ql::CreateStatement::Issues { .. } => return None,
+                };
+                (AuditAction::Create, resource, id, statement.to_string())
+            }
+            IqlQuery::Update(statement) => {
+                let (resource, id) = match &statement.entity {
+                    issuecraft_ql::UpdateTarget::User(id) => (Resource::User, id.to_string()),
+                    issuecraft_ql::UpdateTarget::Project(id) => (Resource::Project, id.to_string()),
+                    issuecraft_ql::UpdateTarget::Issue(id) => (Resource::Issue, id.to_string()),
+                    issuecraft_ql::UpdateTarget::Comment(id) => (Resource::Comment, id.to_string()),
+                    // Bulk updates are recorded once per entity they resolve to
+                    issuecraft_ql::UpdateTarget::Bulk { .. } => return None,
+                };
+                (
+                    AuditAction::Update,
+                    resource,
+                    Some(id),
+                    statement.to_string(),
+                )
+            }
+            IqlQuery::Delete(statement) => {
+                let (resource, id) = match &statement.entity {
+                    DeleteTarget::User(id) => (Resource::User, id.to_string()),
+                    DeleteTarget::Project(id) => (Resource::Project, id.to_string()),
+                    DeleteTarget::Issue(id) => (Resource::Issue, id.to_string()),
+                    DeleteTarget::Comment(id) => (Resource::Comment, id.to_string()),
+                    DeleteTarget::Bulk { .. } => return None,
+                };
+                (
+                    AuditAction::Delete,
+                    resource,
+                    Some(id),
+                    statement.to_string(),
+                )
+            }
+            IqlQuery::Assign(statement) => (
+                AuditAction::Assign,
+                Resource::Issue,
+                Some(statement.issue_id.to_string()),
+                statement.to_string(),
+            ),
+            IqlQuery::Close(
+                statement @ CloseStatement {
+                    target: CloseTarget::Issue(id),
+                    ..
+                },
+            ) => (
+                AuditAction::Close,
+                Resource::Issue,
+                Some(id.to_string()),
+                statement.to_string(),
+            ),
+            IqlQuery::Reopen(statement) => (
+                AuditAction::Reopen,
+                Resource::Issue,
+                Some(statement.issue_id.to_string()),
+                statement.to_string(),
+            ),
+            IqlQuery::Move(statement) => (
+                AuditAction::Move,
+                Resource::Issue,
+                Some(statement.issue_id.to_string()),
+                statement.to_string(),
+            ),
+            IqlQuery::Rename(statement) => (
+                AuditAction::Rename,
+                Resource::Project,
+                Some(statement.project.to_string()),
+                statement.to_string(),
+            ),
+            IqlQuery::Archive(ArchiveStatement { target })
+            | IqlQuery::Unarchive(ArchiveStatement { target }) => {
+                let (resource, id) = match target {
+                    ArchiveTarget::Issue(id) => (Resource::Issue, id.to_string()),
+                    ArchiveTarget::Project(id) => (Resource::Project, id.to_string()),
+                };
+                let action = if matches!(query, IqlQuery::Archive(_)) {
+                    AuditAction::Archive
+                } else {
+                    AuditAction::Unarchive
+                };
+                (action, resource, Some(id), query.to_string())
+            }
+            IqlQuery::Comment(statement) => (
+                AuditAction::Comment,
+                Resource::Comment,
+                None,
+                statement.to_string(),
+            ),
+            _ => return None,
+        };
+        Some(Self {
+            timestamp: issuecraft_core::now(),
+            user: user.clone(),
+            action,
+            resource,
+            id,
+            query,
+        })
+    }
+
+    fn into_entry(self, written_id: &str) -> AuditEntry {
+        AuditEntry {
+            timestamp: self.timestamp,
+            user: self.user,
+            action: self.action,
+            resource: self.resource,
+            id: self.id.unwrap_or_else(|| written_id.to_string()),
+            query: self.query,
+        }
+    }
+}
+
+/// A table read as last committed or, while a transaction is open, as the transaction left it.
+enum ReadTable<'txn> {
+    Committed(redb::ReadOnlyTable<&'static str, String>),
+    Transaction(redb::Table<'txn, &'static str, String>),
+}
+
+impl ReadTable<'_> {
+    fn get(&self, key: &str) -> Result<Option<String>, BackendError> {
+        let value = match self {
+            Self::Committed(table) => table.get(key),
+            Self::Transaction(table) => table.get(key),
+        };
+        Ok(value.map_err(to_iql_error)?.map(|value| value.value()))
+    }
+
+    fn range<'a>(
+        &self,
+        range: impl RangeBounds<&'a str> + 'a,
+    ) -> Result<redb::Range<'_, &'static str, String>, BackendError> {
+        match self {
+            Self::Committed(table) => table.range(range),
+            Self::Transaction(table) => table.range(range),
+        }
+        .map_err(to_iql_error)
+    }
+
+    fn iter(&self) -> Result<redb::Range<'_, &'static str, String>, BackendError> {
+        self.range(..)
+    }
+}
+
+/// The issues `assignee` is assigned to according to `index`.
+fn assigned_issues(
+    index: &impl ReadableMultimapTable<&'static str, &'static str>,
+    assignee: &str,
+) -> Result<Vec<IssueId>, BackendError> {
+    index
+        .get(assignee)
+        .map_err(to_iql_error)?
+        .map(|id| Ok(IssueId::new(id.map_err(to_iql_error)?.value())))
+        .collect()
 }
 
 pub enum DatabaseType {
@@ -31,605 +274,5055 @@ pub enum DatabaseType {
     File(PathBuf),
 }
 
+#[derive(Debug, Facet)]
+struct Dump {
+    version: u64,
+    tables: Vec<DumpTable>,
+}
+
+#[derive(Debug, Facet)]
+struct DumpTable {
+    name: String,
+    entries: Vec<UntypedEntry>,
+}
+
+fn parse_dump(json: &str) -> Result<Dump, BackendError> {
+    let dump: Dump = facet_json::from_str(json).map_err(to_iql_error)?;
+    if dump.version != DUMP_VERSION {
+        return Err(BackendError::ImplementationSpecific(format!(
+            "Unsupported dump version {}, expected {DUMP_VERSION}",
+            dump.version
+        )));
+    }
+    Ok(dump)
+}
+
+/// The kind of entity stored in the table `name` of a dump.
+fn dump_table_kind(name: &str) -> Result<EntityType, BackendError> {
+    ENTITY_TYPES
+        .into_iter()
+        .find(|kind| get_table(*kind).name() == name)
+        .ok_or_else(|| {
+            BackendError::ImplementationSpecific(format!("Unknown table '{name}' in dump"))
+        })
+}
+
+fn default_user() -> UserInfo {
+    UserInfo {
+        name: "Default User".to_string(),
+        display: Some("Default User".to_string()),
+        email: None,
+    }
+}
+
+fn is_archived(row: &Value) -> bool {
+    row.as_object()
+        .and_then(|row| row.get("archived"))
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// Whether a select only asks for the ids of rows that need no filtering, so the values never
+/// have to be read. Issues and projects qualify only when archived ones are included, as
+/// hiding them depends on the value.
+fn is_keys_only(select_statement: &SelectStatement, columns: &[(String, Option<String>)]) -> bool {
+    matches!(columns, [(column, _)] if column == "id")
+        && (select_statement.include_archived
+            || !matches!(
+                select_statement.from,
+                EntityType::Issues | EntityType::Projects
+            ))
+}
+
+/// The columns of a `SELECT *`: the id and the fields of the selected entity, followed by
+/// those of the joined entity prefixed with its name.
+fn all_columns(select_statement: &SelectStatement) -> Vec<String> {
+    let fields = |entity| {
+        describe_entity(entity).map_or_else(
+            |_| vec!["value".to_string()],
+            |fields| fields.into_iter().map(|field| field.field).collect(),
+        )
+    };
+    let mut columns = vec!["id".to_string()];
+    columns.extend(fields(select_statement.from));
+    if let Some(join) = &select_statement.join {
+        let entity = join.entity.to_string().to_lowercase();
+        columns.extend(
+            ["id".to_string()]
+                .into_iter()
+                .chain(fields(join.entity))
+                .map(|field| format!("{entity}.{field}")),
+        );
+    }
+    columns
+}
+
+fn output_columns(columns: &[(String, Option<String>)]) -> Vec<String> {
+    columns
+        .iter()
+        .map(|(column, alias)| column_output_name(column, alias.as_deref()).to_string())
+        .collect()
+}
+
+fn issue_counter_key(project: &str) -> String {
+    format!("issue_counter:{project}")
+}
+
+fn comment_counter_key(issue: &str) -> String {
+    format!("comment_counter:{issue}")
+}
+
+fn issue_assignee(issue: &str) -> Result<Option<String>, BackendError> {
+    let issue = facet_json::from_str::<Value>(issue).map_err(to_iql_error)?;
+    Ok(issue
+        .as_object()
+        .and_then(|issue| issue.get("assignee"))
+        .and_then(|assignee| assignee.as_string())
+        .map(|assignee| assignee.as_str().to_string()))
+}
+
+/// Moves the index entry of the issue `id` from its `old` to its `new` assignee.
+fn index_issue(
+    write_txn: &redb::WriteTransaction,
+    id: &str,
+    old: Option<&str>,
+    new: Option<&str>,
+) -> Result<(), BackendError> {
+    let old = old.map(issue_assignee).transpose()?.flatten();
+    let new = new.map(issue_assignee).transpose()?.flatten();
+    if old == new {
+        return Ok(());
+    }
+    let mut index = write_txn
+        .open_multimap_table(INDEX_ISSUES_BY_ASSIGNEE)
+        .map_err(to_iql_error)?;
+    if let Some(old) = old {
+        index.remove(old.as_str(), id).map_err(to_iql_error)?;
+    }
+    if let Some(new) = new {
+        index.insert(new.as_str(), id).map_err(to_iql_error)?;
+    }
+    Ok(())
+}
+
+/// Versions are zero padded so that the history of an issue is stored in order.
+fn issue_version_key(id: &str, version: u64) -> String {
+    format!("{id}@{version:020}")
+}
+
+/// Appends `entry` to the audit log. Keys are zero-padded sequence numbers, so rows are read in
+/// the order they were written.
+fn record_audit_entry(
+    write_txn: &redb::WriteTransaction,
+    entry: AuditEntry,
+) -> Result<(), BackendError> {
+    let mut audit = write_txn.open_table(TABLE_AUDIT).map_err(to_iql_error)?;
+    let sequence = match audit.last().map_err(to_iql_error)? {
+        Some((key, _)) => key.value().parse::<u64>().map_err(to_iql_error)? + 1,
+        None => 1,
+    };
+    let entry = facet_json::to_string(&entry).map_err(to_iql_error)?;
+    audit
+        .insert(format!("{sequence:020}").as_str(), entry)
+        .map_err(to_iql_error)?;
+    Ok(())
+}
+
+/// Appends the `new` state of the issue `id` to its history, or drops the history if the
+/// issue was deleted. Writes that leave the issue unchanged don't create a version.
+fn record_issue_version(
+    write_txn: &redb::WriteTransaction,
+    id: &str,
+    old: Option<&str>,
+    new: Option<&str>,
+) -> Result<(), BackendError> {
+    if old == new {
+        return Ok(());
+    }
+    let mut history = write_txn
+        .open_table(TABLE_ISSUE_HISTORY)
+        .map_err(to_iql_error)?;
+    // '@' is directly followed by 'A', so this range covers exactly the versions of `id`
+    let min = format!("{id}@");
+    let max = format!("{id}A");
+    match new {
+        Some(new) => {
+            let versions = history
+                .range(min.as_str()..max.as_str())
+                .map_err(to_iql_error)?
+                .count() as u64;
+            history
+                .insert(
+                    issue_version_key(id, versions + 1).as_str(),
+                    new.to_string(),
+                )
+                .map_err(to_iql_error)?;
+        }
+        None => {
+            history
+                .retain_in(min.as_str()..max.as_str(), |_, _| false)
+                .map_err(to_iql_error)?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves the history of the issue `id` over to `new_id`, keeping the version numbers.
+fn move_issue_history(
+    write_txn: &redb::WriteTransaction,
+    id: &str,
+    new_id: &str,
+) -> Result<(), BackendError> {
+    let mut history = write_txn
+        .open_table(TABLE_ISSUE_HISTORY)
+        .map_err(to_iql_error)?;
+    let min = format!("{id}@");
+    let max = format!("{id}A");
+    let mut versions = Vec::new();
+    for entry in history
+        .range(min.as_str()..max.as_str())
+        .map_err(to_iql_error)?
+    {
+        let (key, value) = entry.map_err(to_iql_error)?;
+        versions.push((key.value()[id.len()..].to_string(), value.value()));
+    }
+    history
+        .retain_in(min.as_str()..max.as_str(), |_, _| false)
+        .map_err(to_iql_error)?;
+    for (version, value) in versions {
+        history
+            .insert(format!("{new_id}{version}").as_str(), value)
+            .map_err(to_iql_error)?;
+    }
+    Ok(())
+}
+
+/// Points the links of re-keyed issues at their new ids, given as a map from the old ones.
+fn rekey_links(
+    write_txn: &redb::WriteTransaction,
+    new_ids: &HashMap<String, String>,
+) -> Result<(), BackendError> {
+    let mut links = write_txn.open_table(TABLE_LINKS).map_err(to_iql_error)?;
+    let mut changed = Vec::new();
+    for entry in links.iter().map_err(to_iql_error)? {
+        let (key, value) = entry.map_err(to_iql_error)?;
+        let mut link: LinkInfo = facet_json::from_str(&value.value()).map_err(to_iql_error)?;
+        let source = new_ids.get(&*link.source);
+        let target = new_ids.get(&*link.target);
+        if source.is_none() && target.is_none() {
+            continue;
+        }
+        if let Some(source) = source {
+            link.source = IssueId::new(source);
+        }
+        if let Some(target) = target {
+            link.target = IssueId::new(target);
+        }
+        changed.push((key.value().to_string(), link));
+    }
+    for (key, link) in changed {
+        let link = facet_json::to_string(&link).map_err(to_iql_error)?;
+        links.insert(key.as_str(), link).map_err(to_iql_error)?;
+    }
+    Ok(())
+}
+
+fn write_meta(
+    write_txn: &redb::WriteTransaction,
+    key: &str,
+    value: &str,
+) -> Result<(), BackendError> {
+    let mut meta = write_txn.open_table(TABLE_META).map_err(to_iql_error)?;
+    meta.insert(key, value.to_string()).map_err(to_iql_error)?;
+    Ok(())
+}
+
+fn remove_meta(write_txn: &redb::WriteTransaction, key: &str) -> Result<(), BackendError> {
+    let mut meta = write_txn.open_table(TABLE_META).map_err(to_iql_error)?;
+    meta.remove(key).map_err(to_iql_error)?;
+    Ok(())
+}
+
 fn get_table<'a>(kind: EntityType) -> TableDefinition<'a, &'a str, String> {
     match kind {
         EntityType::Users => TABLE_USERS,
         EntityType::Projects => TABLE_PROJECTS,
         EntityType::Issues => TABLE_ISSUES,
         EntityType::Comments => TABLE_COMMENTS,
+        EntityType::Links => TABLE_LINKS,
+        EntityType::Meta => TABLE_META,
+        EntityType::Audit => TABLE_AUDIT,
+    }
+}
+
+fn describe_entity(entity: EntityType) -> Result<Vec<FieldDescription>, BackendError> {
+    issuecraft_core::describe(entity).ok_or(BackendError::NotSupported)
+}
+
+fn invalid_field(field: &str, reason: String) -> BackendError {
+    BackendError::InvalidField {
+        field: field.to_string(),
+        reason,
     }
 }
 
+/// Only checks the shape `local@domain.tld`, anything stricter is up to the mail server.
+fn check_email(email: &str) -> Result<(), BackendError> {
+    let valid = !email.chars().any(char::is_whitespace)
+        && email.split_once('@').is_some_and(|(local, domain)| {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid_field(
+            "email",
+            format!("'{email}' is not an email address"),
+        ))
+    }
+}
+
+/// `#` is reserved: issue ids are `<project>#<number>` and the issues of a project are looked
+/// up as the key range starting with `<project>#`.
+fn check_project_id(project_id: &ProjectId) -> Result<(), BackendError> {
+    if project_id.contains('#') {
+        return Err(BackendError::InvalidId(format!(
+            "project id '{}' must not contain '#'",
+            &**project_id
+        )));
+    }
+    Ok(())
+}
+
+fn check_title(title: &str) -> Result<(), BackendError> {
+    if title.trim().is_empty() {
+        return Err(invalid_field("title", "must not be empty".to_string()));
+    }
+    Ok(())
+}
+
 impl Database {
     pub fn new(typ: DatabaseType) -> Result<Self, BackendError> {
+        Self::open(typ)?.initialize()
+    }
+
+    /// Like [`Database::new`], but refuses databases written with another schema version
+    /// before any of their records are read.
+    pub fn open_checked(typ: DatabaseType) -> Result<Self, BackendError> {
+        let db = Self::open(typ)?;
+        match db.get_meta(SCHEMA_VERSION_KEY)? {
+            Some(version) if version.parse::<u64>().ok() != Some(SCHEMA_VERSION) => {
+                Err(BackendError::IncompatibleSchemaVersion {
+                    found: version,
+                    expected: SCHEMA_VERSION,
+                })
+            }
+            _ => db.initialize(),
+        }
+    }
+
+    fn open(typ: DatabaseType) -> Result<Self, BackendError> {
         let db = match typ {
             DatabaseType::InMemory => redb::Database::builder()
                 .create_with_backend(InMemoryBackend::new())
                 .map_err(to_iql_error)?,
             DatabaseType::File(path) => redb::Database::create(path).map_err(to_iql_error)?,
         };
-        // TODO: implement proper initialization
-        let mut db = Self { db };
-        db.set(
-            &UserId::new("default"),
-            &UserInfo {
-                name: "Default User".to_string(),
-                display: Some("Default User".to_string()),
-                email: None,
-            },
-        )?;
-        Ok(db)
+        Ok(Self {
+            db,
+            issue_templates: IssueTemplates::default(),
+            unique_project_names: false,
+            read_only: false,
+            pending_audit: Cell::new(None),
+            transaction: None,
+        })
     }
 
-    fn table_exists(&self, table_name: &str) -> Result<bool, BackendError> {
-        let read_txn = self.db.begin_read().map_err(to_iql_error)?;
-        Ok(read_txn
-            .list_tables()
-            .map_err(to_iql_error)?
-            .any(|table| table.name() == table_name))
+    // TODO: implement proper initialization
+    fn initialize(mut self) -> Result<Self, BackendError> {
+        if self.get_meta(SCHEMA_VERSION_KEY)?.is_none() {
+            self.set_meta(SCHEMA_VERSION_KEY, &SCHEMA_VERSION.to_string())?;
+        }
+        self.set(&UserId::new(DEFAULT_USER), &default_user())?;
+        Ok(self)
     }
 
-    fn exists<ID: EntityId>(&self, id: &ID) -> Result<bool, BackendError> {
-        let read_txn = self.db.begin_read().map_err(to_iql_error)?;
-        {
-            let table_definition = get_table(ID::kind());
-            if !self.table_exists(table_definition.name())? {
-                return Ok(false);
-            }
-            let table = read_txn
-                .open_table(table_definition)
-                .map_err(to_iql_error)?;
-            Ok(table
-                .iter()
-                .map_err(to_iql_error)?
-                .any(|entry| match entry {
-                    Ok(e) => e.0.value() == &**id,
-                    Err(_) => false,
-                }))
-        }
+    /// Replaces the policy for which fields new issues of each kind must be created with.
+    #[must_use]
+    pub fn with_issue_templates(mut self, issue_templates: IssueTemplates) -> Self {
+        self.issue_templates = issue_templates;
+        self
     }
 
-    fn get_next_issue_id(&self, project: &ProjectId) -> Result<u64, BackendError> {
-        if !self.table_exists(TABLE_ISSUES.name())? {
-            return Ok(1);
+    /// Rejects projects whose display name is already used by another project. Off by default.
+    #[must_use]
+    pub fn with_unique_project_names(mut self, unique_project_names: bool) -> Self {
+        self.unique_project_names = unique_project_names;
+        self
+    }
+
+    /// Rejects every query that could write, e.g. to serve a backup. Off by default.
+    #[must_use]
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    fn check_project_name(&self, id: &ProjectId, name: Option<&str>) -> Result<(), BackendError> {
+        let Some(name) = name.filter(|_| self.unique_project_names) else {
+            return Ok(());
+        };
+        let taken = self
+            .matching_keys(
+                EntityType::Projects,
+                Some(&issuecraft_ql::field("name").eq(name)),
+            )?
+            .iter()
+            .any(|key| key != &**id);
+        if taken {
+            return Err(BackendError::DuplicateDisplayName(name.to_string()));
         }
-        let read_txn = self.db.begin_read().map_err(to_iql_error)?;
-        let min = format!("{project}#");
-        let max = format!("{project}#{}", u64::MAX);
-        let next = read_txn
-            .open_table(TABLE_ISSUES)
-            .map_err(to_iql_error)?
-            .range(min.as_str()..max.as_str())
-            .map_err(to_iql_error)?
-            .count()
-            + 1;
-        Ok(u64::try_from(next).expect("Maximum issue count exceeded"))
+        Ok(())
     }
 
-    fn delete<ID: EntityId>(&mut self, id: &ID) -> Result<(), BackendError> {
-        let write_txn = self.db.begin_write().map_err(to_iql_error)?;
-        {
-            let table_definition = get_table(ID::kind());
-            let mut table = write_txn
-                .open_table(table_definition)
-                .map_err(to_iql_error)?;
-            table.remove(&**id).map_err(to_iql_error)?;
+    fn table_exists(&self, table_name: &str) -> Result<bool, BackendError> {
+        let tables = match &self.transaction {
+            Some(write_txn) => write_txn
+                .list_tables()
+                .map_err(to_iql_error)?
+                .collect::<Vec<_>>(),
+            None => self
+                .db
+                .begin_read()
+                .map_err(to_iql_error)?
+                .list_tables()
+                .map_err(to_iql_error)?
+                .collect(),
+        };
+        Ok(tables.iter().any(|table| table.name() == table_name))
+    }
+
+    /// Opens a table for reading, through the open transaction if there is one. Returns `None`
+    /// if the table was never written.
+    fn read_table(
+        &self,
+        table_definition: TableDefinition<&'static str, String>,
+    ) -> Result<Option<ReadTable<'_>>, BackendError> {
+        if !self.table_exists(table_definition.name())? {
+            return Ok(None);
         }
-        write_txn.commit().map_err(to_iql_error)
+        let table = match &self.transaction {
+            Some(write_txn) => ReadTable::Transaction(
+                write_txn
+                    .open_table(table_definition)
+                    .map_err(to_iql_error)?,
+            ),
+            None => ReadTable::Committed(
+                self.db
+                    .begin_read()
+                    .map_err(to_iql_error)?
+                    .open_table(table_definition)
+                    .map_err(to_iql_error)?,
+            ),
+        };
+        Ok(Some(table))
     }
 
-    fn delete_comment(
+    /// Runs `f` as part of the open transaction. Without one, `f` gets a transaction of its own
+    /// that is committed if it succeeds.
+    fn with_transaction<T>(
         &mut self,
-        id: &CommentId,
-        result: &mut ExecutionResult,
-    ) -> Result<(), BackendError> {
-        self.delete(id)?;
-        result.inc();
-        Ok(())
+        f: impl FnOnce(&Self, &redb::WriteTransaction) -> Result<T, BackendError>,
+    ) -> Result<T, BackendError> {
+        let own_transaction = self.transaction.is_none();
+        if own_transaction {
+            self.begin()?;
+        }
+        let write_txn = self
+            .transaction
+            .as_ref()
+            .expect("A transaction is open at this point");
+        let result = f(self, write_txn);
+        if own_transaction {
+            match result {
+                Ok(_) => self.commit()?,
+                Err(_) => self.rollback()?,
+            }
+        }
+        result
     }
 
-    fn delete_issue(
-        &mut self,
-        id: &IssueId,
-        result: &mut ExecutionResult,
-    ) -> Result<(), BackendError> {
-        self.delete(id)?;
-        result.inc();
-
-        for comment in self.get_all::<CommentId>(&SelectStatement {
-            columns: issuecraft_ql::Columns::All,
-            from: EntityType::Comments,
-            filter: Some(issuecraft_ql::FilterExpression::Comparison {
-                field: "issue".to_string(),
-                op: issuecraft_ql::ComparisonOp::Equal,
-                value: issuecraft_ql::IqlValue::String(id.to_string()),
-            }),
-            order_by: None,
-            limit: None,
-            offset: None,
-        })? {
-            self.delete_comment(&comment.key, result)?;
+    fn exists<ID: EntityId>(&self, id: &ID) -> Result<bool, BackendError> {
+        let Some(table) = self.read_table(get_table(ID::kind()))? else {
+            return Ok(false);
+        };
+        Ok(table.get(id)?.is_some())
+    }
+
+    fn check_user_exists(&self, id: &UserId) -> Result<(), BackendError> {
+        if !self.exists(id)? {
+            return Err(BackendError::UserNotFound { id: id.to_string() });
         }
         Ok(())
     }
 
-    fn delete_project(
-        &mut self,
-        id: &ProjectId,
-        result: &mut ExecutionResult,
-    ) -> Result<(), BackendError> {
-        self.delete(id)?;
-        result.inc();
-
-        for issue in self.get_all::<IssueId>(&SelectStatement {
-            columns: issuecraft_ql::Columns::All,
-            from: EntityType::Comments,
-            filter: Some(issuecraft_ql::FilterExpression::Comparison {
-                field: "issue".to_string(),
-                op: issuecraft_ql::ComparisonOp::Equal,
-                value: issuecraft_ql::IqlValue::String(id.to_string()),
-            }),
-            order_by: None,
-            limit: None,
-            offset: None,
-        })? {
-            self.delete_issue(&issue.key, result)?;
+    fn get_next_issue_id(&self, project: &ProjectId) -> Result<u64, BackendError> {
+        if let Some(counter) = self.get_meta(&issue_counter_key(project))? {
+            let counter = counter.parse::<u64>().map_err(to_iql_error)?;
+            return Ok(counter + 1);
         }
-        Ok(())
+        // Databases created before the counters were introduced only have the issue keys
+        let Some(issues) = self.read_table(TABLE_ISSUES)? else {
+            return Ok(1);
+        };
+        let min = format!("{project}#");
+        let max = format!("{project}$");
+        let mut high_water_mark = 0;
+        for entry in issues.range(min.as_str()..max.as_str())? {
+            let (key, _) = entry.map_err(to_iql_error)?;
+            if let Some(number) = parse_issue_number(key.value()) {
+                high_water_mark = high_water_mark.max(number);
+            }
+        }
+        Ok(high_water_mark + 1)
     }
 
-    fn update<ID: EntityId>(
-        &mut self,
-        id: &ID,
-        updates: &[FieldUpdate],
-    ) -> Result<(), BackendError> {
-        let mut item_info: Value = self.get_as(id)?;
-        for update in updates {
-            update.apply_to::<ID::EntityType>(&mut item_info)?;
+    fn get_next_comment_number(&self, issue: &IssueId) -> Result<u64, BackendError> {
+        match self.get_meta(&comment_counter_key(issue))? {
+            Some(counter) => Ok(counter.parse::<u64>().map_err(to_iql_error)? + 1),
+            None => Ok(1),
         }
-        self.set_from_value(id, &item_info)?;
-        Ok(())
     }
 
-    fn set_from_value<ID: EntityId, V: Facet<'static>>(
-        &mut self,
-        id: &ID,
-        info: &V,
-    ) -> Result<(), BackendError> {
-        let write_txn = self.db.begin_write().map_err(to_iql_error)?;
-        {
-            let table_definition = get_table(ID::kind());
-            let mut table = write_txn
-                .open_table(table_definition)
-                .map_err(to_iql_error)?;
-            let info_str = facet_json::to_string(info).map_err(to_iql_error)?;
-            table.insert(&**id, &info_str).map_err(to_iql_error)?;
+    fn get_meta(&self, key: &str) -> Result<Option<String>, BackendError> {
+        match self.read_table(TABLE_META)? {
+            Some(table) => table.get(key),
+            None => Ok(None),
         }
-        write_txn.commit().map_err(to_iql_error)
     }
 
-    fn set<ID: EntityId>(&mut self, id: &ID, info: &ID::EntityType) -> Result<(), BackendError> {
-        self.set_from_value(id, info)
+    fn set_meta(&mut self, key: &str, value: &str) -> Result<(), BackendError> {
+        self.with_transaction(|_, write_txn| write_meta(write_txn, key, value))
     }
 
-    fn get_all<K: EntityId>(
-        &self,
-        SelectStatement {
-            columns: _,
-            from,
-            filter,
-            order_by,
-            limit,
-            offset,
-        }: &SelectStatement,
-    ) -> Result<Vec<Entry<K>>, BackendError> {
-        let read_txn = self.db.begin_read().map_err(to_iql_error)?;
-        {
-            let table_definition = get_table(*from);
-            if !read_txn
-                .list_tables()
-                .unwrap()
-                .any(|table| table.name() == table_definition.name())
-            {
-                return Ok(vec![]);
+    /// Dumps the entities of every table as JSON, to be loaded again with [`Database::import`].
+    /// Indexes, the history and the audit log are left out, since they aren't entities.
+    pub fn export(&self) -> Result<String, BackendError> {
+        let mut tables = Vec::new();
+        for kind in ENTITY_TYPES {
+            let table_definition = get_table(kind);
+            let mut entries = Vec::new();
+            if let Some(table) = self.read_table(table_definition)? {
+                for entry in table.iter()? {
+                    let (key, value) = entry.map_err(to_iql_error)?;
+                    entries.push(UntypedEntry {
+                        key: key.value().to_string(),
+                        value: facet_json::from_str(&value.value()).map_err(to_iql_error)?,
+                    });
+                }
             }
-            let table = read_txn
+            tables.push(DumpTable {
+                name: table_definition.name().to_string(),
+                entries,
+            });
+        }
+        facet_json::to_string(&Dump {
+            version: DUMP_VERSION,
+            tables,
+        })
+        .map_err(to_iql_error)
+    }
+
+    /// Copies every table into a new in-memory database, e.g. to try out queries without
+    /// changing this one. The copy keeps the settings but is never read-only.
+    pub fn snapshot(&self) -> Result<Database, BackendError> {
+        let mut snapshot = Self::open(DatabaseType::InMemory)?
+            .with_issue_templates(self.issue_templates.clone())
+            .with_unique_project_names(self.unique_project_names);
+        let write_txn = snapshot.db.begin_write().map_err(to_iql_error)?;
+        for table_definition in TABLES {
+            let Some(source) = self.read_table(table_definition)? else {
+                continue;
+            };
+            let mut target = write_txn
                 .open_table(table_definition)
                 .map_err(to_iql_error)?;
-            let mut values = table
-                .iter()
-                .map_err(to_iql_error)?
-                .map(|entry| {
-                    entry.map_err(to_iql_error).map(|entry| {
-                        facet_json::from_str::<Value>(&entry.1.value())
-                            .map(|v| (K::from_str(entry.0.value()), v))
-                    })
-                })
-                .skip(
-                    usize::try_from(offset.unwrap_or(0))
-                        .expect("Number exceeds max supported value"),
-                )
-                .take(
-                    usize::try_from(limit.unwrap_or(u64::MAX))
-                        .expect("Number exceeds max supported value"),
-                )
-                .collect::<Result<Result<Vec<_>, _>, _>>()?
-                .map_err(to_iql_error)?;
-            if let Some(order_by) = order_by {
-                values.sort_by(|a, b| {
-                    let o1 = a.1.as_object().unwrap();
-                    let o2 = b.1.as_object().unwrap();
-                    match (
-                        o1.get(&order_by.field.clone()),
-                        o2.get(&order_by.field.clone()),
-                    ) {
-                        (None, None) => std::cmp::Ordering::Equal,
-                        (Some(_), None) => std::cmp::Ordering::Greater,
-                        (None, Some(_)) => std::cmp::Ordering::Less,
-                        (Some(v1), Some(v2)) => v1.partial_cmp(v2).unwrap(),
-                    }
-                });
+            for entry in source.iter()? {
+                let (key, value) = entry.map_err(to_iql_error)?;
+                target
+                    .insert(key.value(), value.value())
+                    .map_err(to_iql_error)?;
             }
+        }
+        write_txn.commit().map_err(to_iql_error)?;
+        // The index is derived from the issues, so it is rebuilt rather than copied
+        snapshot.reindex()?;
+        Ok(snapshot)
+    }
 
-            values
-                .into_iter()
-                .filter(|(k, v)| match filter {
-                    None => true,
-                    Some(filter_expr) => filter_expr.matches(k, v),
-                })
-                .map(|(k, v)| {
-                    from_value::<K::EntityType>(v)
-                        .map_err(to_iql_error)
-                        .map(|v| Entry { key: k, value: v })
-                })
-                .collect::<Result<Vec<_>, _>>()
-        }
+    /// Loads a dump created by [`Database::export`] and returns how many entities it held.
+    ///
+    /// Unless `merge` is set, the import fails on the first entity that already exists, even if
+    /// it is unchanged, and nothing is written. With `merge`, the dumped entity replaces it. The
+    /// default user counts as existing only once it was changed, so a fresh database imports
+    /// without a merge. Entities that do not decode, or refer to projects and issues that are
+    /// neither dumped nor stored, fail the import as well.
+    pub fn import(&mut self, json: &str, merge: bool) -> Result<usize, BackendError> {
+        let dump = parse_dump(json)?;
+        let initial_default_user = facet_json::to_string(&default_user()).map_err(to_iql_error)?;
+        self.with_transaction(|db, write_txn| {
+            db.check_dump(&dump)?;
+            for DumpTable { name, entries } in &dump.tables {
+                let kind = dump_table_kind(name)?;
+                let mut table = write_txn
+                    .open_table(get_table(kind))
+                    .map_err(to_iql_error)?;
+                for UntypedEntry { key, value } in entries {
+                    if !merge {
+                        let exists = match table.get(key.as_str()).map_err(to_iql_error)? {
+                            Some(existing) => {
+                                kind != EntityType::Users
+                                    || key != DEFAULT_USER
+                                    || existing.value() != initial_default_user
+                            }
+                            None => false,
+                        };
+                        if exists {
+                            return Err(BackendError::ItemAlreadyExists {
+                                kind: kind.singular().to_string(),
+                                id: key.clone(),
+                            });
+                        }
+                    }
+                    let value = facet_json::to_string(value).map_err(to_iql_error)?;
+                    let old = table
+                        .insert(key.as_str(), &value)
+                        .map_err(to_iql_error)?
+                        .map(|old| old.value());
+                    if kind == EntityType::Issues {
+                        index_issue(write_txn, key, old.as_deref(), Some(&value))?;
+                        record_issue_version(write_txn, key, old.as_deref(), Some(&value))?;
+                    }
+                }
+            }
+
+            let mut high_water_marks = HashMap::<String, u64>::new();
+            let issues = write_txn.open_table(TABLE_ISSUES).map_err(to_iql_error)?;
+            for entry in issues.iter().map_err(to_iql_error)? {
+                let (key, _) = entry.map_err(to_iql_error)?;
+                if let Some((project, _)) = key.value().rsplit_once('#')
+                    && let Some(number) = parse_issue_number(key.value())
+                {
+                    let mark = high_water_marks.entry(project.to_string()).or_default();
+                    *mark = (*mark).max(number);
+                }
+            }
+            let comments = write_txn.open_table(TABLE_COMMENTS).map_err(to_iql_error)?;
+            let mut comment_high_water_marks = HashMap::<String, u64>::new();
+            for entry in comments.iter().map_err(to_iql_error)? {
+                let (key, _) = entry.map_err(to_iql_error)?;
+                if let Some((issue, number)) = parse_comment_number(key.value()) {
+                    let mark = comment_high_water_marks
+                        .entry(issue.to_string())
+                        .or_default();
+                    *mark = (*mark).max(number);
+                }
+            }
+            let mut meta = write_txn.open_table(TABLE_META).map_err(to_iql_error)?;
+            let counters = high_water_marks
+                .into_iter()
+                .map(|(project, number)| (issue_counter_key(&project), number))
+                .chain(
+                    comment_high_water_marks
+                        .into_iter()
+                        .map(|(issue, number)| (comment_counter_key(&issue), number)),
+                );
+            for (key, number) in counters {
+                let current = meta
+                    .get(key.as_str())
+                    .map_err(to_iql_error)?
+                    .and_then(|value| value.value().parse::<u64>().ok())
+                    .unwrap_or(0);
+                if number > current {
+                    meta.insert(key.as_str(), number.to_string())
+                        .map_err(to_iql_error)?;
+                }
+            }
+            Ok(())
+        })?;
+        Ok(dump.tables.iter().map(|table| table.entries.len()).sum())
     }
 
-    fn get<ID: EntityId>(&self, key: &ID) -> Result<ID::EntityType, BackendError> {
-        self.get_as(key)
+    /// Checks that every entry of `dump` is an entity of the kind its table holds, and that the
+    /// projects and issues they refer to are in the dump or already stored.
+    fn check_dump(&self, dump: &Dump) -> Result<(), BackendError> {
+        let mut dumped = HashMap::<EntityType, HashSet<&str>>::new();
+        let mut references = Vec::new();
+        for DumpTable { name, entries } in &dump.tables {
+            let kind = dump_table_kind(name)?;
+            for UntypedEntry { key, value } in entries {
+                let invalid = |err| {
+                    BackendError::ImplementationSpecific(format!(
+                        "Invalid {} '{key}' in dump: {err}",
+                        kind.singular()
+                    ))
+                };
+                match kind {
+                    EntityType::Users => {
+                        from_value::<UserInfo>(value.clone()).map_err(invalid)?;
+                    }
+                    EntityType::Projects => {
+                        from_value::<ProjectInfo>(value.clone()).map_err(invalid)?;
+                    }
+                    EntityType::Issues => {
+                        let issue = from_value::<IssueInfo>(value.clone()).map_err(invalid)?;
+                        references.push((EntityType::Projects, issue.project.to_string()));
+                    }
+                    EntityType::Comments => {
+                        let comment = from_value::<CommentInfo>(value.clone()).map_err(invalid)?;
+                        references.push((EntityType::Issues, comment.issue.to_string()));
+                    }
+                    EntityType::Links => {
+                        let link = from_value::<LinkInfo>(value.clone()).map_err(invalid)?;
+                        references.push((EntityType::Issues, link.source.to_string()));
+                        references.push((EntityType::Issues, link.target.to_string()));
+                    }
+                    _ => unreachable!("Dumps only hold entity tables"),
+                }
+                dumped.entry(kind).or_default().insert(key);
+            }
+        }
+        for (kind, id) in references {
+            let found = dumped
+                .get(&kind)
+                .is_some_and(|ids| ids.contains(id.as_str()))
+                || match kind {
+                    EntityType::Projects => self.exists(&ProjectId::new(&id))?,
+                    _ => self.exists(&IssueId::new(&id))?,
+                };
+            if !found {
+                return Err(BackendError::ItemNotFound {
+                    kind: kind.singular().to_string(),
+                    id,
+                });
+            }
+        }
+        Ok(())
     }
 
-    fn get_as<ID: EntityId, T: Facet<'static>>(&self, key: &ID) -> Result<T, BackendError> {
-        let read_txn = self.db.begin_read().map_err(to_iql_error)?;
-        {
-            let table_definition = get_table(ID::kind());
-            let table = read_txn
-                .open_table(table_definition)
+    /// Drops the secondary indexes and rebuilds them by scanning the primary tables.
+    pub fn reindex(&mut self) -> Result<(), BackendError> {
+        self.with_transaction(|_, write_txn| {
+            write_txn
+                .delete_multimap_table(INDEX_ISSUES_BY_ASSIGNEE)
                 .map_err(to_iql_error)?;
-            let info = table
-                .get(&**key)
+            let issues = write_txn.open_table(TABLE_ISSUES).map_err(to_iql_error)?;
+            let mut index = write_txn
+                .open_multimap_table(INDEX_ISSUES_BY_ASSIGNEE)
+                .map_err(to_iql_error)?;
+            for entry in issues.iter().map_err(to_iql_error)? {
+                let (key, value) = entry.map_err(to_iql_error)?;
+                if let Some(assignee) = issue_assignee(&value.value())? {
+                    index
+                        .insert(assignee.as_str(), key.value())
+                        .map_err(to_iql_error)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Looks up the issues assigned to `assignee` through the assignee index.
+    pub fn issues_assigned_to(&self, assignee: &UserId) -> Result<Vec<IssueId>, BackendError> {
+        if let Some(write_txn) = &self.transaction {
+            let indexed = write_txn
+                .list_multimap_tables()
                 .map_err(to_iql_error)?
-                .ok_or_else(|| BackendError::ItemNotFound {
-                    id: key.to_string(),
-                    kind: ID::kind().to_string(),
-                })?
-                .value();
-            facet_json::from_str(&info).map_err(to_iql_error)
+                .any(|table| table.name() == INDEX_ISSUES_BY_ASSIGNEE.name());
+            if !indexed {
+                return Ok(Vec::new());
+            }
+            let index = write_txn
+                .open_multimap_table(INDEX_ISSUES_BY_ASSIGNEE)
+                .map_err(to_iql_error)?;
+            return assigned_issues(&index, assignee);
+        }
+        let read_txn = self.db.begin_read().map_err(to_iql_error)?;
+        match read_txn.open_multimap_table(INDEX_ISSUES_BY_ASSIGNEE) {
+            Ok(index) => assigned_issues(&index, assignee),
+            Err(TableError::TableDoesNotExist(_)) => Ok(Vec::new()),
+            Err(err) => Err(to_iql_error(err)),
         }
     }
-}
 
-fn stringify<'a, T: Facet<'a>>(value: &'a T) -> String {
-    facet_json::to_string(value).unwrap()
-}
+    /// The keys of every entry of `entity` in key order, read without touching the values.
+    ///
+    /// Archived issues and projects are included.
+    pub fn keys(&self, entity: EntityType) -> Result<Vec<String>, BackendError> {
+        self.keys_after(entity, None)
+    }
 
-fn to_iql_error<E: Display>(err: E) -> BackendError {
-    BackendError::ImplementationSpecific(format!("{err}"))
-}
+    fn keys_after(
+        &self,
+        entity: EntityType,
+        after: Option<&str>,
+    ) -> Result<Vec<String>, BackendError> {
+        let Some(table) = self.read_table(get_table(entity))? else {
+            return Ok(Vec::new());
+        };
+        let entries = match after {
+            Some(after) => table.range((Bound::Excluded(after), Bound::Unbounded)),
+            None => table.iter(),
+        };
+        entries?
+            .map(|entry| Ok(entry.map_err(to_iql_error)?.0.value().to_string()))
+            .collect()
+    }
 
-#[async_trait]
-#[allow(clippy::too_many_lines)]
-impl ExecutionEngine for Database {
-    async fn execute<AP: AuthorizationProvider + Sync>(
+    /// Renders an issue with its comments as a Markdown document for sharing.
+    pub fn issue_to_markdown(&self, id: &IssueId) -> Result<String, BackendError> {
+        let issue = self.get(id)?;
+        let mut comments = self
+            .matching_keys(
+                EntityType::Comments,
+                Some(&issuecraft_ql::field("issue").eq(&**id)),
+            )?
+            .iter()
+            .map(|key| self.get(&CommentId::new(key)))
+            .collect::<Result<Vec<_>, _>>()?;
+        comments.sort_by_key(|comment| comment.number);
+
+        let status = match &issue.status {
+            IssueStatus::Open => "Open".to_string(),
+            IssueStatus::Assigned => "Assigned".to_string(),
+            IssueStatus::Blocked => "Blocked".to_string(),
+            IssueStatus::Closed { reason } => format!("Closed ({reason})"),
+        };
+        let priority = issue
+            .priority
+            .as_ref()
+            .map_or_else(|| "-".to_string(), ToString::to_string);
+        let mut markdown = format!(
+            "# {}\n\n\
+             | Field | Value |\n\
+             | --- | --- |\n\
+             | ID | {} |\n\
+             | Status | {status} |\n\
+             | Priority | {priority} |\n\
+             | Assignee | {} |\n\
+             | Kind | {} |\n",
+            escape_markdown(&issue.title),
+            &**id,
+            issue.assignee,
+            issue.kind
+        );
+        if let Some(description) = &issue.description {
+            markdown.push_str(&format!("\n{}\n", escape_markdown(description)));
+        }
+        if !comments.is_empty() {
+            markdown.push_str("\n## Comments\n");
+        }
+        for comment in comments {
+            let created_at = comment.created_at;
+            markdown.push_str(&format!(
+                "\n**{}** on {} {:02}:{:02} UTC\n\n{}\n",
+                comment.author,
+                created_at.date(),
+                created_at.hour(),
+                created_at.minute(),
+                comment.content
+            ));
+        }
+        Ok(markdown)
+    }
+
+    /// Lists the fields that changed between two versions of an issue. The first version is
+    /// the issue as it was created, every change to it adds another one.
+    pub fn diff_issue(
+        &self,
+        id: &IssueId,
+        from_version: u64,
+        to_version: u64,
+    ) -> Result<Vec<FieldChange>, BackendError> {
+        let missing = |version: u64| BackendError::ItemNotFound {
+            kind: EntityType::Issues.singular().to_string(),
+            id: issue_version_key(id, version),
+        };
+        let Some(history) = self.read_table(TABLE_ISSUE_HISTORY)? else {
+            return Err(missing(from_version));
+        };
+        let version = |version: u64| -> Result<Value, BackendError> {
+            let value = history
+                .get(issue_version_key(id, version).as_str())?
+                .ok_or_else(|| missing(version))?;
+            facet_json::from_str::<Value>(&value).map_err(to_iql_error)
+        };
+        let (from, to) = (version(from_version)?, version(to_version)?);
+        let (Some(from), Some(to)) = (from.as_object(), to.as_object()) else {
+            return Err(BackendError::ImplementationSpecific(format!(
+                "The history of '{}' is corrupt",
+                &**id
+            )));
+        };
+
+        let fields = from
+            .iter()
+            .chain(to.iter())
+            .map(|(field, _)| field.as_str().to_string())
+            .collect::<std::collections::BTreeSet<_>>();
+        Ok(fields
+            .into_iter()
+            .filter_map(|field| {
+                let old = from.get(&field).cloned();
+                let new = to.get(&field).cloned();
+                (old != new).then_some(FieldChange { field, old, new })
+            })
+            .collect())
+    }
+
+    fn delete<ID: EntityId>(&mut self, id: &ID) -> Result<(), BackendError> {
+        self.with_transaction(|db, write_txn| db.remove_entity(write_txn, id))
+    }
+
+    /// Removes `id` as part of `write_txn`, along with its issue index entry, its issue history
+    /// and the pending audit entry.
+    fn remove_entity<ID: EntityId>(
+        &self,
+        write_txn: &redb::WriteTransaction,
+        id: &ID,
+    ) -> Result<(), BackendError> {
+        let table_definition = get_table(ID::kind());
+        let mut table = write_txn
+            .open_table(table_definition)
+            .map_err(to_iql_error)?;
+        let old = table
+            .remove(&**id)
+            .map_err(to_iql_error)?
+            .map(|old| old.value());
+        if ID::kind() == EntityType::Issues {
+            index_issue(write_txn, id, old.as_deref(), None)?;
+            record_issue_version(write_txn, id, old.as_deref(), None)?;
+        }
+        if let Some(audit) = self.pending_audit.take() {
+            record_audit_entry(write_txn, audit.into_entry(id))?;
+        }
+        Ok(())
+    }
+
+    /// Removes the issue `id` and its comments as part of `write_txn`.
+    fn delete_issue(
+        &self,
+        write_txn: &redb::WriteTransaction,
+        id: &IssueId,
+        deleted: &mut Vec<String>,
+    ) -> Result<(), BackendError> {
+        self.remove_entity(write_txn, id)?;
+        deleted.push(id.to_string());
+
+        for comment in self.issue_comments(id)? {
+            self.remove_entity(write_txn, &comment)?;
+            deleted.push(comment.to_string());
+        }
+        Ok(())
+    }
+
+    /// Stores the issue `id` under the next number of `project` together with its comments,
+    /// history and links, and removes it from its old key in the same transaction. Returns the
+    /// new id.
+    fn move_issue(
         &mut self,
-        authorization_provider: &AP,
-        user: UserId,
-        query: &IqlQuery,
-    ) -> Result<ExecutionResult, BackendError> {
-        match query {
-            issuecraft_ql::IqlQuery::Select(select_statement) => {
-                let result = match select_statement.from {
-                    issuecraft_ql::EntityType::Users => {
-                        let result = self.get_all::<UserId>(select_statement)?;
-                        stringify(&result)
-                    }
-                    issuecraft_ql::EntityType::Projects => {
-                        let result = self.get_all::<ProjectId>(select_statement)?;
-                        stringify(&result)
-                    }
-                    issuecraft_ql::EntityType::Issues => {
-                        let result = self.get_all::<IssueId>(select_statement)?;
-                        stringify(&result)
-                    }
-                    issuecraft_ql::EntityType::Comments => {
-                        let result = self.get_all::<CommentId>(select_statement)?;
-                        stringify(&result)
-                    }
-                };
-                Ok(ExecutionResult::zero().data(result).build())
+        id: &IssueId,
+        project: &ProjectId,
+        user: &UserId,
+    ) -> Result<IssueId, BackendError> {
+        let issue_info: IssueInfo = self.get(id)?;
+        let issue_number = self.get_next_issue_id(project)?;
+        let new_id = IssueId::new(&format!("{project}#{issue_number}"));
+        self.with_transaction(|db, write_txn| {
+            db.rekey_issue(
+                write_txn,
+                id,
+                &new_id,
+                &IssueInfo {
+                    project: project.clone(),
+                    updated_at: Some(issuecraft_core::now()),
+                    updated_by: Some(user.clone()),
+                    ..issue_info
+                },
+            )?;
+            rekey_links(
+                write_txn,
+                &HashMap::from([(id.to_string(), new_id.to_string())]),
+            )?;
+            write_meta(
+                write_txn,
+                &issue_counter_key(project),
+                &issue_number.to_string(),
+            )
+        })?;
+        Ok(new_id)
+    }
+
+    /// Changes the id of the project `id` to `new_id`, renaming its issues from `id#N` to
+    /// `new_id#N` along with their comments, history and links, all in one transaction.
+    fn rename_project(
+        &mut self,
+        id: &ProjectId,
+        new_id: &ProjectId,
+        user: &UserId,
+    ) -> Result<(), BackendError> {
+        let project: ProjectInfo = self.get(id)?;
+        let issues = self.project_issues(id)?;
+        self.with_transaction(|db, write_txn| {
+            db.write_entity(
+                write_txn,
+                new_id,
+                &ProjectInfo {
+                    updated_by: Some(user.clone()),
+                    ..project
+                },
+            )?;
+            db.remove_entity(write_txn, id)?;
+            if let Some(counter) = db.get_meta(&issue_counter_key(id))? {
+                write_meta(write_txn, &issue_counter_key(new_id), &counter)?;
+                remove_meta(write_txn, &issue_counter_key(id))?;
             }
-            issuecraft_ql::IqlQuery::Create(create_statement) => match create_statement {
-                issuecraft_ql::CreateStatement::User { .. } => Err(BackendError::NotSupported),
-                issuecraft_ql::CreateStatement::Project {
-                    project_id,
-                    name,
-                    description,
-                    owner,
-                } => {
-                    if self.exists(project_id)? {
-                        return Err(BackendError::ProjectAlreadyExists(project_id.to_string()));
-                    }
-                    let owner = match owner {
-                        Some(owner) => owner.clone(),
-                        None => user.clone(),
-                    };
 
-                    if !authorization_provider
-                        .check_authorization(
-                            &user,
-                            &Action::Create,
-                            &Resource::Project,
-                            Some(value! ({
-                                "owner": (owner.to_string())
-                            })),
-                        )
-                        .await?
-                        .status
-                        .is_authorized()
-                    {
-                        return Err(BackendError::PermissionDenied(user.to_string()));
-                    }
+            let prefix = format!("{}#", &**id);
+            let mut new_ids = HashMap::new();
+            for issue in issues {
+                let issue_info: IssueInfo = db.get(&issue)?;
+                let number = issue.strip_prefix(&prefix).unwrap_or(&issue);
+                let new_issue = IssueId::new(&format!("{}#{number}", &**new_id));
+                db.rekey_issue(
+                    write_txn,
+                    &issue,
+                    &new_issue,
+                    &IssueInfo {
+                        project: new_id.clone(),
+                        ..issue_info
+                    },
+                )?;
+                new_ids.insert(issue.to_string(), new_issue.to_string());
+            }
+            // Links between two issues of the project are re-keyed at both ends at once
+            rekey_links(write_txn, &new_ids)
+        })
+    }
 
-                    if !self.exists(&owner)? {
-                        return Err(BackendError::UserNotFound {
-                            id: owner.to_string(),
-                        });
-                    }
-                    let project_info = ProjectInfo {
-                        owner,
-                        description: description.clone(),
-                        name: name.clone(),
-                    };
-                    self.set(project_id, &project_info)?;
-                    Ok(ExecutionResult::one().build())
-                }
-                issuecraft_ql::CreateStatement::Issue {
-                    project,
-                    kind,
-                    title,
-                    description,
-                    priority,
-                    assignee,
-                } => {
-                    if !self.exists(project)? {
-                        return Err(BackendError::ItemNotFound {
-                            kind: EntityType::Projects.to_string(),
-                            id: project.to_string(),
-                        });
-                    }
+    /// Stores `issue` under `new_id` and removes the issue `id` as part of `write_txn`,
+    /// re-keying its history and comments along. Links are left to [`rekey_links`].
+    fn rekey_issue(
+        &self,
+        write_txn: &redb::WriteTransaction,
+        id: &IssueId,
+        new_id: &IssueId,
+        issue: &IssueInfo,
+    ) -> Result<(), BackendError> {
+        move_issue_history(write_txn, id, new_id)?;
+        self.write_entity(write_txn, new_id, issue)?;
+        self.remove_entity(write_txn, id)?;
 
-                    let project_owner = self.get(project)?.owner;
-                    if !authorization_provider
-                        .check_authorization(
-                            &user,
-                            &Action::Create,
-                            &Resource::Issue,
-                            Some(value! ({
-                                "project_owner": (project_owner.to_string()),
-                                "project": (project.to_string())
-                            })),
-                        )
-                        .await?
-                        .status
-                        .is_authorized()
-                    {
-                        return Err(BackendError::PermissionDenied(user.to_string()));
-                    }
+        // Comment ids start with the id of their issue, so they are re-keyed as well
+        let prefix = format!("{}/", &**id);
+        for comment in self.issue_comments(id)? {
+            let comment_info: CommentInfo = self.get(&comment)?;
+            let suffix = comment.strip_prefix(&prefix).unwrap_or(&comment);
+            self.write_entity(
+                write_txn,
+                &CommentId::from_str(&format!("{}/{suffix}", &**new_id)),
+                &CommentInfo {
+                    issue: new_id.clone(),
+                    ..comment_info
+                },
+            )?;
+            self.remove_entity(write_txn, &comment)?;
+        }
+        if let Some(counter) = self.get_meta(&comment_counter_key(id))? {
+            write_meta(write_txn, &comment_counter_key(new_id), &counter)?;
+            remove_meta(write_txn, &comment_counter_key(id))?;
+        }
+        Ok(())
+    }
 
-                    let assignee = match assignee {
-                        Some(assignee) => assignee.clone(),
-                        None => user.clone(),
-                    };
-                    let issue_number = self.get_next_issue_id(project)?;
-                    let issue_info = IssueInfo {
-                        title: title.clone(),
-                        kind: kind.clone(),
-                        description: description.clone(),
-                        status: IssueStatus::Open,
-                        project: project.clone(),
-                        author: user,
-                        assignee,
-                        priority: priority.clone().map(|p| match p {
-                            issuecraft_ql::Priority::Critical => Priority::Critical,
-                            issuecraft_ql::Priority::High => Priority::High,
-                            issuecraft_ql::Priority::Medium => Priority::Medium,
-                            issuecraft_ql::Priority::Low => Priority::Low,
-                        }),
-                    };
-                    self.set(
-                        &IssueId::new(&format!("{project}#{issue_number}")),
-                        &issue_info,
-                    )?;
+    /// Removes the project `id`, its issues and their comments as part of `write_txn`.
+    fn delete_project(
+        &self,
+        write_txn: &redb::WriteTransaction,
+        id: &ProjectId,
+        deleted: &mut Vec<String>,
+    ) -> Result<(), BackendError> {
+        self.remove_entity(write_txn, id)?;
+        deleted.push(id.to_string());
 
-                    Ok(ExecutionResult::one().build())
-                }
+        for issue in self.project_issues(id)? {
+            self.delete_issue(write_txn, &issue, deleted)?;
+        }
+        Ok(())
+    }
+
+    fn issue_comments(&self, id: &IssueId) -> Result<Vec<CommentId>, BackendError> {
+        Ok(self
+            .get_all::<CommentId>(&SelectStatement {
+                columns: issuecraft_ql::Columns::All,
+                from: EntityType::Comments,
+                from_alias: None,
+                join: None,
+                filter: Some(issuecraft_ql::FilterExpression::Comparison {
+                    field: "issue".to_string(),
+                    op: issuecraft_ql::ComparisonOp::Equal,
+                    value: issuecraft_ql::IqlValue::String(id.to_string()),
+                }),
+                group_by: None,
+                order_by: None,
+                after: None,
+                include_archived: false,
+                limit: None,
+                offset: None,
+            })?
+            .into_iter()
+            .map(|comment| comment.key)
+            .collect())
+    }
+
+    fn project_issues(&self, id: &ProjectId) -> Result<Vec<IssueId>, BackendError> {
+        Ok(self
+            .get_all::<IssueId>(&SelectStatement {
+                columns: issuecraft_ql::Columns::All,
+                from: EntityType::Issues,
+                from_alias: None,
+                join: None,
+                filter: Some(issuecraft_ql::FilterExpression::Comparison {
+                    field: "project".to_string(),
+                    op: issuecraft_ql::ComparisonOp::Equal,
+                    value: issuecraft_ql::IqlValue::String(id.to_string()),
+                }),
+                group_by: None,
+                order_by: None,
+                after: None,
+                // Deleting a project also deletes its archived issues
+                include_archived: true,
+                limit: None,
+                offset: None,
+            })?
+            .into_iter()
+            .map(|issue| issue.key)
+            .collect())
+    }
+
+    /// Resolves a bulk update into the entities it targets.
+    fn bulk_update_targets(
+        &self,
+        entity: EntityType,
+        filter: Option<&FilterExpression>,
+    ) -> Result<Vec<issuecraft_ql::UpdateTarget>, BackendError> {
+        let target: fn(&str) -> issuecraft_ql::UpdateTarget = match entity {
+            EntityType::Users | EntityType::Links | EntityType::Meta | EntityType::Audit => {
+                return Err(BackendError::NotSupported);
+            }
+            EntityType::Projects => |id| issuecraft_ql::UpdateTarget::Project(ProjectId::new(id)),
+            EntityType::Issues => |id| issuecraft_ql::UpdateTarget::Issue(IssueId::new(id)),
+            EntityType::Comments => |id| issuecraft_ql::UpdateTarget::Comment(CommentId::new(id)),
+        };
+        Ok(self
+            .matching_keys(entity, filter)?
+            .iter()
+            .map(|key| target(key))
+            .collect())
+    }
+
+    /// Resolves a bulk close into the matching issues that are still open.
+    fn bulk_close_targets(&self, filter: &FilterExpression) -> Result<Vec<IssueId>, BackendError> {
+        let mut targets = Vec::new();
+        for key in self.matching_keys(EntityType::Issues, Some(filter))? {
+            let id = IssueId::new(&key);
+            if !self.get(&id)?.is_closed() {
+                targets.push(id);
+            }
+        }
+        Ok(targets)
+    }
+
+    /// Resolves a bulk delete into the entities it targets.
+    fn bulk_delete_targets(
+        &self,
+        entity: EntityType,
+        filter: Option<&FilterExpression>,
+    ) -> Result<Vec<DeleteTarget>, BackendError> {
+        let target: fn(&str) -> DeleteTarget = match entity {
+            EntityType::Users | EntityType::Links | EntityType::Meta | EntityType::Audit => {
+                return Err(BackendError::NotSupported);
+            }
+            EntityType::Projects => |id| DeleteTarget::Project(ProjectId::new(id)),
+            EntityType::Issues => |id| DeleteTarget::Issue(IssueId::new(id)),
+            EntityType::Comments => |id| DeleteTarget::Comment(CommentId::new(id)),
+        };
+        Ok(self
+            .matching_keys(entity, filter)?
+            .iter()
+            .map(|key| target(key))
+            .collect())
+    }
+
+    fn matching_keys(
+        &self,
+        entity: EntityType,
+        filter: Option<&FilterExpression>,
+    ) -> Result<Vec<String>, BackendError> {
+        let mut keys = Vec::new();
+        self.select_rows_for_each(
+            &SelectStatement {
+                columns: Columns::All,
+                from: entity,
+                from_alias: None,
+                join: None,
+                filter: filter.cloned(),
+                group_by: None,
+                order_by: None,
+                after: None,
+                include_archived: false,
+                limit: None,
+                offset: None,
             },
-            issuecraft_ql::IqlQuery::Update(UpdateStatement { entity, updates }) => match entity {
-                issuecraft_ql::UpdateTarget::User(_) => Err(BackendError::NotSupported),
-                issuecraft_ql::UpdateTarget::Project(id) => {
-                    let owner = self.get(id)?.owner;
-                    if !authorization_provider
-                        .check_authorization(
-                            &user,
-                            &Action::Update,
-                            &Resource::Project,
-                            Some(value! ({
-                                "owner": (owner.to_string())
-                            })),
-                        )
-                        .await?
-                        .status
-                        .is_authorized()
-                    {
-                        return Err(BackendError::PermissionDenied(user.to_string()));
-                    }
-                    self.update(id, updates)?;
-                    Ok(ExecutionResult::one().build())
+            |key, _| {
+                keys.push(key);
+                Ok(())
+            },
+        )?;
+        Ok(keys)
+    }
+
+    /// Collects the ids a delete of `target` would remove, in the order they would be deleted.
+    fn delete_cascade(&self, target: &DeleteTarget) -> Result<Vec<String>, BackendError> {
+        let mut ids = Vec::new();
+        let add_issue = |ids: &mut Vec<String>, id: &IssueId| -> Result<(), BackendError> {
+            ids.push(id.to_string());
+            ids.extend(
+                self.issue_comments(id)?
+                    .iter()
+                    .map(|comment| comment.to_string()),
+            );
+            Ok(())
+        };
+        match target {
+            DeleteTarget::User(_) => return Err(BackendError::NotSupported),
+            DeleteTarget::Project(id) => {
+                self.get(id)?;
+                ids.push(id.to_string());
+                for issue in self.project_issues(id)? {
+                    add_issue(&mut ids, &issue)?;
                 }
-                issuecraft_ql::UpdateTarget::Issue(id) => {
-                    let project = self.get(id)?.project;
-                    let project_owner = self.get(&project)?.owner;
-                    if !authorization_provider
-                        .check_authorization(
-                            &user,
-                            &Action::Update,
-                            &Resource::Issue,
-                            Some(value! ({
-                                "project_owner": (project_owner.to_string())
-                            })),
-                        )
-                        .await?
-                        .status
-                        .is_authorized()
-                    {
-                        return Err(BackendError::PermissionDenied(user.to_string()));
-                    }
-                    self.update(id, updates)?;
-                    Ok(ExecutionResult::one().build())
+            }
+            DeleteTarget::Issue(id) => {
+                self.get(id)?;
+                add_issue(&mut ids, id)?;
+            }
+            DeleteTarget::Comment(id) => {
+                self.get(id)?;
+                ids.push(id.to_string());
+            }
+            DeleteTarget::Bulk { entity, filter } => {
+                for target in self.bulk_delete_targets(*entity, filter.as_ref())? {
+                    ids.extend(self.delete_cascade(&target)?);
                 }
-                issuecraft_ql::UpdateTarget::Comment(id) => {
-                    let issue = self.get(id)?.issue;
-                    let project = self.get(&issue)?.project;
-                    let project_owner = self.get(&project)?.owner;
-                    if !authorization_provider
-                        .check_authorization(
-                            &user,
-                            &issuecraft_core::Action::Update,
-                            &issuecraft_core::Resource::Comment,
-                            Some(value!({
-                                "project_owner": (project_owner.to_string()),
-                                "author": (self.get(id)?.author.to_string())
-                            })),
-                        )
-                        .await?
-                        .status
-                        .is_authorized()
-                    {
-                        return Err(BackendError::PermissionDenied(user.to_string()));
-                    }
+            }
+        }
+        Ok(ids)
+    }
 
-                    self.update(id, updates)?;
-                    Ok(ExecutionResult::one().build())
-                }
-            },
-            issuecraft_ql::IqlQuery::Delete(DeleteStatement { entity }) => {
-                let mut result = ExecutionResult::zero().build();
+    /// Opens the write transaction that every following read and write goes through until it
+    /// is committed or rolled back. Nothing of it is visible outside of this database before the
+    /// commit, and nothing of it is kept if the process dies first.
+    fn begin(&mut self) -> Result<(), BackendError> {
+        if self.transaction.is_some() {
+            return Err(BackendError::TransactionAlreadyOpen);
+        }
+        self.transaction = Some(self.db.begin_write().map_err(to_iql_error)?);
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), BackendError> {
+        self.transaction
+            .take()
+            .ok_or(BackendError::NoOpenTransaction)?
+            .commit()
+            .map_err(to_iql_error)
+    }
+
+    fn rollback(&mut self) -> Result<(), BackendError> {
+        self.transaction
+            .take()
+            .ok_or(BackendError::NoOpenTransaction)?
+            .abort()
+            .map_err(to_iql_error)
+    }
+
+    /// The audit entries of the issue `id` and of its comments, in the order they were written.
+    fn issue_history(&self, id: &IssueId) -> Result<Vec<UntypedEntry>, BackendError> {
+        let mut entries = Vec::new();
+        let Some(table) = self.read_table(TABLE_AUDIT)? else {
+            return Ok(entries);
+        };
+        let comment_prefix = format!("{}/", &**id);
+        for entry in table.iter()? {
+            let (key, value) = entry.map_err(to_iql_error)?;
+            let value = facet_json::from_str::<Value>(&value.value()).map_err(to_iql_error)?;
+            // Only the issue itself and its comments have ids starting with the issue id
+            let belongs_to_issue = value
+                .as_object()
+                .and_then(|entry| entry.get("id"))
+                .and_then(Value::as_string)
+                .is_some_and(|entry_id| {
+                    entry_id.as_str() == &**id || entry_id.as_str().starts_with(&comment_prefix)
+                });
+            if belongs_to_issue {
+                entries.push(UntypedEntry {
+                    key: key.value().to_string(),
+                    value,
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Reports what `query` would do without writing anything.
+    fn explain(&self, query: &IqlQuery) -> Result<ExecutionResult, BackendError> {
+        let mut deleted = None;
+        let rows = match query {
+            IqlQuery::Delete(DeleteStatement { entity }) => {
+                let ids = self.delete_cascade(entity)?;
+                let rows = ids.len();
+                deleted = Some(ids);
+                rows
+            }
+            IqlQuery::Update(UpdateStatement { entity, updates }) => {
                 match entity {
-                    DeleteTarget::User(_) => return Err(BackendError::NotSupported),
-                    DeleteTarget::Project(id) => {
-                        if !authorization_provider
-                            .check_authorization(
-                                &user,
-                                &Action::Delete,
-                                &Resource::Project,
-                                Some(value! ({
-                                    "owner": (self.get(id)?.owner.to_string())
-                                })),
-                            )
-                            .await?
-                            .status
-                            .is_authorized()
-                        {
-                            return Err(BackendError::PermissionDenied(user.to_string()));
-                        }
-                        self.delete_project(id, &mut result)?;
+                    issuecraft_ql::UpdateTarget::User(_) => return Err(BackendError::NotSupported),
+                    issuecraft_ql::UpdateTarget::Project(id) => {
+                        self.apply_updates(id, updates)?;
                     }
-                    DeleteTarget::Issue(id) => {
-                        if !authorization_provider
-                            .check_authorization(
-                                &user,
-                                &Action::Delete,
-                                &Resource::Project,
-                                Some(value! ({
-                                    "author": (self.get(id)?.author.to_string()),
-                                    "project_owner": (self.get(&self.get(id)?.project)?.owner.to_string())
-                                })),
-                            )
-                            .await?
-                            .status
-                            .is_authorized()
-                        {
-                            return Err(BackendError::PermissionDenied(user.to_string()));
-                        }
-                        self.delete_issue(id, &mut result)?;
+                    issuecraft_ql::UpdateTarget::Issue(id) => {
+                        self.apply_updates(id, updates)?;
+                    }
+                    issuecraft_ql::UpdateTarget::Comment(id) => {
+                        self.apply_updates(id, updates)?;
                     }
-                    DeleteTarget::Comment(id) => {
-                        self.delete_comment(id, &mut result)?;
+                    issuecraft_ql::UpdateTarget::Bulk { entity, filter } => {
+                        let targets = self.bulk_update_targets(*entity, filter.as_ref())?;
+                        for target in &targets {
+                            self.explain(&IqlQuery::Update(UpdateStatement {
+                                entity: target.clone(),
+                                updates: updates.clone(),
+                            }))?;
+                        }
+                        return Ok(ExecutionResult::builder(targets.len() as u64)
+                            .info(format!("Dry run, would affect {} rows", targets.len()))
+                            .build());
                     }
                 }
-                Ok(result)
+                1
             }
-            issuecraft_ql::IqlQuery::Assign(AssignStatement { issue_id, assignee }) => {
-                let mut issue_info: IssueInfo = self.get(issue_id)?;
-                issue_info.assignee = assignee.clone();
-                self.set(issue_id, &issue_info)?;
-                Ok(ExecutionResult::one().build())
+            IqlQuery::Select(select_statement) => {
+                let mut rows = 0;
+                self.select_rows_for_each(select_statement, |_, _| {
+                    rows += 1;
+                    Ok(())
+                })?;
+                rows
             }
-            issuecraft_ql::IqlQuery::Close(CloseStatement { issue_id, reason }) => {
-                let issue_info: IssueInfo = self.get(issue_id)?;
-                if let IssueStatus::Closed { reason } = issue_info.status {
-                    return Err(BackendError::IssueAlreadyClosed(
-                        issue_id.to_string(),
-                        reason,
-                    ));
-                }
-                self.set(
-                    issue_id,
-                    &IssueInfo {
-                        status: IssueStatus::Closed {
-                            reason: reason.clone().unwrap_or_default(),
-                        },
-                        ..issue_info
-                    },
-                )?;
+            IqlQuery::Close(CloseStatement {
+                target: CloseTarget::Bulk { filter },
+                ..
+            }) => self.bulk_close_targets(filter)?.len(),
+            IqlQuery::Explain(query) => return self.explain(query),
+            IqlQuery::Import(statement) => parse_dump(&statement.dump)?
+                .tables
+                .iter()
+                .map(|table| table.entries.len())
+                .sum(),
+            IqlQuery::Reindex
+            | IqlQuery::Begin
+            | IqlQuery::Commit
+            | IqlQuery::Rollback
+            | IqlQuery::Set(_) => 0,
+            IqlQuery::History(issue_id) => self.issue_history(issue_id)?.len(),
+            IqlQuery::Describe(entity) => describe_entity(*entity)?.len(),
+            IqlQuery::Reopen(ReopenStatement { comment, .. }) => 1 + usize::from(comment.is_some()),
+            IqlQuery::Create(issuecraft_ql::CreateStatement::Issues { titles, .. }) => titles.len(),
+            IqlQuery::Create(_)
+            | IqlQuery::Assign(_)
+            | IqlQuery::Close(_)
+            | IqlQuery::Move(_)
+            | IqlQuery::Rename(_)
+            | IqlQuery::Archive(_)
+            | IqlQuery::Unarchive(_)
+            | IqlQuery::Comment(_)
+            | IqlQuery::React(_)
+            | IqlQuery::Unreact(_) => 1,
+        };
+        let result = ExecutionResult::builder(rows as u64)
+            .info(format!("Dry run, would affect {rows} rows"));
+        Ok(match deleted {
+            Some(ids) => result.data(stringify(&ids)).build(),
+            None => result.build(),
+        })
+    }
 
-                Ok(ExecutionResult::one().build())
+    fn apply_updates<ID: EntityId>(
+        &self,
+        id: &ID,
+        updates: &[FieldUpdate],
+    ) -> Result<Value, BackendError> {
+        let mut item_info: Value = self.get_as(id)?;
+        for update in updates {
+            update.apply_to::<ID::EntityType>(&mut item_info)?;
+        }
+        Ok(item_info)
+    }
+
+    fn set_from_value<ID: EntityId, V: Facet<'static>>(
+        &mut self,
+        id: &ID,
+        info: &V,
+    ) -> Result<(), BackendError> {
+        self.with_transaction(|db, write_txn| db.write_entity(write_txn, id, info))
+    }
+
+    /// Writes `info` under `id` as part of `write_txn`, along with the issue index, the issue
+    /// history and the pending audit entry.
+    fn write_entity<ID: EntityId, V: Facet<'static>>(
+        &self,
+        write_txn: &redb::WriteTransaction,
+        id: &ID,
+        info: &V,
+    ) -> Result<(), BackendError> {
+        let table_definition = get_table(ID::kind());
+        let mut table = write_txn
+            .open_table(table_definition)
+            .map_err(to_iql_error)?;
+        let info_str = facet_json::to_string(info).map_err(to_iql_error)?;
+        let old = table
+            .insert(&**id, &info_str)
+            .map_err(to_iql_error)?
+            .map(|old| old.value());
+        if ID::kind() == EntityType::Issues {
+            index_issue(write_txn, id, old.as_deref(), Some(&info_str))?;
+            record_issue_version(write_txn, id, old.as_deref(), Some(&info_str))?;
+        }
+        if let Some(audit) = self.pending_audit.take() {
+            record_audit_entry(write_txn, audit.into_entry(id))?;
+        }
+        Ok(())
+    }
+
+    fn set<ID: EntityId>(&mut self, id: &ID, info: &ID::EntityType) -> Result<(), BackendError> {
+        self.set_from_value(id, info)
+    }
+
+    /// Stores a new entity together with the counter its `number` was taken from, so one is
+    /// never written without the other.
+    fn set_numbered<ID: EntityId>(
+        &mut self,
+        id: &ID,
+        info: &ID::EntityType,
+        counter_key: &str,
+        number: u64,
+    ) -> Result<(), BackendError> {
+        self.with_transaction(|db, write_txn| {
+            db.write_entity(write_txn, id, info)?;
+            write_meta(write_txn, counter_key, &number.to_string())
+        })
+    }
+
+    /// Flags `target` as archived or not. Targets already in that state are left untouched.
+    fn set_archived(
+        &mut self,
+        target: &ArchiveTarget,
+        archived: bool,
+        user: &UserId,
+    ) -> Result<ExecutionResult, BackendError> {
+        match target {
+            ArchiveTarget::Issue(id) => {
+                let issue: IssueInfo = self.get(id)?;
+                if issue.archived == archived {
+                    return Ok(ExecutionResult::zero().build());
+                }
+                self.touch_issue(id, IssueInfo { archived, ..issue }, user)?;
             }
-            issuecraft_ql::IqlQuery::Reopen(ReopenStatement { issue_id }) => {
-                let issue_info: IssueInfo = self.get(issue_id)?;
-                if !matches!(issue_info.status, IssueStatus::Closed { .. }) {
+            ArchiveTarget::Project(id) => {
+                let project: ProjectInfo = self.get(id)?;
+                if project.archived == archived {
                     return Ok(ExecutionResult::zero().build());
                 }
                 self.set(
-                    issue_id,
-                    &IssueInfo {
-                        status: IssueStatus::Open,
-                        ..issue_info
+                    id,
+                    &ProjectInfo {
+                        archived,
+                        updated_by: Some(user.clone()),
+                        ..project
                     },
                 )?;
+            }
+        }
+        Ok(ExecutionResult::one().build())
+    }
 
-                Ok(ExecutionResult::one().build())
+    /// Stores an issue changed by a query, stamping when and by whom it was last updated.
+    fn touch_issue(
+        &mut self,
+        id: &IssueId,
+        issue: IssueInfo,
+        user: &UserId,
+    ) -> Result<(), BackendError> {
+        self.set(
+            id,
+            &IssueInfo {
+                updated_at: Some(issuecraft_core::now()),
+                updated_by: Some(user.clone()),
+                ..issue
+            },
+        )
+    }
+
+    fn count_groups(
+        &self,
+        select_statement: &SelectStatement,
+    ) -> Result<SelectData<Value>, BackendError> {
+        let group_by = select_statement.group_by.as_ref();
+        let mut groups: Vec<(Value, u64)> = Vec::new();
+        // Ordering and limits apply to the groups, not to the rows being counted
+        let rows = SelectStatement {
+            order_by: None,
+            limit: None,
+            offset: None,
+            ..select_statement.clone()
+        };
+        self.select_rows_for_each(&rows, |id, value| {
+            // Rows without the group field are counted under a null key
+            let key = match group_by {
+                None => Value::NULL,
+                Some(field) if field == "id" => VString::new(&id).into_value(),
+                Some(field) => value
+                    .as_object()
+                    .and_then(|object| object.get(field))
+                    .map_or(Value::NULL, group_key),
+            };
+            match groups.iter_mut().find(|(group, _)| *group == key) {
+                Some((_, count)) => *count += 1,
+                None => groups.push((key, 1)),
             }
-            issuecraft_ql::IqlQuery::Comment(CommentStatement { issue_id, content }) => {
-                if !self.exists(issue_id)? {
-                    return Err(BackendError::ItemNotFound {
-                        kind: EntityType::Issues.to_string(),
-                        id: issue_id.to_string(),
-                    });
+            Ok(())
+        })?;
+        if group_by.is_none() && groups.is_empty() {
+            groups.push((Value::NULL, 0));
+        }
+
+        let columns = match &select_statement.columns {
+            Columns::All => group_by
+                .into_iter()
+                .cloned()
+                .chain([COUNT_COLUMN.to_string()])
+                .map(|column| (column, None))
+                .collect(),
+            Columns::Named(columns) => columns.clone(),
+        };
+        let mut rows = groups
+            .into_iter()
+            .map(|(key, count)| {
+                let mut row = VObject::new();
+                for (column, alias) in &columns {
+                    let name = column_output_name(column, alias.as_deref());
+                    if column == COUNT_COLUMN {
+                        row.insert(name, VNumber::from_u64(count).into_value());
+                    } else if Some(column) == group_by {
+                        row.insert(name, key.clone());
+                    } else {
+                        return Err(BackendError::ImplementationSpecific(format!(
+                            "Column '{column}' must appear in GROUP BY or be an aggregate"
+                        )));
+                    }
                 }
-                let comment_info = CommentInfo {
-                    issue: issue_id.clone(),
-                    author: user,
-                    content: content.clone(),
-                    created_at: time::UtcDateTime::now(),
+                Ok(row.into_value())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(order_by) = &select_statement.order_by {
+            // Groups can be ordered by an output column, either through its alias or its name
+            let Some(name) = columns.iter().find_map(|(column, alias)| {
+                let name = column_output_name(column, alias.as_deref());
+                (name == order_by.field || *column == order_by.field).then_some(name)
+            }) else {
+                return Err(BackendError::ImplementationSpecific(format!(
+                    "Cannot order groups by '{}' as it is not an output column",
+                    order_by.field
+                )));
+            };
+            rows.sort_by(|a, b| {
+                compare_ordered(
+                    a.as_object().and_then(|o| o.get(name)),
+                    b.as_object().and_then(|o| o.get(name)),
+                    order_by,
+                )
+            });
+        }
+
+        let rows = rows
+            .into_iter()
+            .skip(
+                usize::try_from(select_statement.offset.unwrap_or(0))
+                    .expect("Number exceeds max supported value"),
+            )
+            .take(
+                usize::try_from(select_statement.limit.unwrap_or(u64::MAX))
+                    .expect("Number exceeds max supported value"),
+            )
+            .collect();
+        Ok(SelectData {
+            columns: output_columns(&columns),
+            rows,
+        })
+    }
+
+    /// Streams the rows matching `select_statement` into `f`.
+    ///
+    /// The filter is evaluated while iterating the table, so only matching rows are converted
+    /// into entries. Rows are only collected up front when they need to be sorted; otherwise the
+    /// scan stops as soon as the limit is reached.
+    pub fn select_for_each<K: EntityId>(
+        &self,
+        select_statement: &SelectStatement,
+        mut f: impl FnMut(Entry<K>) -> Result<(), BackendError>,
+    ) -> Result<(), BackendError> {
+        self.select_rows_for_each(select_statement, |key, value| f(to_entry(&key, value)?))
+    }
+
+    fn select_rows_for_each(
+        &self,
+        SelectStatement {
+            from,
+            join,
+            filter,
+            order_by,
+            after,
+            include_archived,
+            limit,
+            offset,
+            ..
+        }: &SelectStatement,
+        mut f: impl FnMut(String, Value) -> Result<(), BackendError>,
+    ) -> Result<(), BackendError> {
+        if filter
+            .as_ref()
+            .is_some_and(FilterExpression::is_trivially_false)
+        {
+            return Ok(());
+        }
+        // The joined table is loaded first, as a transaction can't open a table twice at once
+        let joined = match join {
+            Some(join) => Some((join, self.load_join_table(join)?)),
+            None => None,
+        };
+        let Some(table) = self.read_table(get_table(*from))? else {
+            return Ok(());
+        };
+        let offset =
+            usize::try_from(offset.unwrap_or(0)).expect("Number exceeds max supported value");
+        let limit =
+            usize::try_from(limit.unwrap_or(u64::MAX)).expect("Number exceeds max supported value");
+        let hides_archived =
+            !include_archived && matches!(from, EntityType::Issues | EntityType::Projects);
+        let entries: Box<dyn Iterator<Item = Result<(String, String), BackendError>>> =
+            if let Some(id) = filter.as_ref().and_then(FilterExpression::required_id) {
+                // At most one row can match, so it is fetched by its key
+                let value = table
+                    .get(id)?
+                    .filter(|_| after.as_ref().is_none_or(|after| id > after.as_str()))
+                    .map(|value| Ok((id.to_string(), value)));
+                Box::new(value.into_iter())
+            } else {
+                // Keys are ordered, so paging resumes right after the given one
+                let entries = match after {
+                    Some(after) => table.range((Bound::Excluded(after.as_str()), Bound::Unbounded)),
+                    None => table.iter(),
                 };
-                self.set(
-                    &CommentId::from_str(&format!("C{}", nanoid!())),
-                    &comment_info,
-                )?;
-                Ok(ExecutionResult::one().build())
+                Box::new(entries?.map(|entry| {
+                    let (key, value) = entry.map_err(to_iql_error)?;
+                    Ok((key.value().to_string(), value.value()))
+                }))
+            };
+        let rows = entries
+            .map(|entry| {
+                let (key, value) = entry?;
+                let value = if *from == EntityType::Meta {
+                    // Meta values are stored as plain strings rather than JSON
+                    let mut object = VObject::new();
+                    object.insert("value", VString::new(&value).into_value());
+                    object.into_value()
+                } else {
+                    facet_json::from_str::<Value>(&value).map_err(to_iql_error)?
+                };
+                Ok(match &joined {
+                    Some((join, rows)) => {
+                        enrich_with_join(&key, value, join, rows).map(|value| (key, value))
+                    }
+                    None => Some((key, value)),
+                })
+            })
+            .filter_map(Result::transpose)
+            .filter(
+                |row: &Result<(String, Value), BackendError>| match (row, filter) {
+                    (Ok((_, value)), _) if hides_archived && is_archived(value) => false,
+                    (Ok((key, value)), Some(filter)) => filter.matches(key, value),
+                    _ => true,
+                },
+            );
+
+        if let Some(order_by) = order_by {
+            let mut rows = rows.collect::<Result<Vec<_>, _>>()?;
+            rows.sort_by(|a, b| {
+                let o1 = a.1.as_object().unwrap();
+                let o2 = b.1.as_object().unwrap();
+                compare_ordered(o1.get(&order_by.field), o2.get(&order_by.field), order_by)
+            });
+            for (key, value) in rows.into_iter().skip(offset).take(limit) {
+                f(key, value)?;
+            }
+        } else {
+            // Rows are read lazily, so the scan stops once `limit` of them matched
+            for row in rows.skip(offset).take(limit) {
+                let (key, value) = row?;
+                f(key, value)?;
             }
         }
+        Ok(())
+    }
+
+    /// Loads the joined table keyed by the value of the join field.
+    fn load_join_table(
+        &self,
+        join: &Join,
+    ) -> Result<HashMap<String, (String, Value)>, BackendError> {
+        let mut rows = HashMap::new();
+        let Some(table) = self.read_table(get_table(join.entity))? else {
+            return Ok(rows);
+        };
+        for entry in table.iter()? {
+            let (key, value) = entry.map_err(to_iql_error)?;
+            let value = facet_json::from_str::<Value>(&value.value()).map_err(to_iql_error)?;
+            let join_key = if join.right_field == "id" {
+                Some(key.value().to_string())
+            } else {
+                value
+                    .as_object()
+                    .and_then(|object| object.get(&join.right_field))
+                    .and_then(|value| value.as_string())
+                    .map(|value| value.as_str().to_string())
+            };
+            if let Some(join_key) = join_key {
+                rows.insert(join_key, (key.value().to_string(), value));
+            }
+        }
+        Ok(rows)
+    }
+
+    fn project(
+        &self,
+        select_statement: &SelectStatement,
+        columns: &[(String, Option<String>)],
+    ) -> Result<Vec<UntypedEntry>, BackendError> {
+        let comment_counts = if select_statement.from == EntityType::Issues
+            && columns
+                .iter()
+                .any(|(column, _)| column == COMMENT_COUNT_COLUMN)
+        {
+            self.comment_counts()?
+        } else {
+            HashMap::new()
+        };
+        let mut entries = Vec::new();
+        self.select_rows_for_each(select_statement, |key, value| {
+            let mut row = VObject::new();
+            for (column, alias) in columns {
+                let field = if column == "id" {
+                    VString::new(&key).into_value()
+                } else if column == COMMENT_COUNT_COLUMN
+                    && select_statement.from == EntityType::Issues
+                {
+                    let count = comment_counts.get(&key).copied().unwrap_or(0);
+                    VNumber::from_u64(count).into_value()
+                } else {
+                    value
+                        .as_object()
+                        .and_then(|object| object.get(column))
+                        .cloned()
+                        .unwrap_or(Value::NULL)
+                };
+                row.insert(column_output_name(column, alias.as_deref()), field);
+            }
+            entries.push(UntypedEntry {
+                key,
+                value: row.into_value(),
+            });
+            Ok(())
+        })?;
+        Ok(entries)
+    }
+
+    /// Answers a select of only the id column from the keys alone.
+    fn project_keys(
+        &self,
+        SelectStatement {
+            from,
+            after,
+            limit,
+            offset,
+            ..
+        }: &SelectStatement,
+        alias: &Option<String>,
+    ) -> Result<Vec<UntypedEntry>, BackendError> {
+        let offset =
+            usize::try_from(offset.unwrap_or(0)).expect("Number exceeds max supported value");
+        let limit =
+            usize::try_from(limit.unwrap_or(u64::MAX)).expect("Number exceeds max supported value");
+        let name = column_output_name("id", alias.as_deref());
+        Ok(self
+            .keys_after(*from, after.as_deref())?
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .map(|key| {
+                let mut row = VObject::new();
+                row.insert(name, VString::new(&key).into_value());
+                UntypedEntry {
+                    key,
+                    value: row.into_value(),
+                }
+            })
+            .collect())
+    }
+
+    /// The number of comments per issue, from a single scan of the comments.
+    fn comment_counts(&self) -> Result<HashMap<String, u64>, BackendError> {
+        let mut counts = HashMap::new();
+        self.select_for_each::<CommentId>(
+            &SelectStatement {
+                columns: Columns::All,
+                from: EntityType::Comments,
+                from_alias: None,
+                join: None,
+                filter: None,
+                group_by: None,
+                order_by: None,
+                after: None,
+                include_archived: false,
+                limit: None,
+                offset: None,
+            },
+            |comment| {
+                *counts.entry(comment.value.issue.to_string()).or_default() += 1;
+                Ok(())
+            },
+        )?;
+        Ok(counts)
+    }
+
+    fn get_all<K: EntityId>(
+        &self,
+        select_statement: &SelectStatement,
+    ) -> Result<Vec<Entry<K>>, BackendError> {
+        let mut entries = Vec::new();
+        self.select_for_each(select_statement, |entry| {
+            entries.push(entry);
+            Ok(())
+        })?;
+        Ok(entries)
+    }
+
+    fn get<ID: EntityId>(&self, key: &ID) -> Result<ID::EntityType, BackendError> {
+        self.get_as(key)
+    }
+
+    fn get_as<ID: EntityId, T: Facet<'static>>(&self, key: &ID) -> Result<T, BackendError> {
+        let not_found = || BackendError::ItemNotFound {
+            id: key.to_string(),
+            kind: ID::kind().singular().to_string(),
+        };
+        let info = self
+            .read_table(get_table(ID::kind()))?
+            .ok_or_else(not_found)?
+            .get(key)?
+            .ok_or_else(not_found)?;
+        facet_json::from_str(&info).map_err(to_iql_error)
+    }
+}
+
+/// Keeps user text on the line it is put on, so it can't end a heading early or start a table.
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\r', '\n'], "<br>")
+}
+
+fn to_entry<K: EntityId>(key: &str, value: Value) -> Result<Entry<K>, BackendError> {
+    Ok(Entry {
+        key: K::from_str(key),
+        value: from_value::<K::EntityType>(value).map_err(to_iql_error)?,
+    })
+}
+
+/// Compares two sort values in the requested direction. Missing and null values are placed
+/// according to the NULLS ordering, independent of the direction.
+fn compare_ordered(a: Option<&Value>, b: Option<&Value>, order_by: &OrderBy) -> Ordering {
+    let nulls = if order_by.nulls_first() {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    };
+    match (a.filter(|v| !v.is_null()), b.filter(|v| !v.is_null())) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => nulls,
+        (Some(_), None) => nulls.reverse(),
+        (Some(v1), Some(v2)) => {
+            // Timestamps of other precisions, like those stored before microseconds were, do
+            // not order in time as strings
+            let ordering = match (priority_rank(v1), priority_rank(v2)) {
+                (Some(r1), Some(r2)) if order_by.field == "priority" => r1.cmp(&r2),
+                _ => v1
+                    .as_string()
+                    .zip(v2.as_string())
+                    .and_then(|(s1, s2)| compare_timestamps(s1.as_str(), s2.as_str()))
+                    .or_else(|| v1.partial_cmp(v2))
+                    .unwrap_or(Ordering::Equal),
+            };
+            match order_by.direction {
+                OrderDirection::Asc => ordering,
+                OrderDirection::Desc => ordering.reverse(),
+            }
+        }
+    }
+}
+
+/// Priorities are stored by name, so they are ranked by severity rather than alphabetically.
+fn priority_rank(value: &Value) -> Option<u8> {
+    match value.as_string()?.as_str().to_ascii_lowercase().as_str() {
+        "low" => Some(0),
+        "medium" => Some(1),
+        "high" => Some(2),
+        "critical" => Some(3),
+        _ => None,
+    }
+}
+
+/// Struct variants like `{"Closed": {"reason": ..}}` are grouped by their variant name only.
+fn group_key(value: &Value) -> Value {
+    match value.as_object() {
+        Some(object) if object.len() == 1 => object
+            .iter()
+            .next()
+            .map(|(variant, _)| VString::new(variant.as_str()).into_value())
+            .unwrap_or(Value::NULL),
+        _ => value.clone(),
+    }
+}
+
+/// Adds the fields of the matching joined row as `<entity>.<field>`.
+///
+/// Returns `None` if there is no matching row, as joins are inner joins.
+fn enrich_with_join(
+    key: &str,
+    mut value: Value,
+    join: &Join,
+    rows: &HashMap<String, (String, Value)>,
+) -> Option<Value> {
+    let object = value.as_object_mut()?;
+    let join_key = if join.left_field == "id" {
+        key.to_string()
+    } else {
+        object
+            .get(&join.left_field)?
+            .as_string()?
+            .as_str()
+            .to_string()
+    };
+    let (joined_key, joined_value) = rows.get(&join_key)?;
+    let entity = join.entity.to_string().to_lowercase();
+    object.insert(
+        format!("{entity}.id"),
+        VString::new(joined_key).into_value(),
+    );
+    for (field, field_value) in joined_value.as_object()?.iter() {
+        object.insert(format!("{entity}.{}", field.as_str()), field_value.clone());
+    }
+    Some(value)
+}
+
+fn parse_issue_number(issue_id: &str) -> Option<u64> {
+    issue_id.rsplit_once('#')?.1.parse().ok()
+}
+
+fn parse_comment_number(comment_id: &str) -> Option<(&str, u64)> {
+    let (issue, number) = comment_id.rsplit_once("/c")?;
+    Some((issue, number.parse().ok()?))
+}
+
+fn stringify<'a, T: Facet<'a>>(value: &'a T) -> String {
+    facet_json::to_string(value).unwrap()
+}
+
+fn to_iql_error<E: Display>(err: E) -> BackendError {
+    BackendError::ImplementationSpecific(format!("{err}"))
+}
+
+impl Drop for Database {
+    fn drop(&mut self) {
+        if self.transaction.is_some() {
+            // Nothing to report the error to; the changes stay as they are
+            let _ = self.rollback();
+        }
+    }
+}
+
+#[async_trait]
+impl ExecutionEngine for Database {
+    async fn execute<AP: AuthorizationProvider + Sync>(
+        &mut self,
+        authorization_provider: &AP,
+        user: UserId,
+        query: &IqlQuery,
+        session: &mut SessionOptions,
+    ) -> Result<ExecutionResult, BackendError> {
+        if self.read_only && !query.is_read_only() {
+            return Err(BackendError::ReadOnly);
+        }
+        let mut query = session.apply(query);
+        resolve_fields(&mut query, &SCHEMA);
+        let query = &query;
+        // Statements that write outside of BEGIN and COMMIT run in a transaction of their own,
+        // so they are applied completely or not at all
+        let own_transaction = self.transaction.is_none()
+            && !query.is_read_only()
+            && !matches!(
+                query,
+                IqlQuery::Begin | IqlQuery::Commit | IqlQuery::Rollback
+            );
+        if own_transaction {
+            self.begin()?;
+        }
+        // Mutations that fail before writing anything leave no audit entry behind
+        self.pending_audit
+            .set(PendingAudit::for_query(&user, query));
+        let result = self
+            .execute_statement(authorization_provider, user, query, session)
+            .await;
+        self.pending_audit.set(None);
+        // A failing statement undoes the whole transaction it is part of. The statements a bulk
+        // statement runs for each match share its transaction, so when one of them fails, the
+        // transaction is already gone by the time the bulk statement fails as well
+        if result.is_err() {
+            if self.transaction.is_some() {
+                self.rollback()?;
+            }
+        } else if own_transaction {
+            self.commit()?;
+        }
+        result
+    }
+}
+
+impl Database {
+    #[allow(clippy::too_many_lines)]
+    async fn execute_statement<AP: AuthorizationProvider + Sync>(
+        &mut self,
+        authorization_provider: &AP,
+        user: UserId,
+        query: &IqlQuery,
+        session: &mut SessionOptions,
+    ) -> Result<ExecutionResult, BackendError> {
+        match query {
+            issuecraft_ql::IqlQuery::Select(select_statement)
+                if select_statement.group_by.is_some() || select_statement.columns.has_count() =>
+            {
+                let result = stringify(&self.count_groups(select_statement)?);
+                Ok(ExecutionResult::zero().data(result).build())
+            }
+            issuecraft_ql::IqlQuery::Select(
+                select_statement @ SelectStatement {
+                    columns: Columns::Named(columns),
+                    join: None,
+                    filter: None,
+                    order_by: None,
+                    ..
+                },
+            ) if is_keys_only(select_statement, columns) => {
+                let result = stringify(&SelectData {
+                    columns: output_columns(columns),
+                    rows: self.project_keys(select_statement, &columns[0].1)?,
+                });
+                Ok(ExecutionResult::zero().data(result).build())
+            }
+            issuecraft_ql::IqlQuery::Select(
+                select_statement @ SelectStatement {
+                    columns: Columns::Named(columns),
+                    ..
+                },
+            ) => {
+                let result = stringify(&SelectData {
+                    columns: output_columns(columns),
+                    rows: self.project(select_statement, columns)?,
+                });
+                Ok(ExecutionResult::zero().data(result).build())
+            }
+            issuecraft_ql::IqlQuery::Select(
+                select_statement @ (SelectStatement { join: Some(_), .. }
+                | SelectStatement {
+                    from: EntityType::Meta | EntityType::Audit,
+                    ..
+                }),
+            ) => {
+                let mut entries = Vec::new();
+                self.select_rows_for_each(select_statement, |key, value| {
+                    entries.push(UntypedEntry { key, value });
+                    Ok(())
+                })?;
+                let result = stringify(&SelectData {
+                    columns: all_columns(select_statement),
+                    rows: entries,
+                });
+                Ok(ExecutionResult::zero().data(result).build())
+            }
+            issuecraft_ql::IqlQuery::Select(select_statement) => {
+                let columns = all_columns(select_statement);
+                let result = match select_statement.from {
+                    issuecraft_ql::EntityType::Users => {
+                        let rows = self.get_all::<UserId>(select_statement)?;
+                        stringify(&SelectData { columns, rows })
+                    }
+                    issuecraft_ql::EntityType::Projects => {
+                        let rows = self.get_all::<ProjectId>(select_statement)?;
+                        stringify(&SelectData { columns, rows })
+                    }
+                    issuecraft_ql::EntityType::Issues => {
+                        let rows = self.get_all::<IssueId>(select_statement)?;
+                        stringify(&SelectData { columns, rows })
+                    }
+                    issuecraft_ql::EntityType::Comments => {
+                        let rows = self.get_all::<CommentId>(select_statement)?;
+                        stringify(&SelectData { columns, rows })
+                    }
+                    issuecraft_ql::EntityType::Links => {
+                        let rows = self.get_all::<LinkId>(select_statement)?;
+                        stringify(&SelectData { columns, rows })
+                    }
+                    issuecraft_ql::EntityType::Meta | issuecraft_ql::EntityType::Audit => {
+                        unreachable!("Meta and audit rows are untyped")
+                    }
+                };
+                Ok(ExecutionResult::zero().data(result).build())
+            }
+            issuecraft_ql::IqlQuery::Create(create_statement) => match create_statement {
+                issuecraft_ql::CreateStatement::User {
+                    username,
+                    email,
+                    name,
+                } => {
+                    if let Some(email) = email {
+                        check_email(email)?;
+                    }
+                    let user_id = UserId::new(username);
+                    if self.exists(&user_id)? {
+                        return Err(BackendError::ItemAlreadyExists {
+                            kind: EntityType::Users.singular().to_string(),
+                            id: username.clone(),
+                        });
+                    }
+                    if !authorization_provider
+                        .check_authorization(&user, &Action::Create, &Resource::User, None)
+                        .await?
+                        .status
+                        .is_authorized()
+                    {
+                        return Err(BackendError::PermissionDenied(user.to_string()));
+                    }
+                    let user_info = UserInfo {
+                        name: name.clone().unwrap_or_else(|| username.clone()),
+                        display: name.clone(),
+                        email: email.clone(),
+                    };
+                    self.set(&user_id, &user_info)?;
+                    Ok(ExecutionResult::one().build())
+                }
+                issuecraft_ql::CreateStatement::Project {
+                    project_id,
+                    name,
+                    description,
+                    owner,
+                    on_conflict,
+                } => {
+                    check_project_id(project_id)?;
+                    if self.exists(project_id)? {
+                        return match on_conflict {
+                            None => Err(BackendError::ProjectAlreadyExists(project_id.to_string())),
+                            Some(OnConflict::DoNothing) => Ok(ExecutionResult::zero().build()),
+                            Some(OnConflict::Update(updates)) => {
+                                self.execute(
+                                    authorization_provider,
+                                    user,
+                                    &IqlQuery::Update(UpdateStatement {
+                                        entity: UpdateTarget::Project(project_id.clone()),
+                                        updates: updates.clone(),
+                                    }),
+                                    session,
+                                )
+                                .await
+                            }
+                        };
+                    }
+                    let owner = match owner {
+                        Some(owner) => owner.clone(),
+                        None => user.clone(),
+                    };
+
+                    if !authorization_provider
+                        .check_authorization(
+                            &user,
+                            &Action::Create,
+                            &Resource::Project,
+                            Some(value! ({
+                                "owner": (owner.to_string())
+                            })),
+                        )
+                        .await?
+                        .status
+                        .is_authorized()
+                    {
+                        return Err(BackendError::PermissionDenied(user.to_string()));
+                    }
+
+                    self.check_user_exists(&owner)?;
+                    self.check_project_name(project_id, name.as_deref())?;
+                    let project_info = ProjectInfo {
+                        owner,
+                        description: description.clone(),
+                        name: name.clone(),
+                        updated_by: Some(user.clone()),
+                        archived: false,
+                    };
+                    self.set(project_id, &project_info)?;
+                    Ok(ExecutionResult::one().build())
+                }
+                issuecraft_ql::CreateStatement::Issue {
+                    project,
+                    kind,
+                    title,
+                    description,
+                    priority,
+                    assignee,
+                    labels,
+                } => {
+                    check_title(title)?;
+                    if !self.exists(project)? {
+                        return Err(BackendError::ItemNotFound {
+                            kind: EntityType::Projects.singular().to_string(),
+                            id: project.to_string(),
+                        });
+                    }
+                    for field in self.issue_templates.required_fields_for(kind) {
+                        let present = match field {
+                            IssueField::Description => description.is_some(),
+                            IssueField::Priority => priority.is_some(),
+                            IssueField::Assignee => assignee.is_some(),
+                            IssueField::Labels => !labels.is_empty(),
+                        };
+                        if !present {
+                            return Err(BackendError::MissingRequiredField {
+                                kind: kind.to_string(),
+                                field,
+                            });
+                        }
+                    }
+
+                    let project_owner = self.get(project)?.owner;
+                    if !authorization_provider
+                        .check_authorization(
+                            &user,
+                            &Action::Create,
+                            &Resource::Issue,
+                            Some(value! ({
+                                "project_owner": (project_owner.to_string()),
+                                "project": (project.to_string())
+                            })),
+                        )
+                        .await?
+                        .status
+                        .is_authorized()
+                    {
+                        return Err(BackendError::PermissionDenied(user.to_string()));
+                    }
+
+                    let assignee = match assignee {
+                        Some(assignee) => assignee.clone(),
+                        None => user.clone(),
+                    };
+                    self.check_user_exists(&assignee)?;
+                    let issue_number = self.get_next_issue_id(project)?;
+                    let issue_info = IssueInfo {
+                        title: title.clone(),
+                        kind: kind.clone(),
+                        description: description.clone(),
+                        status: IssueStatus::Open,
+                        project: project.clone(),
+                        author: user.clone(),
+                        assignee,
+                        priority: priority.clone().map(|p| match p {
+                            issuecraft_ql::Priority::Critical => Priority::Critical,
+                            issuecraft_ql::Priority::High => Priority::High,
+                            issuecraft_ql::Priority::Medium => Priority::Medium,
+                            issuecraft_ql::Priority::Low => Priority::Low,
+                        }),
+                        labels: labels.clone(),
+                        updated_at: Some(issuecraft_core::now()),
+                        updated_by: Some(user.clone()),
+                        archived: false,
+                    };
+                    self.set_numbered(
+                        &IssueId::new(&format!("{project}#{issue_number}")),
+                        &issue_info,
+                        &issue_counter_key(project),
+                        issue_number,
+                    )?;
+
+                    Ok(ExecutionResult::one().build())
+                }
+                issuecraft_ql::CreateStatement::Issues {
+                    project,
+                    kind,
+                    titles,
+                } => {
+                    // All issues and the counter are written in the transaction of this
+                    // statement, so a failing issue creates none and leaves the counter as it was
+                    let mut result = ExecutionResult::zero().build();
+                    for title in titles {
+                        self.execute(
+                            authorization_provider,
+                            user.clone(),
+                            &IqlQuery::Create(issuecraft_ql::CreateStatement::Issue {
+                                project: project.clone(),
+                                title: title.clone(),
+                                kind: kind.clone(),
+                                description: None,
+                                priority: None,
+                                assignee: None,
+                                labels: Vec::new(),
+                            }),
+                            session,
+                        )
+                        .await?;
+                        result.inc();
+                    }
+                    Ok(result)
+                }
+            },
+            issuecraft_ql::IqlQuery::Update(UpdateStatement { entity, updates }) => match entity {
+                issuecraft_ql::UpdateTarget::User(_) => Err(BackendError::NotSupported),
+                issuecraft_ql::UpdateTarget::Project(id) => {
+                    let owner = self.get(id)?.owner;
+                    if !authorization_provider
+                        .check_authorization(
+                            &user,
+                            &Action::Update,
+                            &Resource::Project,
+                            Some(value! ({
+                                "owner": (owner.to_string())
+                            })),
+                        )
+                        .await?
+                        .status
+                        .is_authorized()
+                    {
+                        return Err(BackendError::PermissionDenied(user.to_string()));
+                    }
+                    let mut project: ProjectInfo =
+                        from_value(self.apply_updates(id, updates)?).map_err(to_iql_error)?;
+                    self.check_project_name(id, project.name.as_deref())?;
+                    project.updated_by = Some(user.clone());
+                    self.set(id, &project)?;
+                    Ok(ExecutionResult::one().build())
+                }
+                issuecraft_ql::UpdateTarget::Issue(id) => {
+                    let project = self.get(id)?.project;
+                    let project_owner = self.get(&project)?.owner;
+                    if !authorization_provider
+                        .check_authorization(
+                            &user,
+                            &Action::Update,
+                            &Resource::Issue,
+                            Some(value! ({
+                                "project_owner": (project_owner.to_string())
+                            })),
+                        )
+                        .await?
+                        .status
+                        .is_authorized()
+                    {
+                        return Err(BackendError::PermissionDenied(user.to_string()));
+                    }
+                    let issue =
+                        from_value(self.apply_updates(id, updates)?).map_err(to_iql_error)?;
+                    self.touch_issue(id, issue, &user)?;
+                    Ok(ExecutionResult::one().build())
+                }
+                issuecraft_ql::UpdateTarget::Comment(id) => {
+                    let issue = self.get(id)?.issue;
+                    let project = self.get(&issue)?.project;
+                    let project_owner = self.get(&project)?.owner;
+                    if !authorization_provider
+                        .check_authorization(
+                            &user,
+                            &issuecraft_core::Action::Update,
+                            &issuecraft_core::Resource::Comment,
+                            Some(value!({
+                                "project_owner": (project_owner.to_string()),
+                                "author": (self.get(id)?.author.to_string())
+                            })),
+                        )
+                        .await?
+                        .status
+                        .is_authorized()
+                    {
+                        return Err(BackendError::PermissionDenied(user.to_string()));
+                    }
+
+                    let mut comment: CommentInfo =
+                        from_value(self.apply_updates(id, updates)?).map_err(to_iql_error)?;
+                    comment.updated_by = Some(user.clone());
+                    self.set(id, &comment)?;
+                    Ok(ExecutionResult::one().build())
+                }
+                issuecraft_ql::UpdateTarget::Bulk { entity, filter } => {
+                    // Every matched entity goes through the same checks as an update by id, all
+                    // in the transaction of this statement, so a denied one updates none
+                    let mut result = ExecutionResult::zero().build();
+                    for target in self.bulk_update_targets(*entity, filter.as_ref())? {
+                        self.execute(
+                            authorization_provider,
+                            user.clone(),
+                            &IqlQuery::Update(UpdateStatement {
+                                entity: target,
+                                updates: updates.clone(),
+                            }),
+                            session,
+                        )
+                        .await?;
+                        result.inc();
+                    }
+                    Ok(result)
+                }
+            },
+            issuecraft_ql::IqlQuery::Delete(DeleteStatement { entity }) => {
+                // Cascades report every removed id so clients can invalidate what they cached.
+                // Matches of a bulk delete are all removed in the transaction of this statement,
+                // so one that is denied leaves the others in place
+                let mut deleted = Vec::new();
+                let targets = match entity {
+                    DeleteTarget::Bulk { entity, filter } => {
+                        self.bulk_delete_targets(*entity, filter.as_ref())?
+                    }
+                    target => vec![target.clone()],
+                };
+                for target in &targets {
+                    match target {
+                        DeleteTarget::User(_) => return Err(BackendError::NotSupported),
+                        DeleteTarget::Project(id) => {
+                            if !authorization_provider
+                                .check_authorization(
+                                    &user,
+                                    &Action::Delete,
+                                    &Resource::Project,
+                                    Some(value! ({
+                                        "owner": (self.get(id)?.owner.to_string())
+                                    })),
+                                )
+                                .await?
+                                .status
+                                .is_authorized()
+                            {
+                                return Err(BackendError::PermissionDenied(user.to_string()));
+                            }
+                            self.with_transaction(|db, write_txn| {
+                                db.delete_project(write_txn, id, &mut deleted)
+                            })?;
+                        }
+                        DeleteTarget::Issue(id) => {
+                            if !authorization_provider
+                            .check_authorization(
+                                &user,
+                                &Action::Delete,
+                                &Resource::Project,
+                                Some(value! ({
+                                    "author": (self.get(id)?.author.to_string()),
+                                    "project_owner": (self.get(&self.get(id)?.project)?.owner.to_string())
+                                })),
+                            )
+                            .await?
+                            .status
+                            .is_authorized()
+                        {
+                            return Err(BackendError::PermissionDenied(user.to_string()));
+                        }
+                            self.with_transaction(|db, write_txn| {
+                                db.delete_issue(write_txn, id, &mut deleted)
+                            })?;
+                        }
+                        DeleteTarget::Comment(id) => {
+                            let comment: CommentInfo = self.get(id)?;
+                            let project_owner = self.get(&self.get(&comment.issue)?.project)?.owner;
+                            if !authorization_provider
+                                .check_authorization(
+                                    &user,
+                                    &Action::Delete,
+                                    &Resource::Comment,
+                                    Some(value! ({
+                                        "project_owner": (project_owner.to_string()),
+                                        "author": (comment.author.to_string())
+                                    })),
+                                )
+                                .await?
+                                .status
+                                .is_authorized()
+                            {
+                                return Err(BackendError::PermissionDenied(user.to_string()));
+                            }
+                            self.delete(id)?;
+                            deleted.push(id.to_string());
+                        }
+                        DeleteTarget::Bulk { .. } => {
+                            unreachable!("Bulk deletes resolve to single entities")
+                        }
+                    }
+                }
+                Ok(ExecutionResult::builder(deleted.len() as u64)
+                    .data(stringify(&deleted))
+                    .build())
+            }
+            issuecraft_ql::IqlQuery::Assign(AssignStatement { issue_id, assignee }) => {
+                let mut issue_info: IssueInfo = self.get(issue_id)?;
+                self.check_user_exists(assignee)?;
+                issue_info.assignee = assignee.clone();
+                self.touch_issue(issue_id, issue_info, &user)?;
+                Ok(ExecutionResult::one().build())
+            }
+            issuecraft_ql::IqlQuery::Close(CloseStatement {
+                target: CloseTarget::Bulk { filter },
+                reason,
+            }) => {
+                // Issues that are already closed keep their original reason. The others are
+                // closed in the transaction of this statement, so either all of them or none
+                let mut result = ExecutionResult::zero().build();
+                for issue_id in self.bulk_close_targets(filter)? {
+                    self.execute(
+                        authorization_provider,
+                        user.clone(),
+                        &IqlQuery::Close(CloseStatement {
+                            target: CloseTarget::Issue(issue_id),
+                            reason: reason.clone(),
+                        }),
+                        session,
+                    )
+                    .await?;
+                    result.inc();
+                }
+                Ok(result)
+            }
+            issuecraft_ql::IqlQuery::Close(CloseStatement {
+                target: CloseTarget::Issue(issue_id),
+                reason,
+            }) => {
+                let issue_info: IssueInfo = self.get(issue_id)?;
+                if let IssueStatus::Closed { reason } = issue_info.status {
+                    return Err(BackendError::IssueAlreadyClosed(
+                        issue_id.to_string(),
+                        reason,
+                    ));
+                }
+                if let Some(CloseReason::Duplicate { of: Some(of) }) = reason
+                    && !self.exists(of)?
+                {
+                    return Err(BackendError::ItemNotFound {
+                        kind: EntityType::Issues.singular().to_string(),
+                        id: of.to_string(),
+                    });
+                }
+                self.touch_issue(
+                    issue_id,
+                    IssueInfo {
+                        status: IssueStatus::Closed {
+                            reason: reason.clone().unwrap_or_default(),
+                        },
+                        ..issue_info
+                    },
+                    &user,
+                )?;
+
+                Ok(ExecutionResult::one().build())
+            }
+            issuecraft_ql::IqlQuery::Reopen(ReopenStatement { issue_id, comment }) => {
+                let issue_info: IssueInfo = self.get(issue_id)?;
+                if !matches!(issue_info.status, IssueStatus::Closed { .. }) {
+                    return Ok(ExecutionResult::zero().build());
+                }
+                // The comment is written in the transaction of this statement as well, a
+                // failing comment also leaves the issue closed
+                self.touch_issue(
+                    issue_id,
+                    IssueInfo {
+                        status: IssueStatus::Open,
+                        ..issue_info
+                    },
+                    &user,
+                )?;
+                let mut result = ExecutionResult::one().build();
+                if let Some(comment) = comment {
+                    self.execute(
+                        authorization_provider,
+                        user,
+                        &IqlQuery::Comment(CommentStatement {
+                            issue_id: issue_id.clone(),
+                            content: comment.clone(),
+                            author: None,
+                        }),
+                        session,
+                    )
+                    .await?;
+                    result.inc();
+                }
+                Ok(result)
+            }
+            issuecraft_ql::IqlQuery::Move(MoveStatement { issue_id, project }) => {
+                let issue_info: IssueInfo = self.get(issue_id)?;
+                if !self.exists(project)? {
+                    return Err(BackendError::ItemNotFound {
+                        kind: EntityType::Projects.singular().to_string(),
+                        id: project.to_string(),
+                    });
+                }
+                if issue_info.project == *project {
+                    return Ok(ExecutionResult::zero().build());
+                }
+                // The issue leaves its project under its old id, as if it were deleted there
+                if !authorization_provider
+                    .check_authorization(
+                        &user,
+                        &Action::Delete,
+                        &Resource::Issue,
+                        Some(value! ({
+                            "author": (issue_info.author.to_string()),
+                            "project_owner": (self.get(&issue_info.project)?.owner.to_string()),
+                            "project": (issue_info.project.to_string())
+                        })),
+                    )
+                    .await?
+                    .status
+                    .is_authorized()
+                {
+                    return Err(BackendError::PermissionDenied(user.to_string()));
+                }
+                // Moving an issue into a project needs the same permission as creating it there
+                let project_owner = self.get(project)?.owner;
+                if !authorization_provider
+                    .check_authorization(
+                        &user,
+                        &Action::Create,
+                        &Resource::Issue,
+                        Some(value! ({
+                            "project_owner": (project_owner.to_string()),
+                            "project": (project.to_string())
+                        })),
+                    )
+                    .await?
+                    .status
+                    .is_authorized()
+                {
+                    return Err(BackendError::PermissionDenied(user.to_string()));
+                }
+                let new_id = self.move_issue(issue_id, project, &user)?;
+                Ok(ExecutionResult::one()
+                    .info(format!("Moved to {}", &*new_id))
+                    .build())
+            }
+            issuecraft_ql::IqlQuery::Rename(RenameStatement { project, new_id }) => {
+                let owner = self.get(project)?.owner;
+                check_project_id(new_id)?;
+                if self.exists(new_id)? {
+                    return Err(BackendError::ProjectAlreadyExists(new_id.to_string()));
+                }
+                if !authorization_provider
+                    .check_authorization(
+                        &user,
+                        &Action::Update,
+                        &Resource::Project,
+                        Some(value! ({
+                            "owner": (owner.to_string())
+                        })),
+                    )
+                    .await?
+                    .status
+                    .is_authorized()
+                {
+                    return Err(BackendError::PermissionDenied(user.to_string()));
+                }
+                self.rename_project(project, new_id, &user)?;
+                Ok(ExecutionResult::one().build())
+            }
+            issuecraft_ql::IqlQuery::Archive(ArchiveStatement { target })
+            | issuecraft_ql::IqlQuery::Unarchive(ArchiveStatement { target }) => {
+                // Archiving hides the target the way deleting it would, so it needs the
+                // permission to delete it
+                let (resource, context) = match target {
+                    ArchiveTarget::Issue(id) => {
+                        let issue: IssueInfo = self.get(id)?;
+                        let context = value! ({
+                            "author": (issue.author.to_string()),
+                            "project_owner": (self.get(&issue.project)?.owner.to_string()),
+                            "project": (issue.project.to_string())
+                        });
+                        (Resource::Issue, context)
+                    }
+                    ArchiveTarget::Project(id) => {
+                        let context = value! ({
+                            "owner": (self.get(id)?.owner.to_string())
+                        });
+                        (Resource::Project, context)
+                    }
+                };
+                if !authorization_provider
+                    .check_authorization(&user, &Action::Delete, &resource, Some(context))
+                    .await?
+                    .status
+                    .is_authorized()
+                {
+                    return Err(BackendError::PermissionDenied(user.to_string()));
+                }
+                let archived = matches!(query, IqlQuery::Archive(_));
+                self.set_archived(target, archived, &user)
+            }
+            issuecraft_ql::IqlQuery::Comment(CommentStatement {
+                issue_id,
+                content,
+                author,
+            }) => {
+                if !self.exists(issue_id)? {
+                    return Err(BackendError::ItemNotFound {
+                        kind: EntityType::Issues.singular().to_string(),
+                        id: issue_id.to_string(),
+                    });
+                }
+                let author = match author {
+                    Some(author) if *author != user => {
+                        // Commenting on behalf of someone else needs to be allowed explicitly
+                        if !authorization_provider
+                            .check_authorization(
+                                &user,
+                                &Action::Create,
+                                &Resource::Comment,
+                                Some(value! ({
+                                    "author": (author.to_string())
+                                })),
+                            )
+                            .await?
+                            .status
+                            .is_authorized()
+                        {
+                            return Err(BackendError::PermissionDenied(user.to_string()));
+                        }
+                        if !self.exists(author)? {
+                            return Err(BackendError::UserNotFound {
+                                id: author.to_string(),
+                            });
+                        }
+                        author.clone()
+                    }
+                    _ => user.clone(),
+                };
+                let number = self.get_next_comment_number(issue_id)?;
+                let comment_info = CommentInfo {
+                    issue: issue_id.clone(),
+                    number,
+                    author,
+                    content: content.clone(),
+                    created_at: issuecraft_core::now(),
+                    reactions: BTreeMap::new(),
+                    updated_by: Some(user),
+                };
+                self.set_numbered(
+                    &CommentId::from_str(&format!("{}/c{number}", &**issue_id)),
+                    &comment_info,
+                    &comment_counter_key(issue_id),
+                    number,
+                )?;
+                Ok(ExecutionResult::one().build())
+            }
+            issuecraft_ql::IqlQuery::React(ReactionStatement {
+                comment_id,
+                reaction,
+            }) => {
+                let mut comment_info = self.get(comment_id)?;
+                // A reaction is added in the user's own name, like a comment, so it needs the
+                // permission to comment rather than to edit the comment reacted to
+                let project_owner = self.get(&self.get(&comment_info.issue)?.project)?.owner;
+                if !authorization_provider
+                    .check_authorization(
+                        &user,
+                        &Action::Create,
+                        &Resource::Comment,
+                        Some(value! ({
+                            "project_owner": (project_owner.to_string()),
+                            "author": (user.to_string())
+                        })),
+                    )
+                    .await?
+                    .status
+                    .is_authorized()
+                {
+                    return Err(BackendError::PermissionDenied(user.to_string()));
+                }
+                let users: &mut Vec<UserId> =
+                    comment_info.reactions.entry(reaction.clone()).or_default();
+                // Reacting twice with the same reaction counts once
+                if users.contains(&user) {
+                    return Ok(ExecutionResult::zero().build());
+                }
+                users.push(user.clone());
+                comment_info.updated_by = Some(user);
+                self.set(comment_id, &comment_info)?;
+                Ok(ExecutionResult::one().build())
+            }
+            issuecraft_ql::IqlQuery::Unreact(ReactionStatement {
+                comment_id,
+                reaction,
+            }) => {
+                let mut comment_info = self.get(comment_id)?;
+                // Only the user's own reaction is taken back, so this needs no more than reacting
+                let project_owner = self.get(&self.get(&comment_info.issue)?.project)?.owner;
+                if !authorization_provider
+                    .check_authorization(
+                        &user,
+                        &Action::Create,
+                        &Resource::Comment,
+                        Some(value! ({
+                            "project_owner": (project_owner.to_string()),
+                            "author": (user.to_string())
+                        })),
+                    )
+                    .await?
+                    .status
+                    .is_authorized()
+                {
+                    return Err(BackendError::PermissionDenied(user.to_string()));
+                }
+                let Some(users) = comment_info.reactions.get_mut(reaction) else {
+                    return Ok(ExecutionResult::zero().build());
+                };
+                let count = users.len();
+                users.retain(|reacted| *reacted != user);
+                if users.len() == count {
+                    return Ok(ExecutionResult::zero().build());
+                }
+                if users.is_empty() {
+                    comment_info.reactions.remove(reaction);
+                }
+                comment_info.updated_by = Some(user);
+                self.set(comment_id, &comment_info)?;
+                Ok(ExecutionResult::one().build())
+            }
+            issuecraft_ql::IqlQuery::Explain(query) => self.explain(query),
+            issuecraft_ql::IqlQuery::Reindex => {
+                self.reindex()?;
+                Ok(ExecutionResult::zero().build())
+            }
+            issuecraft_ql::IqlQuery::Import(statement) => {
+                let actions = if statement.merge {
+                    [Action::Create, Action::Update].as_slice()
+                } else {
+                    [Action::Create].as_slice()
+                };
+                for action in actions {
+                    for resource in [
+                        Resource::User,
+                        Resource::Project,
+                        Resource::Issue,
+                        Resource::Comment,
+                    ] {
+                        if !authorization_provider
+                            .check_authorization(&user, action, &resource, None)
+                            .await?
+                            .status
+                            .is_authorized()
+                        {
+                            return Err(BackendError::PermissionDenied(user.to_string()));
+                        }
+                    }
+                }
+                let rows = self.import(&statement.dump, statement.merge)?;
+                Ok(ExecutionResult::builder(rows as u64).build())
+            }
+            issuecraft_ql::IqlQuery::Begin => {
+                self.begin()?;
+                Ok(ExecutionResult::zero().build())
+            }
+            issuecraft_ql::IqlQuery::Commit => {
+                self.commit()?;
+                Ok(ExecutionResult::zero().build())
+            }
+            issuecraft_ql::IqlQuery::Rollback => {
+                self.rollback()?;
+                Ok(ExecutionResult::zero().build())
+            }
+            issuecraft_ql::IqlQuery::Set(option) => {
+                session.set(option);
+                Ok(ExecutionResult::zero().build())
+            }
+            issuecraft_ql::IqlQuery::History(issue_id) => {
+                let entries = self.issue_history(issue_id)?;
+                Ok(ExecutionResult::zero().data(stringify(&entries)).build())
+            }
+            issuecraft_ql::IqlQuery::Describe(entity) => {
+                let fields = describe_entity(*entity)?;
+                Ok(ExecutionResult::zero().data(stringify(&fields)).build())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use issuecraft_core::{AuthorizationResult, AuthorizationStatus};
+    use std::task::{Context, Poll, Waker};
+
+    use issuecraft_ql::{ComparisonOp, IqlValue, IssueKind, NullsOrder};
+
+    use super::*;
+
+    fn issue(title: &str, assignee: &str) -> IssueInfo {
+        IssueInfo {
+            author: UserId::new("default"),
+            title: title.to_string(),
+            kind: IssueKind::Bug,
+            description: None,
+            status: IssueStatus::Open,
+            project: ProjectId::new("test"),
+            priority: None,
+            assignee: UserId::new(assignee),
+            labels: Vec::new(),
+            updated_at: None,
+            updated_by: None,
+            archived: false,
+        }
+    }
+
+    /// Stores `value` under `key` as is, bypassing every check. A row that can't be parsed or
+    /// decoded fails the queries that read it, which shows whether they did.
+    fn store_raw(db: &Database, table: TableDefinition<&str, String>, key: &str, value: &str) {
+        let write_txn = db.db.begin_write().unwrap();
+        write_txn
+            .open_table(table)
+            .unwrap()
+            .insert(key, value.to_string())
+            .unwrap();
+        write_txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_select_for_each_only_decodes_matching_rows() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        for number in 1..=10 {
+            let assignee = if number % 5 == 0 { "alice" } else { "bob" };
+            db.set(
+                &IssueId::new(&format!("test#{number}")),
+                &issue(&format!("Issue {number}"), assignee),
+            )
+            .unwrap();
+        }
+
+        let select_statement = SelectStatement {
+            columns: Columns::All,
+            from: EntityType::Issues,
+            from_alias: None,
+            join: None,
+            filter: Some(FilterExpression::Comparison {
+                field: "assignee".to_string(),
+                op: ComparisonOp::Equal,
+                value: IqlValue::String("alice".to_string()),
+            }),
+            group_by: None,
+            order_by: None,
+            after: None,
+            include_archived: false,
+            limit: None,
+            offset: None,
+        };
+        // Bob's row is no issue, decoding it would fail the select
+        store_raw(&db, TABLE_ISSUES, "test#11", r#"{"assignee":"bob"}"#);
+        assert!(db.get(&IssueId::new("test#11")).is_err());
+        let mut titles = Vec::new();
+        db.select_for_each::<IssueId>(&select_statement, |entry| {
+            titles.push(entry.value.title);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(titles, vec!["Issue 10", "Issue 5"]);
+    }
+
+    #[test]
+    fn test_select_by_id_does_a_single_keyed_lookup() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        for number in 1..=10 {
+            db.set(
+                &IssueId::new(&format!("test#{number}")),
+                &issue(&format!("Issue {number}"), "bob"),
+            )
+            .unwrap();
+        }
+        let titles = |db: &Database, query: &str| {
+            let IqlQuery::Select(select) = issuecraft_ql::parse_query(query).unwrap() else {
+                unreachable!()
+            };
+            let mut titles = Vec::new();
+            db.select_for_each::<IssueId>(&select, |entry| {
+                titles.push(entry.value.title);
+                Ok(())
+            })
+            .map(|()| titles)
+        };
+
+        // Only a scan reads the broken row
+        store_raw(&db, TABLE_ISSUES, "test#3", "not json");
+        let by_key = titles(&db, "SELECT * FROM issues WHERE id = 'test#7'").unwrap();
+        assert_eq!(by_key, vec!["Issue 7"]);
+        assert!(titles(&db, "SELECT * FROM issues WHERE id IN ('test#7')").is_err());
+
+        let query = "SELECT * FROM issues WHERE assignee = 'alice' AND id = 'test#7'";
+        assert!(titles(&db, query).unwrap().is_empty());
+        let missing = titles(&db, "SELECT * FROM issues WHERE id = 'test#11'").unwrap();
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn test_exists_does_a_single_keyed_lookup() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        for number in 1..=10 {
+            db.set(
+                &IssueId::new(&format!("test#{number}")),
+                &issue(&format!("Issue {number}"), "bob"),
+            )
+            .unwrap();
+        }
+
+        // Neither this row nor any other is parsed, only the key is looked up
+        store_raw(&db, TABLE_ISSUES, "test#3", "not json");
+        assert!(db.exists(&IssueId::new("test#3")).unwrap());
+        assert!(db.exists(&IssueId::new("test#7")).unwrap());
+        assert!(!db.exists(&IssueId::new("test#11")).unwrap());
+    }
+
+    #[test]
+    fn test_order_by_places_missing_values_by_nulls_order() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        for (number, priority) in [
+            (1, Some(Priority::Low)),
+            (2, None),
+            (3, Some(Priority::High)),
+        ] {
+            db.set(
+                &IssueId::new(&format!("test#{number}")),
+                &IssueInfo {
+                    priority,
+                    ..issue(&format!("Issue {number}"), "bob")
+                },
+            )
+            .unwrap();
+        }
+
+        let ordered = |direction: OrderDirection, nulls: Option<NullsOrder>| {
+            db.get_all::<IssueId>(&SelectStatement {
+                columns: Columns::All,
+                from: EntityType::Issues,
+                from_alias: None,
+                join: None,
+                filter: None,
+                group_by: None,
+                order_by: Some(OrderBy {
+                    field: "priority".to_string(),
+                    direction,
+                    nulls,
+                }),
+                after: None,
+                include_archived: false,
+                limit: None,
+                offset: None,
+            })
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.key.to_string())
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ordered(OrderDirection::Asc, None).last().unwrap(), "test#2");
+        assert_eq!(
+            ordered(OrderDirection::Desc, None).first().unwrap(),
+            "test#2"
+        );
+        for direction in [OrderDirection::Asc, OrderDirection::Desc] {
+            assert_eq!(
+                ordered(direction.clone(), Some(NullsOrder::First))
+                    .first()
+                    .unwrap(),
+                "test#2"
+            );
+            assert_eq!(
+                ordered(direction, Some(NullsOrder::Last)).last().unwrap(),
+                "test#2"
+            );
+        }
+    }
+
+    #[test]
+    fn test_order_by_priority_sorts_by_severity() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        for (number, priority) in [
+            (1, Priority::Medium),
+            (2, Priority::Critical),
+            (3, Priority::Low),
+            (4, Priority::High),
+        ] {
+            db.set(
+                &IssueId::new(&format!("test#{number}")),
+                &IssueInfo {
+                    priority: Some(priority),
+                    ..issue(&format!("Issue {number}"), "bob")
+                },
+            )
+            .unwrap();
+        }
+
+        let ordered = db
+            .get_all::<IssueId>(&SelectStatement {
+                columns: Columns::All,
+                from: EntityType::Issues,
+                from_alias: None,
+                join: None,
+                filter: None,
+                group_by: None,
+                order_by: Some(OrderBy {
+                    field: "priority".to_string(),
+                    direction: OrderDirection::Desc,
+                    nulls: None,
+                }),
+                after: None,
+                include_archived: false,
+                limit: None,
+                offset: None,
+            })
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.key.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(ordered, vec!["test#2", "test#4", "test#1", "test#3"]);
+    }
+
+    #[test]
+    fn test_links_can_be_selected_by_type_and_source() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        for (id, source, target, link_type) in [
+            ("L1", "test#1", "test#2", "blocks"),
+            ("L2", "test#1", "test#3", "relates"),
+            ("L3", "test#2", "test#3", "blocks"),
+        ] {
+            db.set(
+                &LinkId::new(id),
+                &LinkInfo {
+                    source: IssueId::new(source),
+                    target: IssueId::new(target),
+                    link_type: link_type.to_string(),
+                },
+            )
+            .unwrap();
+        }
+
+        let links = |field: &str, value: &str| {
+            db.get_all::<LinkId>(&SelectStatement {
+                columns: Columns::All,
+                from: EntityType::Links,
+                from_alias: None,
+                join: None,
+                filter: Some(FilterExpression::Comparison {
+                    field: field.to_string(),
+                    op: ComparisonOp::Equal,
+                    value: IqlValue::String(value.to_string()),
+                }),
+                group_by: None,
+                order_by: None,
+                after: None,
+                include_archived: false,
+                limit: None,
+                offset: None,
+            })
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.key.to_string())
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(links("type", "blocks"), vec!["L1", "L3"]);
+        assert_eq!(links("source", "test#1"), vec!["L1", "L2"]);
+        assert!(links("type", "duplicates").is_empty());
+    }
+
+    #[test]
+    fn test_diff_issue_lists_changes_between_versions() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        let id = IssueId::new("test#1");
+        let created = issue("Issue 1", "bob");
+        db.set(&id, &created).unwrap();
+        let prioritized = IssueInfo {
+            priority: Some(Priority::High),
+            ..created.clone()
+        };
+        db.set(&id, &prioritized).unwrap();
+        // Writing the same state again doesn't create a version
+        db.set(&id, &prioritized).unwrap();
+        db.set(
+            &id,
+            &IssueInfo {
+                status: IssueStatus::Blocked,
+                ..prioritized
+            },
+        )
+        .unwrap();
+
+        let string = |value: &str| Some(VString::new(value).into_value());
+        assert_eq!(
+            db.diff_issue(&id, 1, 3).unwrap(),
+            vec![
+                FieldChange {
+                    field: "priority".to_string(),
+                    old: None,
+                    new: string("High"),
+                },
+                FieldChange {
+                    field: "status".to_string(),
+                    old: string("Open"),
+                    new: string("Blocked"),
+                },
+            ]
+        );
+        assert_eq!(
+            db.diff_issue(&id, 2, 3).unwrap(),
+            vec![FieldChange {
+                field: "status".to_string(),
+                old: string("Open"),
+                new: string("Blocked"),
+            }]
+        );
+        assert!(db.diff_issue(&id, 3, 3).unwrap().is_empty());
+        assert!(db.diff_issue(&id, 1, 4).is_err());
+
+        db.delete(&id).unwrap();
+        assert!(db.diff_issue(&id, 1, 3).is_err());
+    }
+
+    /// Lets every user do everything, so tests can act as someone other than the default user.
+    struct AllowAll;
+
+    #[async_trait]
+    impl AuthorizationProvider for AllowAll {
+        async fn check_authorization(
+            &self,
+            principal: &UserId,
+            action: &Action,
+            resource: &Resource,
+            _context: Option<Value>,
+        ) -> Result<AuthorizationResult, BackendError> {
+            Ok(AuthorizationResult {
+                user: principal.clone(),
+                action: action.clone(),
+                resource: resource.clone(),
+                status: AuthorizationStatus::Authorized,
+            })
+        }
+    }
+
+    /// Lets the given number of checks through like [`AllowAll`] and denies all after them.
+    struct AllowFirst(std::sync::atomic::AtomicUsize);
+
+    impl AllowFirst {
+        fn new(checks: usize) -> Self {
+            Self(std::sync::atomic::AtomicUsize::new(checks))
+        }
+    }
+
+    #[async_trait]
+    impl AuthorizationProvider for AllowFirst {
+        async fn check_authorization(
+            &self,
+            principal: &UserId,
+            action: &Action,
+            resource: &Resource,
+            context: Option<Value>,
+        ) -> Result<AuthorizationResult, BackendError> {
+            let left = self.0.load(std::sync::atomic::Ordering::SeqCst);
+            if left == 0 {
+                return Ok(AuthorizationResult {
+                    user: principal.clone(),
+                    action: action.clone(),
+                    resource: resource.clone(),
+                    status: AuthorizationStatus::Denied,
+                });
+            }
+            self.0.store(left - 1, std::sync::atomic::Ordering::SeqCst);
+            AllowAll
+                .check_authorization(principal, action, resource, context)
+                .await
+        }
+    }
+
+    /// Lets everything through like [`AllowAll`], writing down each check it was asked for.
+    #[derive(Default)]
+    struct Recording(std::sync::Mutex<Vec<String>>);
+
+    impl Recording {
+        fn take(&self) -> Vec<String> {
+            std::mem::take(&mut self.0.lock().unwrap())
+        }
+    }
+
+    #[async_trait]
+    impl AuthorizationProvider for Recording {
+        async fn check_authorization(
+            &self,
+            principal: &UserId,
+            action: &Action,
+            resource: &Resource,
+            context: Option<Value>,
+        ) -> Result<AuthorizationResult, BackendError> {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{action:?} {resource:?} {context:?}"));
+            AllowAll
+                .check_authorization(principal, action, resource, context)
+                .await
+        }
+    }
+
+    /// The engine never suspends with the providers used here, so one poll finishes a query.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        match std::pin::pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("Query did not complete"),
+        }
+    }
+
+    fn run_as(
+        db: &mut Database,
+        user: &str,
+        query: &IqlQuery,
+    ) -> Result<ExecutionResult, BackendError> {
+        block_on(db.execute(
+            &AllowAll,
+            UserId::new(user),
+            query,
+            &mut SessionOptions::default(),
+        ))
+    }
+
+    fn execute_as(
+        db: &mut Database,
+        user: &str,
+        query: &str,
+    ) -> Result<ExecutionResult, BackendError> {
+        run_as(db, user, &issuecraft_ql::parse_query(query).unwrap())
+    }
+
+    fn execute(db: &mut Database, query: &str) -> Result<ExecutionResult, BackendError> {
+        execute_as(db, "default", query)
+    }
+
+    fn execute_in(
+        db: &mut Database,
+        session: &mut SessionOptions,
+        query: &str,
+    ) -> Result<ExecutionResult, BackendError> {
+        let query = issuecraft_ql::parse_query(query).unwrap();
+        block_on(db.execute(&AllowAll, UserId::new("default"), &query, session))
+    }
+
+    #[test]
+    fn test_bulk_close_skips_fresh_and_closed_issues() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        let stale = Some(time::UtcDateTime::from_unix_timestamp(1_640_995_200).unwrap());
+        db.set(
+            &IssueId::new("test#1"),
+            &IssueInfo {
+                updated_at: stale,
+                ..issue("Stale", "bob")
+            },
+        )
+        .unwrap();
+        db.set(
+            &IssueId::new("test#2"),
+            &IssueInfo {
+                updated_at: Some(time::UtcDateTime::now()),
+                ..issue("Fresh", "bob")
+            },
+        )
+        .unwrap();
+        db.set(
+            &IssueId::new("test#3"),
+            &IssueInfo {
+                status: IssueStatus::Closed {
+                    reason: CloseReason::Done,
+                },
+                updated_at: stale,
+                ..issue("Stale but closed", "bob")
+            },
+        )
+        .unwrap();
+
+        let result = execute(
+            &mut db,
+            "CLOSE issues WHERE updated_at < DATE '2023-01-01' WITH wontfix",
+        )
+        .unwrap();
+
+        assert_eq!(result.rows, 1);
+        let status = |db: &Database, id: &str| db.get(&IssueId::new(id)).unwrap().status;
+        assert!(matches!(
+            status(&db, "test#1"),
+            IssueStatus::Closed {
+                reason: CloseReason::WontFix { reason: None }
+            }
+        ));
+        assert!(db.get(&IssueId::new("test#1")).unwrap().updated_at > stale);
+        assert!(matches!(status(&db, "test#2"), IssueStatus::Open));
+        assert!(matches!(
+            status(&db, "test#3"),
+            IssueStatus::Closed {
+                reason: CloseReason::Done
+            }
+        ));
+    }
+
+    #[test]
+    fn test_mutations_record_the_acting_user() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT test WITH NAME 'Test'").unwrap();
+        execute(
+            &mut db,
+            "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Crash'",
+        )
+        .unwrap();
+        execute(&mut db, "COMMENT ON ISSUE test#1 WITH 'Seen it too'").unwrap();
+        let project = ProjectId::new("test");
+        let issue = IssueId::new("test#1");
+        let comment = CommentId::new("test#1/c1");
+        let default = Some(UserId::new("default"));
+        assert_eq!(db.get(&project).unwrap().updated_by, default);
+        assert_eq!(db.get(&issue).unwrap().updated_by, default);
+        assert_eq!(db.get(&comment).unwrap().updated_by, default);
+
+        execute_as(
+            &mut db,
+            "alice",
+            "UPDATE project test SET description = 'Tests'",
+        )
+        .unwrap();
+        execute_as(&mut db, "alice", "UPDATE issue test#1 SET priority = high").unwrap();
+        execute_as(
+            &mut db,
+            "alice",
+            "UPDATE comment test#1/c1 SET content = 'Fixed'",
+        )
+        .unwrap();
+
+        let alice = Some(UserId::new("alice"));
+        assert_eq!(db.get(&project).unwrap().updated_by, alice);
+        assert_eq!(db.get(&issue).unwrap().updated_by, alice);
+        assert_eq!(db.get(&comment).unwrap().updated_by, alice);
+
+        execute_as(&mut db, "bob", "CLOSE issue test#1").unwrap();
+        assert_eq!(db.get(&issue).unwrap().updated_by, Some(UserId::new("bob")));
+    }
+
+    #[test]
+    fn test_mutations_are_recorded_in_the_audit_log_in_order() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        // Written directly so that it is not audited
+        db.set(
+            &UserId::new("alice"),
+            &UserInfo {
+                name: "alice".to_string(),
+                display: None,
+                email: None,
+            },
+        )
+        .unwrap();
+        let queries = [
+            "CREATE PROJECT test WITH NAME 'Test'",
+            "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Crash'",
+            "UPDATE issue test#1 SET priority = high",
+            "ASSIGN ISSUE test#1 TO alice",
+            "CLOSE ISSUE test#1 WITH DONE",
+            "REOPEN ISSUE test#1",
+            "COMMENT ON ISSUE test#1 WITH 'Still broken'",
+            "DELETE ISSUE test#1",
+        ];
+        for query in queries {
+            execute(&mut db, query).unwrap();
+        }
+        // Neither failed mutations nor reads are audited
+        assert!(execute(&mut db, "CLOSE ISSUE test#1").is_err());
+        execute(&mut db, "SELECT * FROM issues").unwrap();
+
+        let result = execute(&mut db, "SELECT * FROM audit").unwrap();
+        let entries = facet_json::from_str::<SelectData<UntypedEntry>>(&result.data.unwrap())
+            .unwrap()
+            .rows;
+        let field = |entry: &UntypedEntry, field: &str| {
+            entry
+                .value
+                .as_object()
+                .unwrap()
+                .get(field)
+                .unwrap()
+                .as_string()
+                .unwrap()
+                .to_string()
+        };
+        let recorded = entries
+            .iter()
+            .map(|entry| (field(entry, "action"), field(entry, "id")))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            recorded,
+            [
+                ("Create", "test"),
+                ("Create", "test#1"),
+                ("Update", "test#1"),
+                ("Assign", "test#1"),
+                ("Close", "test#1"),
+                ("Reopen", "test#1"),
+                ("Comment", "test#1/c1"),
+                ("Delete", "test#1"),
+            ]
+            .map(|(action, id)| (action.to_string(), id.to_string()))
+        );
+        assert!(
+            entries
+                .iter()
+                .all(|entry| field(entry, "user") == "default")
+        );
+        assert_eq!(
+            field(&entries[2], "query"),
+            "UPDATE ISSUE test#1 SET priority = HIGH"
+        );
+
+        // The log is read-only
+        assert!(issuecraft_ql::parse_query("UPDATE audit SET id = 'x'").is_err());
+        assert!(issuecraft_ql::parse_query("DELETE ALL audit").is_err());
+    }
+
+    #[test]
+    fn test_issue_to_markdown_contains_the_issue_and_its_comments() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        let id = IssueId::new("test#1");
+        db.set(
+            &id,
+            &IssueInfo {
+                description: Some("The app crashes on start".to_string()),
+                priority: Some(Priority::High),
+                status: IssueStatus::Closed {
+                    reason: CloseReason::Done,
+                },
+                ..issue("Crash on start", "bob")
+            },
+        )
+        .unwrap();
+        for (number, content) in [(1, "Happens to me too"), (2, "Fixed in 1.2")] {
+            db.set(
+                &CommentId::new(&format!("test#1/c{number}")),
+                &CommentInfo {
+                    issue: id.clone(),
+                    number,
+                    created_at: time::UtcDateTime::now(),
+                    content: content.to_string(),
+                    author: UserId::new("alice"),
+                    reactions: BTreeMap::new(),
+                    updated_by: None,
+                },
+            )
+            .unwrap();
+        }
+
+        let markdown = db.issue_to_markdown(&id).unwrap();
+
+        assert!(markdown.starts_with("# Crash on start\n"));
+        assert!(markdown.contains("| Status | Closed (DONE) |"));
+        assert!(markdown.contains("| Priority | HIGH |"));
+        assert!(markdown.contains("| Kind | BUG |"));
+        assert!(markdown.contains("The app crashes on start"));
+        let first = markdown.find("Happens to me too").unwrap();
+        let second = markdown.find("Fixed in 1.2").unwrap();
+        assert!(first < second);
+    }
+
+    #[test]
+    fn test_issue_to_markdown_escapes_pipes_and_newlines() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        let id = IssueId::new("test#1");
+        db.set(
+            &id,
+            &IssueInfo {
+                description: Some("Steps:\r\n| a | b |\n---".to_string()),
+                ..issue("Crash | hang\n# on start", "bob")
+            },
+        )
+        .unwrap();
+
+        let markdown = db.issue_to_markdown(&id).unwrap();
+
+        assert!(markdown.starts_with("# Crash \\| hang<br># on start\n"));
+        assert!(markdown.contains("\nSteps:<br>\\| a \\| b \\|<br>---\n"));
+    }
+
+    #[test]
+    fn test_reindex_repairs_the_assignee_index() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        for number in 1..=6 {
+            let assignee = if number % 3 == 0 { "alice" } else { "bob" };
+            db.set(
+                &IssueId::new(&format!("test#{number}")),
+                &issue(&format!("Issue {number}"), assignee),
+            )
+            .unwrap();
+        }
+        db.delete(&IssueId::new("test#6")).unwrap();
+
+        // Drift the index away from the issues table
+        let write_txn = db.db.begin_write().unwrap();
+        {
+            let mut index = write_txn
+                .open_multimap_table(INDEX_ISSUES_BY_ASSIGNEE)
+                .unwrap();
+            index.remove("alice", "test#3").unwrap();
+            index.insert("alice", "test#1").unwrap();
+            index.insert("carol", "test#9").unwrap();
+        }
+        write_txn.commit().unwrap();
+
+        db.reindex().unwrap();
+
+        let scanned = |assignee: &str| {
+            db.get_all::<IssueId>(&SelectStatement {
+                columns: Columns::All,
+                from: EntityType::Issues,
+                from_alias: None,
+                join: None,
+                filter: Some(FilterExpression::Comparison {
+                    field: "assignee".to_string(),
+                    op: ComparisonOp::Equal,
+                    value: IqlValue::String(assignee.to_string()),
+                }),
+                group_by: None,
+                order_by: None,
+                after: None,
+                include_archived: false,
+                limit: None,
+                offset: None,
+            })
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.key)
+            .collect::<Vec<_>>()
+        };
+        for assignee in ["alice", "bob", "carol"] {
+            assert_eq!(
+                db.issues_assigned_to(&UserId::new(assignee)).unwrap(),
+                scanned(assignee),
+                "{assignee}"
+            );
+        }
+        assert_eq!(
+            db.issues_assigned_to(&UserId::new("alice")).unwrap(),
+            vec![IssueId::new("test#3")]
+        );
+    }
+
+    #[test]
+    fn test_paging_after_a_key_is_stable_under_inserts() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        for number in 1..=3 {
+            db.set(
+                &IssueId::new(&format!("test#{number}")),
+                &issue("Issue", "bob"),
+            )
+            .unwrap();
+        }
+        let page = |db: &Database, query: &str| {
+            let IqlQuery::Select(select) = issuecraft_ql::parse_query(query).unwrap() else {
+                panic!("Expected a SELECT");
+            };
+            db.get_all::<IssueId>(&select)
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.key.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let first = page(&db, "SELECT * FROM issues LIMIT 2");
+        assert_eq!(first, ["test#1", "test#2"]);
+        db.set(&IssueId::new("test#4"), &issue("Inserted", "bob"))
+            .unwrap();
+        let second = page(&db, "SELECT * FROM issues AFTER 'test#2' LIMIT 2");
+        assert_eq!(second, ["test#3", "test#4"]);
+        assert!(page(&db, "SELECT * FROM issues AFTER 'test#4' LIMIT 2").is_empty());
+    }
+
+    #[test]
+    fn test_open_checked_rejects_other_schema_versions() {
+        let dir = std::env::temp_dir().join(format!("issuecraft-schema-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("old.redb");
+
+        let mut db = Database::open_checked(DatabaseType::File(path.clone())).unwrap();
+        db.set_meta(SCHEMA_VERSION_KEY, "0").unwrap();
+        drop(db);
+
+        let error = Database::open_checked(DatabaseType::File(path.clone()))
+            .err()
+            .unwrap();
+        assert!(matches!(
+            &error,
+            BackendError::IncompatibleSchemaVersion { found, expected: SCHEMA_VERSION } if found == "0"
+        ));
+        assert!(error.to_string().contains("import it into a new database"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_archived_issues_are_only_selected_when_asked_for() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT test").unwrap();
+        db.set(&IssueId::new("test#1"), &issue("Kept", "bob"))
+            .unwrap();
+        db.set(&IssueId::new("test#2"), &issue("Archived", "bob"))
+            .unwrap();
+        let selected = |db: &mut Database, query: &str| -> Vec<String> {
+            let result = execute(db, query).unwrap();
+            let entries = facet_json::from_str::<SelectData<UntypedEntry>>(&result.data.unwrap())
+                .unwrap()
+                .rows;
+            entries.into_iter().map(|entry| entry.key).collect()
+        };
+
+        assert_eq!(execute(&mut db, "ARCHIVE ISSUE test#2").unwrap().rows, 1);
+        assert_eq!(execute(&mut db, "ARCHIVE ISSUE test#2").unwrap().rows, 0);
+
+        assert_eq!(selected(&mut db, "SELECT * FROM issues"), ["test#1"]);
+        assert_eq!(
+            selected(&mut db, "SELECT * FROM issues INCLUDING ARCHIVED"),
+            ["test#1", "test#2"]
+        );
+
+        execute(&mut db, "UNARCHIVE ISSUE test#2").unwrap();
+        assert_eq!(
+            selected(&mut db, "SELECT * FROM issues"),
+            ["test#1", "test#2"]
+        );
+    }
+
+    #[test]
+    fn test_comment_count_is_computed_for_selected_issues() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        for query in [
+            "CREATE PROJECT test",
+            "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Discussed'",
+            "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Quiet'",
+            "COMMENT ON ISSUE test#1 WITH 'Reproduced'",
+            "COMMENT ON ISSUE test#1 WITH 'Fixed'",
+        ] {
+            execute(&mut db, query).unwrap();
+        }
+
+        let result = execute(&mut db, "SELECT id, title, comment_count FROM issues").unwrap();
+        let entries = facet_json::from_str::<SelectData<UntypedEntry>>(&result.data.unwrap())
+            .unwrap()
+            .rows;
+        let counts = entries
+            .iter()
+            .map(|entry| {
+                let count = entry
+                    .value
+                    .as_object()
+                    .unwrap()
+                    .get("comment_count")
+                    .unwrap();
+                (
+                    entry.key.as_str(),
+                    count.as_number().unwrap().to_u64().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(counts, [("test#1", 2), ("test#2", 0)]);
+    }
+
+    #[test]
+    fn test_issues_can_only_be_assigned_to_existing_users() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT test").unwrap();
+        execute(
+            &mut db,
+            "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Crash'",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            execute(&mut db, "ASSIGN ISSUE test#1 TO alice"),
+            Err(BackendError::UserNotFound { id }) if id == "alice"
+        ));
+        execute(&mut db, "CREATE USER alice").unwrap();
+        execute(&mut db, "ASSIGN ISSUE test#1 TO alice").unwrap();
+        assert_eq!(
+            db.get(&IssueId::new("test#1")).unwrap().assignee,
+            UserId::new("alice")
+        );
+
+        let query = "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Lost' ASSIGNEE bob";
+        assert!(matches!(
+            execute(&mut db, query),
+            Err(BackendError::UserNotFound { id }) if id == "bob"
+        ));
+        // Without an assignee, issues are assigned to whoever creates them
+        assert!(matches!(
+            execute_as(
+                &mut db,
+                "bob",
+                "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Lost'"
+            ),
+            Err(BackendError::UserNotFound { id }) if id == "bob"
+        ));
+        assert!(!db.exists(&IssueId::new("test#2")).unwrap());
+    }
+
+    #[test]
+    fn test_reopen_can_add_a_comment() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        for query in [
+            "CREATE PROJECT test",
+            "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Crash'",
+            "CLOSE ISSUE test#1 WITH DONE",
+        ] {
+            execute(&mut db, query).unwrap();
+        }
+        let issue = IssueId::new("test#1");
+        let comments = |db: &Database| db.issue_comments(&issue).unwrap();
+
+        let query = "REOPEN ISSUE test#1 WITH 'regression found'";
+        assert_eq!(execute(&mut db, query).unwrap().rows, 2);
+        assert!(!db.get(&issue).unwrap().is_closed());
+        assert_eq!(comments(&db), [CommentId::new("test#1/c1")]);
+        let comment = db.get(&CommentId::new("test#1/c1")).unwrap();
+        assert_eq!(comment.content, "regression found");
+        assert_eq!(comment.author, UserId::new("default"));
+        assert!(db.transaction.is_none());
+
+        // Within BEGIN, both go with the surrounding transaction
+        execute(&mut db, "CLOSE ISSUE test#1 WITH DONE").unwrap();
+        execute(&mut db, "BEGIN").unwrap();
+        assert_eq!(execute(&mut db, query).unwrap().rows, 2);
+        execute(&mut db, "ROLLBACK").unwrap();
+        assert!(db.get(&issue).unwrap().is_closed());
+        assert_eq!(comments(&db).len(), 1);
+        execute(&mut db, "REOPEN ISSUE test#1").unwrap();
+
+        // Reopening an open issue neither changes it nor comments on it
+        assert_eq!(execute(&mut db, query).unwrap().rows, 0);
+        assert_eq!(comments(&db).len(), 1);
+
+        execute(&mut db, "CLOSE ISSUE test#1 WITH DONE").unwrap();
+        assert_eq!(execute(&mut db, "REOPEN ISSUE test#1").unwrap().rows, 1);
+        assert_eq!(comments(&db).len(), 1);
+    }
+
+    #[test]
+    fn test_creating_several_issues_at_once() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT test").unwrap();
+
+        let result = execute(
+            &mut db,
+            "CREATE ISSUES IN test WITH TITLES ['Plan', 'Build', 'Ship']",
+        )
+        .unwrap();
+        assert_eq!(result.rows, 3);
+        for (number, title) in (1..).zip(["Plan", "Build", "Ship"]) {
+            let issue: IssueInfo = db.get(&IssueId::new(&format!("test#{number}"))).unwrap();
+            assert_eq!(issue.title, title);
+            assert_eq!(issue.kind, IssueKind::Task);
+        }
+
+        // An invalid title creates none of the issues and leaves the counter alone
+        let counter = |db: &Database| db.get_meta(&issue_counter_key("test")).unwrap();
+        assert!(execute(&mut db, "CREATE ISSUES IN test WITH TITLES ['Review', '']").is_err());
+        assert!(!db.exists(&IssueId::new("test#4")).unwrap());
+        assert_eq!(counter(&db).as_deref(), Some("3"));
+
+        // Within BEGIN, the issues are part of the surrounding transaction
+        execute(&mut db, "BEGIN").unwrap();
+        execute(
+            &mut db,
+            "CREATE ISSUES IN test WITH TITLES ['Review', 'Ship again']",
+        )
+        .unwrap();
+        assert!(db.exists(&IssueId::new("test#5")).unwrap());
+        execute(&mut db, "ROLLBACK").unwrap();
+        assert!(!db.exists(&IssueId::new("test#4")).unwrap());
+        assert_eq!(counter(&db).as_deref(), Some("3"));
+
+        execute(&mut db, "CREATE ISSUES IN test WITH TITLES ['Review']").unwrap();
+        assert!(db.exists(&IssueId::new("test#4")).unwrap());
+    }
+
+    #[test]
+    fn test_moving_an_issue_re_keys_it_and_its_comments() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT a").unwrap();
+        execute(&mut db, "CREATE PROJECT b").unwrap();
+        execute(
+            &mut db,
+            "CREATE ISSUE OF KIND bug IN a WITH TITLE 'Wrong place'",
+        )
+        .unwrap();
+        execute(
+            &mut db,
+            "CREATE ISSUE OF KIND bug IN b WITH TITLE 'Already here'",
+        )
+        .unwrap();
+        execute(&mut db, "COMMENT ON ISSUE a#1 WITH 'Belongs to b'").unwrap();
+        db.set(
+            &LinkId::new("L1"),
+            &LinkInfo {
+                source: IssueId::new("b#1"),
+                target: IssueId::new("a#1"),
+                link_type: "blocks".to_string(),
+            },
+        )
+        .unwrap();
+
+        let error = execute(&mut db, "MOVE ISSUE a#1 TO c").unwrap_err();
+        assert!(
+            matches!(error, BackendError::ItemNotFound { .. }),
+            "{error}"
+        );
+
+        let result = execute(&mut db, "MOVE ISSUE a#1 TO b").unwrap();
+        assert_eq!(result.rows, 1);
+        let moved = IssueId::new("b#2");
+        assert!(!db.exists(&IssueId::new("a#1")).unwrap());
+        assert_eq!(db.get(&moved).unwrap().project, ProjectId::new("b"));
+        assert_eq!(db.get(&moved).unwrap().title, "Wrong place");
+        assert!(!db.exists(&CommentId::new("a#1/c1")).unwrap());
+        let comment = db.get(&CommentId::new("b#2/c1")).unwrap();
+        assert_eq!(comment.issue, moved);
+        assert_eq!(comment.content, "Belongs to b");
+        assert_eq!(
+            db.issues_assigned_to(&UserId::new("default")).unwrap(),
+            [IssueId::new("b#1"), moved.clone()]
+        );
+        // The history comes along, so the move is its latest version
+        let changes = db.diff_issue(&moved, 1, 2).unwrap();
+        let project = changes
+            .iter()
+            .find(|change| change.field == "project")
+            .unwrap();
+        assert_eq!(project.old, Some(VString::new("a").into_value()));
+        assert!(db.diff_issue(&IssueId::new("a#1"), 1, 1).is_err());
+        let link = db.get(&LinkId::new("L1")).unwrap();
+        assert_eq!(link.source, IssueId::new("b#1"));
+        assert_eq!(link.target, moved);
+
+        execute(&mut db, "COMMENT ON ISSUE b#2 WITH 'Thanks'").unwrap();
+        assert!(db.exists(&CommentId::new("b#2/c2")).unwrap());
+        execute(&mut db, "CREATE ISSUE OF KIND bug IN a WITH TITLE 'Next'").unwrap();
+        assert!(db.exists(&IssueId::new("a#2")).unwrap());
+    }
+
+    #[test]
+    fn test_moving_is_authorized_in_both_projects() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT a").unwrap();
+        execute(&mut db, "CREATE PROJECT b").unwrap();
+        execute(
+            &mut db,
+            "CREATE ISSUE OF KIND bug IN a WITH TITLE 'Wrong place'",
+        )
+        .unwrap();
+        let recording = Recording::default();
+        block_on(db.execute(
+            &recording,
+            UserId::new("default"),
+            &issuecraft_ql::parse_query("MOVE ISSUE a#1 TO b").unwrap(),
+            &mut SessionOptions::default(),
+        ))
+        .unwrap();
+
+        let checks = recording.take();
+        assert_eq!(checks.len(), 2);
+        assert!(checks[0].starts_with("Delete Issue"), "{}", checks[0]);
+        assert!(
+            checks[0].contains(r#""author": "default""#),
+            "{}",
+            checks[0]
+        );
+        assert!(checks[0].contains(r#""project": "a""#), "{}", checks[0]);
+        assert!(checks[1].starts_with("Create Issue"), "{}", checks[1]);
+        assert!(checks[1].contains(r#""project": "b""#), "{}", checks[1]);
+    }
+
+    #[test]
+    fn test_renaming_a_project_re_keys_its_issues_and_comments() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT old").unwrap();
+        execute(&mut db, "CREATE PROJECT taken").unwrap();
+        execute(
+            &mut db,
+            "CREATE ISSUE OF KIND bug IN old WITH TITLE 'First'",
+        )
+        .unwrap();
+        execute(
+            &mut db,
+            "CREATE ISSUE OF KIND bug IN old WITH TITLE 'Second'",
+        )
+        .unwrap();
+        execute(&mut db, "COMMENT ON ISSUE old#1 WITH 'On the first'").unwrap();
+        execute(&mut db, "COMMENT ON ISSUE old#2 WITH 'On the second'").unwrap();
+        for (id, source, target) in [("L1", "old#1", "old#2"), ("L2", "old#2", "elsewhere#1")] {
+            db.set(
+                &LinkId::new(id),
+                &LinkInfo {
+                    source: IssueId::new(source),
+                    target: IssueId::new(target),
+                    link_type: "blocks".to_string(),
+                },
+            )
+            .unwrap();
+        }
+
+        let error = execute(&mut db, "RENAME PROJECT old TO taken").unwrap_err();
+        assert!(
+            matches!(error, BackendError::ProjectAlreadyExists(_)),
+            "{error}"
+        );
+        assert!(db.exists(&IssueId::new("old#1")).unwrap());
+
+        execute(&mut db, "RENAME PROJECT old TO new").unwrap();
+        assert!(!db.exists(&ProjectId::new("old")).unwrap());
+        assert!(db.exists(&ProjectId::new("new")).unwrap());
+        for (number, title) in [(1, "First"), (2, "Second")] {
+            assert!(!db.exists(&IssueId::new(&format!("old#{number}"))).unwrap());
+            assert!(
+                !db.exists(&CommentId::new(&format!("old#{number}/c1")))
+                    .unwrap()
+            );
+            let id = IssueId::new(&format!("new#{number}"));
+            let issue = db.get(&id).unwrap();
+            assert_eq!(issue.title, title);
+            assert_eq!(issue.project, ProjectId::new("new"));
+            let comment = db
+                .get(&CommentId::new(&format!("new#{number}/c1")))
+                .unwrap();
+            assert_eq!(comment.issue, id);
+            let changes = db.diff_issue(&id, 1, 2).unwrap();
+            assert!(changes.iter().any(|change| change.field == "project"));
+        }
+        let link = db.get(&LinkId::new("L1")).unwrap();
+        assert_eq!(
+            (link.source, link.target),
+            (IssueId::new("new#1"), IssueId::new("new#2"))
+        );
+        let link = db.get(&LinkId::new("L2")).unwrap();
+        assert_eq!(
+            (link.source, link.target),
+            (IssueId::new("new#2"), IssueId::new("elsewhere#1"))
+        );
+
+        execute(
+            &mut db,
+            "CREATE ISSUE OF KIND bug IN new WITH TITLE 'Third'",
+        )
+        .unwrap();
+        assert!(db.exists(&IssueId::new("new#3")).unwrap());
+        // The counters of the old ids went along rather than being copied
+        assert_eq!(db.get_meta(&issue_counter_key("old")).unwrap(), None);
+        assert_eq!(db.get_meta(&comment_counter_key("old#1")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_selects_name_their_columns() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        for query in [
+            "CREATE PROJECT test",
+            "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Crash'",
+        ] {
+            execute(&mut db, query).unwrap();
+        }
+        let columns = |db: &mut Database, query: &str| {
+            let result = execute(db, query).unwrap();
+            facet_json::from_str::<SelectData<Value>>(&result.data.unwrap())
+                .unwrap()
+                .columns
+        };
+
+        assert_eq!(
+            columns(&mut db, "SELECT title AS name, kind FROM issues"),
+            ["name", "kind"]
+        );
+        assert_eq!(
+            columns(
+                &mut db,
+                "SELECT id AS project FROM projects INCLUDING ARCHIVED"
+            ),
+            ["project"]
+        );
+        assert_eq!(
+            columns(
+                &mut db,
+                "SELECT kind, COUNT(*) AS n FROM issues GROUP BY kind"
+            ),
+            ["kind", "n"]
+        );
+        let all = columns(&mut db, "SELECT * FROM issues");
+        assert_eq!(all[..3], ["id", "author", "title"]);
+        let joined = columns(
+            &mut db,
+            "SELECT * FROM issues JOIN projects ON issues.project = projects.id",
+        );
+        assert!(joined.iter().any(|column| column == "projects.name"));
+
+        let table = execute(&mut db, "SELECT title AS name, kind FROM issues")
+            .unwrap()
+            .to_string();
+        assert!(table.contains("id     | name  | kind"), "{table}");
+        assert!(table.contains("test#1 | Crash | Bug"), "{table}");
+    }
+
+    #[test]
+    fn test_joining_issues_with_their_projects() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT a WITH NAME 'Alpha'").unwrap();
+        execute(&mut db, "CREATE PROJECT b WITH NAME 'Beta'").unwrap();
+        execute(
+            &mut db,
+            "CREATE ISSUE OF KIND bug IN a WITH TITLE 'In alpha'",
+        )
+        .unwrap();
+        execute(
+            &mut db,
+            "CREATE ISSUE OF KIND bug IN b WITH TITLE 'In beta'",
+        )
+        .unwrap();
+        execute(
+            &mut db,
+            "CREATE ISSUE OF KIND bug IN a WITH TITLE 'Also in alpha'",
+        )
+        .unwrap();
+
+        let query = "SELECT i.title, p.name FROM issues i JOIN projects p ON i.project = p.id";
+        let rows = |db: &mut Database| {
+            let result = execute(db, query).unwrap();
+            let rows = facet_json::from_str::<SelectData<UntypedEntry>>(&result.data.unwrap())
+                .unwrap()
+                .rows;
+            rows.into_iter()
+                .map(|row| {
+                    let row = row.value.as_object().unwrap().clone();
+                    let field = |name| row.get(name).unwrap().as_string().unwrap().to_string();
+                    (field("title"), field("projects.name"))
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            rows(&mut db),
+            [
+                ("In alpha".to_string(), "Alpha".to_string()),
+                ("Also in alpha".to_string(), "Alpha".to_string()),
+                ("In beta".to_string(), "Beta".to_string()),
+            ]
+        );
+
+        // Only the project row goes away, leaving its issue without a match
+        db.delete(&ProjectId::new("b")).unwrap();
+        assert_eq!(
+            rows(&mut db),
+            [
+                ("In alpha".to_string(), "Alpha".to_string()),
+                ("Also in alpha".to_string(), "Alpha".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unordered_selects_stop_scanning_at_the_limit() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT a").unwrap();
+        for title in ["First", "Second", "Third", "Second", "Fifth"] {
+            execute(
+                &mut db,
+                &format!("CREATE ISSUE OF KIND bug IN a WITH TITLE '{title}'"),
+            )
+            .unwrap();
+        }
+
+        // The scan never gets to the broken third row
+        store_raw(&db, TABLE_ISSUES, "a#3", "not json");
+        let result = execute(
+            &mut db,
+            "SELECT * FROM issues WHERE title = 'Second' LIMIT 1",
+        )
+        .unwrap();
+        assert!(result.data.unwrap().contains("a#2"));
+
+        // Ordering needs every matching row before the first one is known
+        assert!(
+            execute(
+                &mut db,
+                "SELECT * FROM issues WHERE title = 'Second' ORDER BY title LIMIT 1",
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_import_statement_requires_merge_for_existing_entities() {
+        let mut source = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut source, "CREATE PROJECT a").unwrap();
+        execute(
+            &mut source,
+            "CREATE ISSUE OF KIND bug IN a WITH TITLE 'Crash'",
+        )
+        .unwrap();
+        let import = |merge| {
+            IqlQuery::Import(issuecraft_ql::ImportStatement {
+                dump: source.export().unwrap(),
+                merge,
+            })
+        };
+
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        let explained = run_as(
+            &mut db,
+            "default",
+            &IqlQuery::Explain(Box::new(import(false))),
+        );
+        assert_eq!(explained.unwrap().rows, 3);
+        assert!(!db.exists(&ProjectId::new("a")).unwrap());
+
+        let denied = block_on(db.execute(
+            &issuecraft_core::SingleUserAuthorizationProvider,
+            UserId::new("bob"),
+            &import(false),
+            &mut SessionOptions::default(),
+        ));
+        assert!(matches!(denied, Err(BackendError::PermissionDenied(_))));
+
+        assert_eq!(run_as(&mut db, "default", &import(false)).unwrap().rows, 3);
+        assert!(db.exists(&IssueId::new("a#1")).unwrap());
+        // Unchanged entities count as existing too
+        assert!(matches!(
+            run_as(&mut db, "default", &import(false)),
+            Err(BackendError::ItemAlreadyExists { .. })
+        ));
+        assert_eq!(run_as(&mut db, "default", &import(true)).unwrap().rows, 3);
+    }
+
+    #[test]
+    fn test_import_checks_entries_before_writing() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        let dump = |tables: &str| format!(r#"{{"version":{DUMP_VERSION},"tables":[{tables}]}}"#);
+        let project =
+            r#"{"name":"projects","entries":[{"key":"a","value":{"name":"A","owner":"default"}}]}"#;
+
+        // An issue without a title is not an issue
+        let error = db
+            .import(
+                &dump(&format!(
+                    r#"{project},{{"name":"issues","entries":[{{"key":"a#1","value":{{"project":"a"}}}}]}}"#
+                )),
+                false,
+            )
+            .unwrap_err();
+        assert!(error.to_string().contains("a#1"), "{error}");
+        assert!(!db.exists(&ProjectId::new("a")).unwrap());
+
+        // Comments need their issue, in the dump or the database
+        let comment = r#"{"name":"comments","entries":[{"key":"a#1/c1","value":{"issue":"a#1","created_at":"2024-01-01T00:00:00Z","content":"Lost","author":"default"}}]}"#;
+        let error = db
+            .import(&dump(&format!("{project},{comment}")), false)
+            .unwrap_err();
+        assert!(
+            matches!(&error, BackendError::ItemNotFound { id, .. } if id == "a#1"),
+            "{error}"
+        );
+        assert!(!db.exists(&ProjectId::new("a")).unwrap());
+
+        db.import(&dump(project), false).unwrap();
+        execute(&mut db, "CREATE ISSUE OF KIND bug IN a WITH TITLE 'Crash'").unwrap();
+        db.import(&dump(comment), false).unwrap();
+        assert!(db.exists(&CommentId::new("a#1/c1")).unwrap());
+    }
+
+    #[test]
+    fn test_a_default_limit_applies_to_the_following_selects() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT a").unwrap();
+        for title in ["First", "Second", "Third"] {
+            execute(
+                &mut db,
+                &format!("CREATE ISSUE OF KIND bug IN a WITH TITLE '{title}'"),
+            )
+            .unwrap();
+        }
+        let selected = |db: &mut Database, session: &mut SessionOptions, query: &str| {
+            let result = execute_in(db, session, query).unwrap();
+            facet_json::from_str::<SelectData<UntypedEntry>>(&result.data.unwrap())
+                .unwrap()
+                .rows
+                .into_iter()
+                .map(|entry| entry.key)
+                .collect::<Vec<_>>()
+        };
+
+        let mut session = SessionOptions::default();
+        let result = execute_in(&mut db, &mut session, "SET default_limit = 2").unwrap();
+        assert_eq!(result.rows, 0);
+        assert_eq!(
+            selected(&mut db, &mut session, "SELECT * FROM issues"),
+            ["a#1", "a#2"]
+        );
+        assert_eq!(
+            selected(
+                &mut db,
+                &mut session,
+                "SELECT title FROM issues ORDER BY title DESC"
+            ),
+            ["a#3", "a#2"]
+        );
+        // A query's own limit wins over the default
+        assert_eq!(
+            selected(&mut db, &mut session, "SELECT * FROM issues LIMIT 1"),
+            ["a#1"]
+        );
+        // Other sessions keep their own options
+        let mut other = SessionOptions::default();
+        assert_eq!(
+            selected(&mut db, &mut other, "SELECT * FROM issues").len(),
+            3
+        );
+
+        execute_in(&mut db, &mut session, "SET default_limit = NULL").unwrap();
+        assert_eq!(
+            selected(&mut db, &mut session, "SELECT * FROM issues").len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_filtering_comments_by_a_timestamp() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        let id = IssueId::new("test#1");
+        let created = [
+            1_704_063_600_000_000_000, // 2023-12-31T23:00:00Z
+            1_704_067_200_500_000_000, // 2024-01-01T00:00:00.5Z
+            1_705_314_600_000_000_000, // 2024-01-15T10:30:00Z
+        ];
+        for (number, nanos) in (1..).zip(created) {
+            db.set(
+                &CommentId::new(&format!("test#1/c{number}")),
+                &CommentInfo {
+                    issue: id.clone(),
+                    number,
+                    created_at: time::UtcDateTime::from_unix_timestamp_nanos(nanos).unwrap(),
+                    content: format!("Comment {number}"),
+                    author: UserId::new("default"),
+                    reactions: BTreeMap::new(),
+                    updated_by: None,
+                },
+            )
+            .unwrap();
+        }
+
+        let keys = |db: &mut Database, query: &str| {
+            let result = execute(db, query).unwrap();
+            let rows = facet_json::from_str::<SelectData<UntypedEntry>>(&result.data.unwrap())
+                .unwrap()
+                .rows;
+            rows.into_iter().map(|row| row.key).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            keys(
+                &mut db,
+                "SELECT * FROM comments WHERE created_at > '2024-01-01T00:00:00Z'"
+            ),
+            ["test#1/c2", "test#1/c3"]
+        );
+        assert_eq!(
+            keys(
+                &mut db,
+                "SELECT * FROM comments WHERE created_at <= '2024-01-01T01:00:00+01:00'"
+            ),
+            ["test#1/c1"]
+        );
+    }
+
+    #[test]
+    fn test_creating_an_existing_project_on_conflict() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        let create = "CREATE PROJECT test WITH NAME 'Test'";
+        execute(&mut db, create).unwrap();
+
+        let error = execute(&mut db, create).unwrap_err();
+        assert!(
+            matches!(error, BackendError::ProjectAlreadyExists(_)),
+            "{error}"
+        );
+
+        let result = execute(&mut db, &format!("{create} ON CONFLICT DO NOTHING")).unwrap();
+        assert_eq!(result.rows, 0);
+        assert_eq!(
+            db.get(&ProjectId::new("test")).unwrap().name.as_deref(),
+            Some("Test")
+        );
+
+        let result = execute(
+            &mut db,
+            &format!("{create} ON CONFLICT UPDATE SET name = 'Renamed', description = 'Again'"),
+        )
+        .unwrap();
+        assert_eq!(result.rows, 1);
+        let project = db.get(&ProjectId::new("test")).unwrap();
+        assert_eq!(project.name.as_deref(), Some("Renamed"));
+        assert_eq!(project.description.as_deref(), Some("Again"));
+
+        // Without a conflict the clause has no effect
+        execute(
+            &mut db,
+            "CREATE PROJECT other ON CONFLICT UPDATE SET name = 'Unused'",
+        )
+        .unwrap();
+        assert_eq!(db.get(&ProjectId::new("other")).unwrap().name, None);
+    }
+
+    #[test]
+    fn test_selecting_only_ids_reads_just_the_keys() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT beta").unwrap();
+        execute(&mut db, "CREATE PROJECT alpha").unwrap();
+        execute(&mut db, "ARCHIVE PROJECT beta").unwrap();
+        assert_eq!(db.keys(EntityType::Projects).unwrap(), ["alpha", "beta"]);
+        // Selects that read these values fail
+        store_raw(&db, TABLE_PROJECTS, "alpha", "not json");
+        store_raw(&db, TABLE_USERS, DEFAULT_USER, "not json");
+
+        let result = execute(&mut db, "SELECT id FROM projects INCLUDING ARCHIVED").unwrap();
+        let rows = facet_json::from_str::<SelectData<UntypedEntry>>(&result.data.unwrap())
+            .unwrap()
+            .rows;
+        assert_eq!(
+            rows.iter().map(|row| row.key.as_str()).collect::<Vec<_>>(),
+            ["alpha", "beta"]
+        );
+        assert_eq!(
+            rows[0]
+                .value
+                .as_object()
+                .unwrap()
+                .get("id")
+                .unwrap()
+                .as_string()
+                .unwrap()
+                .as_str(),
+            "alpha"
+        );
+        let result = execute(&mut db, "SELECT id AS name FROM users LIMIT 1").unwrap();
+        assert!(result.data.unwrap().contains(r#""name":"default""#));
+
+        // Hiding the archived project needs its value
+        assert!(execute(&mut db, "SELECT id FROM projects").is_err());
+    }
+
+    #[test]
+    fn test_missing_items_are_named_in_the_singular() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        let error = execute(&mut db, "COMMENT ON ISSUE test#1 WITH 'Hello'").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "No item of type 'ISSUE' with the id 'test#1' exists"
+        );
+        let error = db.get(&IssueId::new("test#1")).unwrap_err();
+        assert!(error.to_string().contains("'ISSUE'"), "{error}");
+
+        let error = execute(&mut db, "CREATE USER default").unwrap_err();
+        assert!(error.to_string().contains("'USER'"), "{error}");
+    }
+
+    #[test]
+    fn test_snapshots_are_independent_of_the_original() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT test").unwrap();
+        execute(
+            &mut db,
+            "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Crash'",
+        )
+        .unwrap();
+
+        let mut snapshot = db.snapshot().unwrap();
+        let issue = IssueId::new("test#1");
+        assert_eq!(snapshot.get(&issue).unwrap().title, "Crash");
+        assert_eq!(
+            snapshot
+                .issues_assigned_to(&UserId::new("default"))
+                .unwrap(),
+            std::slice::from_ref(&issue)
+        );
+
+        execute(&mut snapshot, "UPDATE issue test#1 SET title = 'What if'").unwrap();
+        execute(
+            &mut snapshot,
+            "CREATE ISSUE OF KIND bug IN test WITH TITLE 'New'",
+        )
+        .unwrap();
+        assert_eq!(snapshot.get(&issue).unwrap().title, "What if");
+        assert_eq!(db.get(&issue).unwrap().title, "Crash");
+        assert!(!db.exists(&IssueId::new("test#2")).unwrap());
+
+        execute(&mut db, "DELETE ISSUE test#1").unwrap();
+        assert!(snapshot.exists(&issue).unwrap());
+    }
+
+    #[test]
+    fn test_archiving_is_authorized_like_deleting() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT a").unwrap();
+        execute(&mut db, "CREATE ISSUE OF KIND bug IN a WITH TITLE 'Crash'").unwrap();
+        let recording = Recording::default();
+        let mut checks = |query: &str| {
+            let query = issuecraft_ql::parse_query(query).unwrap();
+            block_on(db.execute(
+                &recording,
+                UserId::new("default"),
+                &query,
+                &mut SessionOptions::default(),
+            ))
+            .unwrap();
+            recording.take()
+        };
+
+        let archive_issue = checks("ARCHIVE ISSUE a#1");
+        assert_eq!(archive_issue.len(), 1);
+        assert!(
+            archive_issue[0].starts_with("Delete Issue"),
+            "{}",
+            archive_issue[0]
+        );
+        assert!(
+            archive_issue[0].contains(r#""project_owner": "default""#),
+            "{}",
+            archive_issue[0]
+        );
+        assert_eq!(checks("UNARCHIVE ISSUE a#1"), archive_issue);
+        let archive_project = checks("ARCHIVE PROJECT a");
+        assert_eq!(archive_project.len(), 1);
+        assert_eq!(checks("UNARCHIVE PROJECT a"), archive_project);
+        assert_eq!(checks("DELETE PROJECT a"), archive_project);
+
+        execute(&mut db, "CREATE PROJECT b").unwrap();
+        let denied = block_on(db.execute(
+            &issuecraft_core::SingleUserAuthorizationProvider,
+            UserId::new("bob"),
+            &issuecraft_ql::parse_query("ARCHIVE PROJECT b").unwrap(),
+            &mut SessionOptions::default(),
+        ));
+        assert!(matches!(denied, Err(BackendError::PermissionDenied(_))));
+        assert!(!db.get(&ProjectId::new("b")).unwrap().archived);
+    }
+
+    #[test]
+    fn test_reacting_is_authorized_like_commenting() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT a").unwrap();
+        execute(&mut db, "CREATE USER bob").unwrap();
+        execute(&mut db, "CREATE ISSUE OF KIND bug IN a WITH TITLE 'Crash'").unwrap();
+        execute(&mut db, "COMMENT ON ISSUE a#1 WITH 'Same here' AUTHOR bob").unwrap();
+        let recording = Recording::default();
+        let mut checks = |query: &str| {
+            let query = issuecraft_ql::parse_query(query).unwrap();
+            block_on(db.execute(
+                &recording,
+                UserId::new("default"),
+                &query,
+                &mut SessionOptions::default(),
+            ))
+            .unwrap();
+            recording.take()
+        };
+
+        // Reacting to someone else's comment is not editing it
+        let react = checks("REACT TO COMMENT a#1/c1 WITH '+1'");
+        assert_eq!(react.len(), 1);
+        assert!(react[0].starts_with("Create Comment"), "{}", react[0]);
+        assert!(react[0].contains(r#""author": "default""#), "{}", react[0]);
+        assert_eq!(checks("UNREACT TO COMMENT a#1/c1 WITH '+1'"), react);
+
+        let denied = block_on(db.execute(
+            &issuecraft_core::SingleUserAuthorizationProvider,
+            UserId::new("bob"),
+            &issuecraft_ql::parse_query("REACT TO COMMENT a#1/c1 WITH '+1'").unwrap(),
+            &mut SessionOptions::default(),
+        ));
+        assert!(matches!(denied, Err(BackendError::PermissionDenied(_))));
+        let comment: CommentInfo = db.get(&CommentId::new("a#1/c1")).unwrap();
+        assert!(comment.reactions.is_empty());
+    }
+
+    #[test]
+    fn test_deleting_comments_is_authorized_for_each() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT a").unwrap();
+        execute(&mut db, "CREATE USER bob").unwrap();
+        execute(&mut db, "CREATE ISSUE OF KIND bug IN a WITH TITLE 'Crash'").unwrap();
+        execute(&mut db, "COMMENT ON ISSUE a#1 WITH 'Same here'").unwrap();
+        execute(&mut db, "COMMENT ON ISSUE a#1 WITH 'Me too' AUTHOR bob").unwrap();
+
+        let denied = block_on(db.execute(
+            &issuecraft_core::SingleUserAuthorizationProvider,
+            UserId::new("bob"),
+            &issuecraft_ql::parse_query("DELETE ALL comments").unwrap(),
+            &mut SessionOptions::default(),
+        ));
+        assert!(matches!(denied, Err(BackendError::PermissionDenied(_))));
+        assert!(db.exists(&CommentId::new("a#1/c2")).unwrap());
+
+        let recording = Recording::default();
+        block_on(db.execute(
+            &recording,
+            UserId::new("default"),
+            &issuecraft_ql::parse_query("DELETE ALL comments").unwrap(),
+            &mut SessionOptions::default(),
+        ))
+        .unwrap();
+        let checks = recording.take();
+        assert_eq!(checks.len(), 2);
+        assert!(
+            checks
+                .iter()
+                .all(|check| check.starts_with("Delete Comment"))
+        );
+        assert!(
+            checks[0].contains(r#""author": "default""#),
+            "{}",
+            checks[0]
+        );
+        assert!(checks[1].contains(r#""author": "bob""#), "{}", checks[1]);
+        assert!(!db.exists(&CommentId::new("a#1/c1")).unwrap());
+    }
+
+    #[test]
+    fn test_deleting_a_project_deletes_its_archived_issues() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT test").unwrap();
+        execute(&mut db, "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Old'").unwrap();
+        execute(&mut db, "ARCHIVE ISSUE test#1").unwrap();
+
+        let result = execute(&mut db, "EXPLAIN DELETE PROJECT test").unwrap();
+        assert!(result.data.unwrap().contains("test#1"));
+        execute(&mut db, "DELETE PROJECT test").unwrap();
+        assert!(!db.exists(&IssueId::new("test#1")).unwrap());
+    }
+
+    #[test]
+    fn test_timestamps_order_by_time() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT test WITH NAME 'Test'").unwrap();
+        execute(
+            &mut db,
+            "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Crash'",
+        )
+        .unwrap();
+        execute(&mut db, "COMMENT ON ISSUE test#1 WITH 'First'").unwrap();
+        execute(&mut db, "COMMENT ON ISSUE test#1 WITH 'Second'").unwrap();
+
+        // The later comment is written a microsecond before the earlier one
+        let first = CommentId::from_str("test#1/c1");
+        let second = CommentId::from_str("test#1/c2");
+        let created_at = db.get(&first).unwrap().created_at;
+        db.set(
+            &second,
+            &CommentInfo {
+                created_at: created_at - time::Duration::MICROSECOND,
+                ..db.get(&second).unwrap()
+            },
+        )
+        .unwrap();
+
+        let result = execute(
+            &mut db,
+            "SELECT content, created_at FROM comments ORDER BY created_at",
+        )
+        .unwrap();
+        let rows = facet_json::from_str::<SelectData<UntypedEntry>>(&result.data.unwrap())
+            .unwrap()
+            .rows;
+        let field = |row: &UntypedEntry, name| {
+            let row = row.value.as_object().unwrap();
+            row.get(name).unwrap().as_string().unwrap().to_string()
+        };
+        assert_eq!(
+            rows.iter().map(|row| row.key.clone()).collect::<Vec<_>>(),
+            ["test#1/c2", "test#1/c1"]
+        );
+        assert_eq!(
+            rows.iter()
+                .map(|row| field(row, "content"))
+                .collect::<Vec<_>>(),
+            ["Second", "First"]
+        );
+        assert!(
+            rows.iter().all(|row| {
+                field(row, "created_at").len() == "2024-01-01T12:00:00.000000Z".len()
+            })
+        );
+
+        // Timestamps stored with another precision still order in time
+        let order_by = OrderBy {
+            field: "created_at".to_string(),
+            direction: OrderDirection::Asc,
+            nulls: None,
+        };
+        let timestamp = |value| VString::new(value).into_value();
+        assert_eq!(
+            compare_ordered(
+                Some(&timestamp("2024-01-01T12:00:00Z")),
+                Some(&timestamp("2024-01-01T12:00:00.000001Z")),
+                &order_by
+            ),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_ordered(
+                Some(&timestamp("2024-01-01T12:00:00.5Z")),
+                Some(&timestamp("2024-01-01T12:00:00.000001Z")),
+                &order_by
+            ),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_history_lists_the_changes_of_an_issue_in_order() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        let queries = [
+            "CREATE USER alice",
+            "CREATE PROJECT test WITH NAME 'Test'",
+            "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Crash'",
+            "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Unrelated'",
+            "COMMENT ON ISSUE test#1 WITH 'Reproduced'",
+            "COMMENT ON ISSUE test#2 WITH 'Elsewhere'",
+            "ASSIGN ISSUE test#1 TO alice",
+            "CLOSE ISSUE test#1 WITH DONE",
+        ];
+        for query in queries {
+            execute(&mut db, query).unwrap();
+        }
+
+        let result = execute(&mut db, "HISTORY ISSUE test#1").unwrap();
+        let entries: Vec<UntypedEntry> = facet_json::from_str(&result.data.unwrap()).unwrap();
+        let actions = entries
+            .iter()
+            .map(|entry| {
+                let entry = entry.value.as_object().unwrap();
+                let field = |name| entry.get(name).unwrap().as_string().unwrap().to_string();
+                (field("action"), field("id"))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            [
+                ("Create", "test#1"),
+                ("Comment", "test#1/c1"),
+                ("Assign", "test#1"),
+                ("Close", "test#1"),
+            ]
+            .map(|(action, id)| (action.to_string(), id.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_create_rejects_invalid_fields() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        let invalid_field = |result: Result<ExecutionResult, BackendError>| match result {
+            Err(BackendError::InvalidField { field, .. }) => field,
+            other => panic!("Expected an invalid field, got {other:?}"),
+        };
+
+        for email in [
+            "alice",
+            "alice@",
+            "@example.com",
+            "alice@example",
+            "a b@example.com",
+        ] {
+            let query = format!("CREATE USER alice WITH EMAIL '{email}'");
+            assert_eq!(invalid_field(execute(&mut db, &query)), "email", "{email}");
+        }
+        execute(&mut db, "CREATE USER alice WITH EMAIL 'alice@example.com'").unwrap();
+
+        execute(&mut db, "CREATE PROJECT test").unwrap();
+
+        for title in ["", "   "] {
+            let query = format!("CREATE ISSUE OF KIND bug IN test WITH TITLE '{title}'");
+            assert_eq!(invalid_field(execute(&mut db, &query)), "title");
+        }
+        execute(
+            &mut db,
+            "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Crash'",
+        )
+        .unwrap();
+        assert!(!db.exists(&IssueId::new("test#2")).unwrap());
+    }
+
+    #[test]
+    fn test_fixture_seeds_through_the_engine() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        block_on(
+            test_support::Fixture::new()
+                .with_user("alice")
+                .with_project("a")
+                .with_issue("a", IssueKind::Bug, "Crash")
+                .with_issue("a", IssueKind::Task, "Docs")
+                .with_query(issuecraft_ql::parse_query("ASSIGN ISSUE a#2 TO alice").unwrap())
+                .build(&mut db),
+        )
+        .unwrap();
+
+        assert_eq!(db.get(&IssueId::new("a#1")).unwrap().title, "Crash");
+        assert_eq!(
+            db.get(&IssueId::new("a#2")).unwrap().assignee,
+            UserId::new("alice")
+        );
+        // Going through the engine also writes the audit log and the counters
+        let result = execute(&mut db, "HISTORY ISSUE a#2").unwrap();
+        let entries: Vec<UntypedEntry> = facet_json::from_str(&result.data.unwrap()).unwrap();
+        assert_eq!(entries.len(), 2);
+        execute(&mut db, "CREATE ISSUE OF KIND bug IN a WITH TITLE 'Next'").unwrap();
+        assert!(db.exists(&IssueId::new("a#3")).unwrap());
+
+        let failing = test_support::Fixture::new().with_issue("missing", IssueKind::Bug, "Lost");
+        assert!(matches!(
+            block_on(failing.build(&mut db)),
+            Err(BackendError::ItemNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_project_ids_must_not_contain_the_issue_separator() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        let create_project = |project_id| {
+            IqlQuery::Create(issuecraft_ql::CreateStatement::Project {
+                project_id: ProjectId::new(project_id),
+                name: None,
+                description: None,
+                owner: None,
+                on_conflict: None,
+            })
+        };
+
+        assert!(matches!(
+            run_as(&mut db, "default", &create_project("a#b")),
+            Err(BackendError::InvalidId(_))
+        ));
+        assert!(!db.exists(&ProjectId::new("a#b")).unwrap());
+
+        run_as(&mut db, "default", &create_project("a")).unwrap();
+        execute(&mut db, "CREATE ISSUE OF KIND bug IN a WITH TITLE 'Crash'").unwrap();
+        assert!(db.exists(&IssueId::new("a#1")).unwrap());
+    }
+
+    #[test]
+    fn test_describe_lists_the_fields_of_an_entity() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        let result = execute(&mut db, "DESCRIBE issues").unwrap();
+        let fields: Vec<FieldDescription> = facet_json::from_str(&result.data.unwrap()).unwrap();
+        let field = |name: &str| fields.iter().find(|field| field.field == name).unwrap();
+
+        assert_eq!(field("title").field_type, "String");
+        assert!(!field("title").optional);
+        assert!(!field("status").optional);
+        assert_eq!(field("priority").field_type, "Priority");
+        assert!(field("priority").optional);
+        assert!(field("labels").field_type.starts_with("List<"));
+
+        assert!(matches!(
+            execute(&mut db, "DESCRIBE meta"),
+            Err(BackendError::NotSupported)
+        ));
+    }
+
+    #[test]
+    fn test_a_failing_statement_rolls_back_its_transaction() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        let users = |db: &mut Database| {
+            let result = execute(db, "SELECT * FROM users").unwrap();
+            let entries = facet_json::from_str::<SelectData<UntypedEntry>>(&result.data.unwrap())
+                .unwrap()
+                .rows;
+            entries
+                .into_iter()
+                .map(|entry| entry.key)
+                .collect::<Vec<_>>()
+        };
+
+        execute(&mut db, "BEGIN").unwrap();
+        execute(&mut db, "CREATE USER alice").unwrap();
+        assert_eq!(users(&mut db), ["alice", "default"]);
+        assert!(execute(&mut db, "CLOSE ISSUE test#1").is_err());
+        assert_eq!(users(&mut db), ["default"]);
+        // The failure ended the transaction
+        assert!(matches!(
+            execute(&mut db, "COMMIT"),
+            Err(BackendError::NoOpenTransaction)
+        ));
+
+        execute(&mut db, "BEGIN").unwrap();
+        execute(&mut db, "CREATE USER bob").unwrap();
+        execute(&mut db, "ROLLBACK").unwrap();
+        assert_eq!(users(&mut db), ["default"]);
+
+        execute(&mut db, "BEGIN").unwrap();
+        execute(&mut db, "CREATE USER carol").unwrap();
+        execute(&mut db, "COMMIT").unwrap();
+        assert_eq!(users(&mut db), ["carol", "default"]);
+
+        execute(&mut db, "BEGIN").unwrap();
+        assert!(matches!(
+            execute(&mut db, "BEGIN"),
+            Err(BackendError::TransactionAlreadyOpen)
+        ));
+    }
+
+    #[test]
+    fn test_transactions_are_only_committed_as_a_whole() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        let committed = |db: &Database, user: &str| {
+            db.db
+                .begin_read()
+                .unwrap()
+                .open_table(TABLE_USERS)
+                .unwrap()
+                .get(user)
+                .unwrap()
+                .is_some()
+        };
+
+        execute(&mut db, "BEGIN").unwrap();
+        execute(&mut db, "CREATE USER alice").unwrap();
+        execute(&mut db, "CREATE PROJECT test OWNER alice").unwrap();
+        // The statements of the transaction see each other, nothing else does yet
+        assert!(db.exists(&UserId::new("alice")).unwrap());
+        assert!(!committed(&db, "alice"));
+        execute(&mut db, "COMMIT").unwrap();
+        assert!(committed(&db, "alice"));
+        assert!(db.exists(&ProjectId::new("test")).unwrap());
+    }
+
+    #[test]
+    fn test_bulk_updates_apply_to_all_matches_or_none() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT test").unwrap();
+        for title in ["First", "Second", "Third"] {
+            execute(
+                &mut db,
+                &format!("CREATE ISSUE OF KIND bug IN test WITH TITLE '{title}'"),
+            )
+            .unwrap();
+        }
+
+        let denied = block_on(db.execute(
+            &AllowFirst::new(2),
+            UserId::new("default"),
+            &issuecraft_ql::parse_query("UPDATE issues SET priority = high").unwrap(),
+            &mut SessionOptions::default(),
+        ));
+        assert!(
+            matches!(denied, Err(BackendError::PermissionDenied(_))),
+            "{denied:?}"
+        );
+        for id in ["test#1", "test#2", "test#3"] {
+            assert!(db.get(&IssueId::new(id)).unwrap().priority.is_none());
+        }
+
+        let result = execute(&mut db, "CLOSE ISSUES WHERE title != 'Third'").unwrap();
+        assert_eq!(result.rows, 2);
+        assert!(db.transaction.is_none());
+        assert!(matches!(
+            db.get(&IssueId::new("test#2")).unwrap().status,
+            IssueStatus::Closed { .. }
+        ));
+    }
+
+    #[test]
+    fn test_bulk_deletes_remove_all_matches_or_none() {
+        let mut db = Database::new(DatabaseType::InMemory).unwrap();
+        execute(&mut db, "CREATE PROJECT a").unwrap();
+        execute(&mut db, "CREATE PROJECT b").unwrap();
+        execute(&mut db, "CREATE ISSUE OF KIND bug IN a WITH TITLE 'First'").unwrap();
+        execute(&mut db, "CREATE ISSUE OF KIND bug IN b WITH TITLE 'Second'").unwrap();
+
+        for query in ["DELETE ALL issues", "DELETE ALL projects"] {
+            let denied = block_on(db.execute(
+                &AllowFirst::new(1),
+                UserId::new("default"),
+                &issuecraft_ql::parse_query(query).unwrap(),
+                &mut SessionOptions::default(),
+            ));
+            assert!(
+                matches!(denied, Err(BackendError::PermissionDenied(_))),
+                "{query}: {denied:?}"
+            );
+            assert!(db.exists(&IssueId::new("a#1")).unwrap(), "{query}");
+            assert!(db.exists(&IssueId::new("b#1")).unwrap(), "{query}");
+            assert!(db.exists(&ProjectId::new("a")).unwrap(), "{query}");
+        }
+    }
+
+    #[test]
+    fn test_read_only_databases_only_run_reads() {
+        let mut db = Database::new(DatabaseType::InMemory)
+            .unwrap()
+            .with_read_only(true);
+
+        assert!(execute(&mut db, "SELECT * FROM users").is_ok());
+        assert!(execute(&mut db, "EXPLAIN CREATE USER alice").is_ok());
+        for query in ["CREATE USER alice", "BEGIN", "REINDEX"] {
+            assert!(matches!(
+                execute(&mut db, query),
+                Err(BackendError::ReadOnly)
+            ));
+        }
     }
 }
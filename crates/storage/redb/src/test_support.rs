@@ -0,0 +1,94 @@
+//! Seeding databases for tests, built with the `test-util` feature.
+
+use issuecraft_core::{AnyUserAuthorizationProvider, BackendError, ExecutionEngine};
+use issuecraft_ql::{CreateStatement, IqlQuery, IssueKind, ProjectId, SessionOptions, UserId};
+
+use crate::Database;
+
+/// Entities to seed a database with. They are created in the order they were added, through
+/// the same queries a client would run.
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    user: UserId,
+    queries: Vec<IqlQuery>,
+}
+
+impl Default for Fixture {
+    fn default() -> Self {
+        Self {
+            user: UserId::new("default"),
+            queries: Vec::new(),
+        }
+    }
+}
+
+impl Fixture {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the queries as `user` rather than `default`, who then also owns the projects
+    /// and authors the issues.
+    #[must_use]
+    pub fn as_user(mut self, user: &str) -> Self {
+        self.user = UserId::new(user);
+        self
+    }
+
+    #[must_use]
+    pub fn with_user(self, username: &str) -> Self {
+        self.with_query(IqlQuery::Create(CreateStatement::User {
+            username: username.to_string(),
+            email: None,
+            name: None,
+        }))
+    }
+
+    #[must_use]
+    pub fn with_project(self, project: &str) -> Self {
+        self.with_query(IqlQuery::Create(CreateStatement::Project {
+            project_id: ProjectId::new(project),
+            name: None,
+            description: None,
+            owner: None,
+            on_conflict: None,
+        }))
+    }
+
+    /// Issues are numbered per project in the order they are added, starting at `<project>#1`.
+    #[must_use]
+    pub fn with_issue(self, project: &str, kind: IssueKind, title: &str) -> Self {
+        self.with_query(IqlQuery::Create(CreateStatement::Issue {
+            project: ProjectId::new(project),
+            kind,
+            title: title.to_string(),
+            description: None,
+            priority: None,
+            assignee: None,
+            labels: Vec::new(),
+        }))
+    }
+
+    /// Adds any other statement, like a comment or an assignment.
+    #[must_use]
+    pub fn with_query(mut self, query: IqlQuery) -> Self {
+        self.queries.push(query);
+        self
+    }
+
+    /// Runs the queries against `db`, stopping at the first one that fails.
+    pub async fn build(self, db: &mut Database) -> Result<(), BackendError> {
+        let mut session = SessionOptions::default();
+        for query in &self.queries {
+            db.execute(
+                &AnyUserAuthorizationProvider,
+                self.user.clone(),
+                query,
+                &mut session,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
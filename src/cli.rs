@@ -1,13 +1,32 @@
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Debug, Parser)]
 #[clap(author, version, about, long_about = None)]
 pub struct Cli {
+    /// Database to use, over the one of the config file
     #[arg(short, long, alias = "db", env = "ISSUECRAFT_DB")]
     pub database: Option<PathBuf>,
-    pub query: String,
+    /// Config file, by default `issuecraft/config.json` in the platform config dir
+    #[arg(long, env = "ISSUECRAFT_CONFIG")]
+    pub config: Option<PathBuf>,
+    /// Query to run; its words may also be given as separate arguments
+    #[arg(required_unless_present = "file", trailing_var_arg = true)]
+    pub query: Vec<String>,
+    /// Runs the semicolon-separated statements of a file instead of a single query
+    #[arg(short, long, conflicts_with = "query")]
+    pub file: Option<PathBuf>,
     #[arg(short, long, default_value = "default", env = "ISSUECRAFT_USER")]
     pub user: String,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Csv,
+    /// Markdown document of the issue a select matched
+    Md,
 }
@@ -3,22 +3,47 @@ use std::path::{Path, PathBuf};
 use facet::Facet;
 
 const DEFAULT_DB_NAME: &str = "issuecraft.redb";
+const CONFIG_FILE_NAME: &str = "config.json";
 
-#[derive(Debug, Facet)]
+/// Settings read from the config file. Everything is optional.
+#[derive(Debug, Default, Facet)]
 pub struct Config {
-    pub db_path: PathBuf,
+    #[facet(default)]
+    pub db_path: Option<PathBuf>,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            db_path: directories::BaseDirs::new()
-                .map_or_else(
-                    || "~/.local/share".into(),
-                    |bd| bd.data_local_dir().to_path_buf(),
-                )
-                .join("issuecraft")
-                .join(DEFAULT_DB_NAME),
+impl Config {
+    /// `issuecraft/config.json` in the platform config dir, e.g. `~/.config` on Linux.
+    pub fn default_path() -> Option<PathBuf> {
+        directories::BaseDirs::new()
+            .map(|bd| bd.config_dir().join("issuecraft").join(CONFIG_FILE_NAME))
+    }
+
+    /// Reads the config at `path`. A missing file is the same as an empty one.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
         }
+        let contents = std::fs::read_to_string(path)?;
+        facet_json::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Invalid config file {}: {e}", path.display()))
+    }
+
+    /// Resolves the database to use. `database` comes from `--database` or `ISSUECRAFT_DB`
+    /// and wins over the config file, which wins over the platform data dir.
+    pub fn db_path(&self, database: Option<PathBuf>) -> PathBuf {
+        database
+            .or_else(|| self.db_path.clone())
+            .unwrap_or_else(default_db_path)
     }
 }
+
+fn default_db_path() -> PathBuf {
+    directories::BaseDirs::new()
+        .map_or_else(
+            || "~/.local/share".into(),
+            |bd| bd.data_local_dir().to_path_buf(),
+        )
+        .join("issuecraft")
+        .join(DEFAULT_DB_NAME)
+}
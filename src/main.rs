@@ -2,24 +2,38 @@
 
 use std::path::{Path, PathBuf};
 
+use anyhow::Context;
 use clap::Parser;
-use issuecraft_core::{AuthorizationProvider, Client, ExecutionEngine, ExecutionResult};
-use issuecraft_ql::{IqlQuery, UserId};
+use issuecraft_core::{
+    AuthorizationProvider, Client, ExecutionEngine, ExecutionResult, SelectData, UntypedEntry,
+};
+use issuecraft_ql::{EntityType, IqlQuery, IssueId, SelectStatement, SessionOptions, UserId};
 
-use crate::{cli::Cli, config::Config};
+use crate::{
+    cli::{Cli, OutputFormat},
+    config::Config,
+};
 
 mod cli;
 mod config;
+mod output;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let Cli {
         database,
+        config,
         query,
+        file,
         user,
+        format,
     } = Cli::parse();
 
-    let db_path = database.unwrap_or_else(|| Config::default().db_path);
+    let config = match config.or_else(Config::default_path) {
+        Some(path) => Config::load(&path)?,
+        None => Config::default(),
+    };
+    let db_path = config.db_path(database);
 
     let db_path = format!("{}", db_path.display());
     let db_path = PathBuf::from(shellexpand::full(&db_path)?.to_string());
@@ -28,12 +42,24 @@ async fn main() -> anyhow::Result<()> {
     }
 
     let authorization_provider = issuecraft_core::SingleUserAuthorizationProvider;
-    let mut db = issuecraft_redb::Database::new(issuecraft_redb::DatabaseType::File(db_path))?;
-    let query = issuecraft_ql::parse_query(&query)?;
-    println!(
-        "{}",
-        run_query(&authorization_provider, &user, &mut db, &query).await?
-    );
+    let mut db =
+        issuecraft_redb::Database::open_checked(issuecraft_redb::DatabaseType::File(db_path))?;
+    let queries = match file {
+        Some(file) => issuecraft_ql::parse_queries(&tokio::fs::read_to_string(file).await?)?,
+        None => vec![issuecraft_ql::parse_query(&query.join(" "))?],
+    };
+    // The statements of a file share their session, so a `SET` applies to the ones after it
+    let mut session = SessionOptions::default();
+    for (index, query) in queries.iter().enumerate() {
+        let result = run_query(&authorization_provider, &user, &mut db, query, &mut session)
+            .await
+            .with_context(|| format!("Statement {} failed", index + 1))?;
+        match format {
+            OutputFormat::Text => println!("{result}"),
+            OutputFormat::Csv => print!("{}", output::to_csv(&result)),
+            OutputFormat::Md => print!("{}", issue_markdown(&db, query, &result)?),
+        }
+    }
 
     Ok(())
 }
@@ -43,8 +69,34 @@ async fn run_query<AP: AuthorizationProvider + Sync, T: ExecutionEngine>(
     user: &str,
     engine: &mut T,
     query: &IqlQuery,
+    session: &mut SessionOptions,
 ) -> anyhow::Result<ExecutionResult> {
     Ok(engine
-        .execute(authorization_provider, UserId::new(user), query)
+        .execute(authorization_provider, UserId::new(user), query, session)
         .await?)
 }
+
+/// Renders the single issue a select matched as Markdown. Statements other than selects are
+/// reported as text.
+fn issue_markdown(
+    db: &issuecraft_redb::Database,
+    query: &IqlQuery,
+    result: &ExecutionResult,
+) -> anyhow::Result<String> {
+    let entries = match query {
+        IqlQuery::Select(SelectStatement {
+            from: EntityType::Issues,
+            ..
+        }) => result
+            .data
+            .as_deref()
+            .and_then(|data| facet_json::from_str::<SelectData<UntypedEntry>>(data).ok())
+            .map(|data| data.rows),
+        IqlQuery::Select(_) => None,
+        _ => return Ok(format!("{result}\n")),
+    };
+    match entries.as_deref() {
+        Some([entry]) => Ok(db.issue_to_markdown(&IssueId::new(&entry.key))?),
+        _ => anyhow::bail!("Markdown output needs a select of all columns matching a single issue"),
+    }
+}
@@ -0,0 +1,30 @@
+use issuecraft_core::ExecutionResult;
+
+/// Renders a result as CSV following RFC 4180.
+///
+/// Rows of a SELECT become one record each, under a header of the selected columns. Any other
+/// result is reported through a single `affected_rows` column.
+pub fn to_csv(result: &ExecutionResult) -> String {
+    let Some((columns, rows)) = result.table() else {
+        return format!("affected_rows\r\n{}\r\n", result.rows);
+    };
+    let mut csv = record(columns.iter().map(String::as_str));
+    for row in &rows {
+        csv.push_str(&record(row.iter().map(String::as_str)));
+    }
+    csv
+}
+
+fn record<'a>(fields: impl Iterator<Item = &'a str>) -> String {
+    let mut record = fields.map(escape).collect::<Vec<_>>().join(",");
+    record.push_str("\r\n");
+    record
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
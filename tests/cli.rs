@@ -0,0 +1,154 @@
+use std::{path::PathBuf, process::Command};
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("issuecraft-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run_file(name: &str, statements: &str, args: &[&str]) -> std::process::Output {
+    let dir = scratch_dir(name);
+    let file = dir.join("migration.iql");
+    std::fs::write(&file, statements).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_issuecraft"))
+        .arg("--database")
+        .arg(dir.join("issuecraft.redb"))
+        .arg("--file")
+        .arg(&file)
+        .args(args)
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    output
+}
+
+#[test]
+fn runs_every_statement_of_a_file() {
+    let output = run_file(
+        "file",
+        "CREATE PROJECT test WITH name 'Test Project';\nSELECT * FROM projects;\n",
+        &[],
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Test Project"), "{stdout}");
+}
+
+#[test]
+fn reports_the_failing_statement() {
+    let output = run_file(
+        "file-error",
+        "CREATE PROJECT test WITH name 'Test Project';\nCREATE PROJECT test WITH name 'Again';\n",
+        &[],
+    );
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Statement 2 failed"), "{stderr}");
+}
+
+#[test]
+fn renders_csv_with_escaped_values() {
+    let output = run_file(
+        "csv",
+        "CREATE PROJECT test WITH name 'Test Project';\n\
+         CREATE ISSUE OF KIND bug IN test WITH TITLE 'a,\"b\"';\n\
+         SELECT title, kind FROM issues;\n",
+        &["--format", "csv"],
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout,
+        "affected_rows\r\n1\r\n\
+         affected_rows\r\n1\r\n\
+         id,title,kind\r\n\
+         test#1,\"a,\"\"b\"\"\",Bug\r\n"
+    );
+}
+
+#[test]
+fn renders_a_single_issue_as_markdown() {
+    let output = run_file(
+        "md",
+        "CREATE PROJECT test WITH name 'Test Project';\n\
+         CREATE ISSUE OF KIND bug IN test WITH TITLE 'Crash' DESCRIPTION 'On start';\n\
+         COMMENT ON ISSUE test#1 WITH 'Me too';\n\
+         SELECT * FROM issues WHERE id = 'test#1';\n",
+        &["--format", "md"],
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("# Crash\n"), "{stdout}");
+    assert!(stdout.contains("| Status | Open |"), "{stdout}");
+    assert!(stdout.contains("Me too"), "{stdout}");
+}
+
+#[test]
+fn resolves_the_database_by_precedence() {
+    let dir = scratch_dir("db-precedence");
+    let config = dir.join("config.json");
+    std::fs::write(
+        &config,
+        format!(
+            r#"{{"db_path": "{}"}}"#,
+            dir.join("from-config.redb").display()
+        ),
+    )
+    .unwrap();
+    let run = |config: &PathBuf, env: Option<&str>, flag: Option<&str>| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_issuecraft"));
+        command
+            .env_remove("ISSUECRAFT_DB")
+            .env("ISSUECRAFT_CONFIG", config)
+            .env("XDG_DATA_HOME", dir.join("data"));
+        if let Some(env) = env {
+            command.env("ISSUECRAFT_DB", dir.join(env));
+        }
+        if let Some(flag) = flag {
+            command.arg("--database").arg(dir.join(flag));
+        }
+        let output = command.arg("SELECT * FROM users").output().unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "{stderr}");
+    };
+
+    run(&dir.join("missing.json"), None, None);
+    assert!(dir.join("data/issuecraft/issuecraft.redb").exists());
+    run(&config, None, None);
+    assert!(dir.join("from-config.redb").exists());
+    run(&config, Some("from-env.redb"), None);
+    assert!(dir.join("from-env.redb").exists());
+    run(&config, Some("ignored.redb"), Some("from-flag.redb"));
+    assert!(dir.join("from-flag.redb").exists());
+    assert!(!dir.join("ignored.redb").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn parses_help_and_a_query_given_as_words() {
+    let output = Command::new(env!("CARGO_BIN_EXE_issuecraft"))
+        .arg("--help")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("--database"));
+
+    let dir = scratch_dir("words");
+    let output = Command::new(env!("CARGO_BIN_EXE_issuecraft"))
+        .arg("--database")
+        .arg(dir.join("issuecraft.redb"))
+        .args(["SELECT", "*", "FROM", "users"])
+        .output()
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+}
@@ -7,6 +7,22 @@ Feature: Query feature
   Scenario: A fresh database always has a default user
     Then a user "default" exists with the name "Default User"
 
+  Scenario: A user exists after creation
+    When I execute the query "CREATE USER alice WITH EMAIL 'alice@example.com' NAME 'Alice'"
+    Then a user "alice" exists with the name "Alice"
+    And the first row of the query "SELECT email FROM users WHERE id = 'alice'" has "email" set to "alice@example.com"
+
+  Scenario: A user without a name is named after its id
+    When I execute the query "CREATE USER bob"
+    Then a user "bob" exists with the name "bob"
+
+  Scenario: The schema version can be read from the meta table
+    Then the first row of the query "SELECT * FROM meta WHERE id = 'schema_version'" has "value" set to "1"
+
+  Scenario: Meta entries are not listed as users
+    Then the query "SELECT * FROM users" returns 1 rows
+    And the query "SELECT * FROM users WHERE id = 'schema_version'" returns 0 rows
+
   Scenario: A project exists after creation
     When I create a project "test" with the display name "Test Project"
     Then a project "test" exists with the name "Test Project"
@@ -16,6 +32,28 @@ Feature: Query feature
     And I update the display name of the project "test" to "Renamed Project"
     Then a project "test" exists with the name "Renamed Project"
 
+  Scenario: An unset optional project field can be updated
+    When I create a project "test" with the display name "Test Project"
+    And I execute the query "UPDATE project test SET description = 'A test project'"
+    Then a project "test" exists with the name "Test Project"
+    And the first row of the query "SELECT description FROM projects" has "description" set to "A test project"
+
+  Scenario: An issue is created with all fields
+    When I create a project "test" with the display name "Test Project"
+    And I execute the query "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Crash' DESCRIPTION 'It crashes' PRIORITY high ASSIGNEE default LABELS ('ui', 'regression')"
+    Then an issue "test#1" exists with the kind "bug" and title "Crash"
+    And the first row of the query "SELECT description FROM issues" has "description" set to "It crashes"
+    And the first row of the query "SELECT priority FROM issues" has "priority" set to "High"
+    And the first row of the query "SELECT assignee FROM issues" has "assignee" set to "default"
+    And the query "SELECT * FROM issues WHERE labels HAS 'regression'" returns 1 rows
+
+  Scenario: An unset optional issue field can be updated
+    When I create a project "test" with the display name "Test Project"
+    And I create an issue of kind "bug" with the title "Test Bug" in project "test"
+    And I execute the query "UPDATE issue test#1 SET priority = high"
+    Then an issue "test#1" exists with the kind "bug" and title "Test Bug"
+    And the first row of the query "SELECT priority FROM issues" has "priority" set to "High"
+
   Rule: An issue can only be created for a project
 
     Background:
@@ -32,6 +70,7 @@ Feature: Query feature
         | bug         | Test Bug         | bug         | Test Bug 2         |
         | improvement | Test Improvement | improvement | Test Improvement 2 |
         | task        | Test Task        | bug         | Test Bug 4         |
+        | bug         | Test Bug         | epic        | Test Epic          |
 
   Rule: A comment can only be created for an issue
 
@@ -39,7 +78,351 @@ Feature: Query feature
         When I create a project "test" with the display name "Test Project"
         And I create an issue of kind "bug" with the title "Test Bug" in project "test"
 
-    Scenario: A comment is created with an id starting with 'C'
+    Scenario: A comment is created with an id numbered within its issue
         When I comment "Test Comment" on issue "test#1"
         Then a comment exists with author "default", issue id "test#1" and content "Test Comment"
+        And the query "SELECT * FROM comments WHERE id = 'test#1/c1' AND number = 1" returns 1 rows
+
+    Scenario: Comment numbers are not reused after a deletion
+        When I comment "First" on issue "test#1"
+        And I comment "Second" on issue "test#1"
+        And I comment "Third" on issue "test#1"
+        And I execute the query "DELETE COMMENT test#1/c3"
+        And I comment "Fourth" on issue "test#1"
+        Then the query "SELECT * FROM comments WHERE id = 'test#1/c1'" returns 1 rows
+        And the query "SELECT * FROM comments WHERE id = 'test#1/c2'" returns 1 rows
+        And the query "SELECT * FROM comments WHERE id = 'test#1/c3'" returns 0 rows
+        And the first row of the query "SELECT content FROM comments WHERE id = 'test#1/c4'" has "content" set to "Fourth"
+
+    Scenario: The comments of an issue can be listed, edited and deleted
+        When I create an issue of kind "bug" with the title "Other Bug" in project "test"
+        And I comment "First" on issue "test#1"
+        And I comment "Second" on issue "test#1"
+        And I comment "Elsewhere" on issue "test#2"
+        Then the query "SELECT * FROM comments WHERE issue = 'test#1'" returns 2 rows
+        When I execute the query "UPDATE comment test#1/c1 SET content = 'Edited'"
+        Then the first row of the query "SELECT content FROM comments WHERE id = 'test#1/c1'" has "content" set to "Edited"
+        When I execute the query "DELETE COMMENT test#1/c2"
+        Then the query "SELECT * FROM comments WHERE issue = 'test#1'" returns 1 rows
+        And the query "SELECT * FROM comments WHERE issue = 'test#2'" returns 1 rows
+
+    Scenario: A comment can be written on behalf of another user
+        When I execute the query "CREATE USER alice"
+        And I execute the query "COMMENT ON ISSUE test#1 WITH 'Test Comment' AUTHOR alice"
+        Then a comment exists with author "alice", issue id "test#1" and content "Test Comment"
+
+    Scenario: A comment without an author is written by the acting user
+        When I execute the query "COMMENT ON ISSUE test#1 WITH 'Test Comment'"
+        Then a comment exists with author "default", issue id "test#1" and content "Test Comment"
+
+    Scenario: A user can react to a comment
+        When I comment "Test Comment" on issue "test#1"
+        And I react with "+1" to the comment on issue "test#1"
+        Then the comment on issue "test#1" has 1 "+1" reactions
+
+    Scenario: Reacting twice with the same reaction counts once
+        When I comment "Test Comment" on issue "test#1"
+        And I react with "+1" to the comment on issue "test#1"
+        And I react with "+1" to the comment on issue "test#1"
+        Then the comment on issue "test#1" has 1 "+1" reactions
+
+    Scenario: A reaction can be removed again
+        When I comment "Test Comment" on issue "test#1"
+        And I react with "+1" to the comment on issue "test#1"
+        And I remove my "+1" reaction from the comment on issue "test#1"
+        Then the comment on issue "test#1" has 0 "+1" reactions
+
+
+  Rule: An issue is closed with a reason
+
+    Background:
+        When I create a project "test" with the display name "Test Project"
+        And I create an issue of kind "bug" with the title "Test Bug" in project "test"
+        And I create an issue of kind "bug" with the title "Test Bug 2" in project "test"
+
+    Scenario: An issue closed without a reason is done
+        When I execute the query "CLOSE issue test#1"
+        Then the issue "test#1" is closed with the reason "DONE"
+
+    Scenario: An issue closed as won't fix keeps the explanation
+        When I execute the query "CLOSE issue test#1 WITH WONTFIX 'not planned'"
+        Then the issue "test#1" is closed with the reason "WONTFIX 'not planned'"
+
+    Scenario: An issue closed as duplicate keeps the original issue
+        When I execute the query "CLOSE issue test#2 WITH DUPLICATE OF test#1"
+        Then the issue "test#2" is closed with the reason "DUPLICATE OF test#1"
+
+    Scenario: An issue can be closed as duplicate without naming the original
+        When I execute the query "CLOSE issue test#2 WITH DUPLICATE"
+        Then the issue "test#2" is closed with the reason "DUPLICATE"
+
+    Scenario: Creating and closing an issue report the rows they affected
+        When I create an issue of kind "bug" with the title "Test Bug 3" in project "test"
+        Then the result affected 1 rows
+        When I execute the query "CLOSE issue test#3"
+        Then the result affected 1 rows
+        When I execute the query "EXPLAIN CLOSE ISSUES WHERE OPEN"
+        Then the result affected 2 rows
+        And the result info contains "Dry run"
+
+    Scenario: Reopening an open issue affects no rows
+        When I execute the query "CLOSE issue test#1"
+        Then the result affected 1 rows
+        When I execute the query "REOPEN issue test#2"
+        Then the result affected 0 rows
+        When I execute the query "SELECT * FROM issues WHERE OPEN"
+        Then the result returns 1 rows
+
+
+  Rule: An issue kind can require fields on creation
+
+    Background:
+        Given issues of kind "bug" require a "description"
+        When I create a project "test" with the display name "Test Project"
+
+    Scenario: A bug without the required field is rejected
+        Then the query "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Crash'" fails with a missing "description"
+        And the query "SELECT * FROM issues" returns 0 rows
+
+    Scenario: A bug with the required field is created
+        When I execute the query "CREATE ISSUE OF KIND bug IN test WITH TITLE 'Crash' DESCRIPTION 'Open the app'"
+        Then an issue "test#1" exists with the kind "bug" and title "Crash"
+
+    Scenario: A task without extra requirements is created
+        When I create an issue of kind "task" with the title "Test Task" in project "test"
+        Then an issue "test#1" exists with the kind "task" and title "Test Task"
+
+
+  Rule: Project display names can be required to be unique
+
+    Background:
+        When I create a project "alpha" with the display name "Alpha"
+        And I create a project "beta" with the display name "Beta"
+
+    Scenario: Duplicate display names are allowed by default
+        When I execute the query "CREATE PROJECT gamma WITH NAME 'Alpha'"
+        Then the query "SELECT * FROM projects WHERE name = 'Alpha'" returns 2 rows
+
+    Scenario: Creating a project with a taken display name is rejected
+        Given project display names must be unique
+        Then the query "CREATE PROJECT gamma WITH NAME 'Alpha'" fails with a duplicate display name "Alpha"
+        And the query "SELECT * FROM projects" returns 2 rows
+
+    Scenario: Renaming a project to a taken display name is rejected
+        Given project display names must be unique
+        Then the query "UPDATE project beta SET name = 'Alpha'" fails with a duplicate display name "Alpha"
+        And the query "SELECT * FROM projects WHERE name = 'Beta'" returns 1 rows
+
+    Scenario: A project keeps its own display name when updated
+        Given project display names must be unique
+        When I execute the query "UPDATE project alpha SET name = 'Alpha', description = 'First'"
+        Then the query "SELECT * FROM projects WHERE name = 'Alpha'" returns 1 rows
+
+
+  Rule: A database can be exported and imported
+
+    Background:
+        When I create a project "test" with the display name "Test Project"
+        And I create an issue of kind "bug" with the title "Test Bug" in project "test"
+        And I create an issue of kind "task" with the title "Test Task" in project "test"
+        And I comment "Test Comment" on issue "test#2"
+        And I export the database
+
+    Scenario: All entities are available after importing into a fresh database
+        When I import the export into a fresh database
+        Then a user "default" exists with the name "Default User"
+        And a project "test" exists with the name "Test Project"
+        And an issue "test#1" exists with the kind "bug" and title "Test Bug"
+        And an issue "test#2" exists with the kind "task" and title "Test Task"
+        And a comment exists with author "default", issue id "test#2" and content "Test Comment"
+
+    Scenario: Issues created after an import continue the sequence
+        When I import the export into a fresh database
+        And I create an issue of kind "improvement" with the title "Test Improvement" in project "test"
+        Then an issue "test#3" exists with the kind "improvement" and title "Test Improvement"
+
+    Scenario: Importing conflicting entities requires a merge
+        When I update the display name of the project "test" to "Renamed Project"
+        Then importing the export into the database fails
+        When I merge the export into the database
+        Then a project "test" exists with the name "Test Project"
+
+    Scenario: Importing entities that already exist requires a merge even if they are unchanged
+        Then importing the export into the database fails
+        When I merge the export into the database
+        Then the result affected 5 rows
+        And a project "test" exists with the name "Test Project"
+
+  Rule: Deleting reports every removed entity
+
+    Background:
+        When I create a project "test" with the display name "Test Project"
+        And I create an issue of kind "bug" with the title "Test Bug" in project "test"
+        And I create an issue of kind "task" with the title "Test Task" in project "test"
+
+    Scenario: Deleting a project also deletes its issues
+        Then the query "DELETE project test" deletes "test, test#1, test#2"
+        And the query "SELECT * FROM issues" returns 0 rows
+
+    Scenario: Explaining a delete reports the cascade without deleting
+        When I comment "Test Comment" on issue "test#1"
+        Then the query "EXPLAIN DELETE project test" affects 4 rows
+        And a project "test" exists with the name "Test Project"
+        And an issue "test#1" exists with the kind "bug" and title "Test Bug"
+        And an issue "test#2" exists with the kind "task" and title "Test Task"
+        And a comment exists with author "default", issue id "test#1" and content "Test Comment"
+
+    Scenario: Issues matching a filter are deleted with their comments
+        When I comment "Test Comment" on issue "test#1"
+        Then the query "DELETE issues WHERE title = 'Test Bug'" affects 2 rows
+        And the query "SELECT * FROM issues" returns 1 rows
+        And the query "SELECT * FROM comments" returns 0 rows
+        And a project "test" exists with the name "Test Project"
+
+    Scenario: Deleting every entity of a kind has to be explicit
+        Then the query "DELETE ALL issues" affects 2 rows
+        And the query "SELECT * FROM issues" returns 0 rows
+
+  Rule: Entities can be updated in bulk
+
+    Background:
+        When I create a project "test" with the display name "Test Project"
+        And I create an issue of kind "bug" with the title "Test Bug" in project "test"
+        And I create an issue of kind "bug" with the title "Test Bug 2" in project "test"
+        And I create an issue of kind "task" with the title "Test Task" in project "test"
+
+    Scenario: Only matching issues are updated
+        Then the query "UPDATE issues SET priority = low WHERE title LIKE 'Test Bug%'" affects 2 rows
+        And the first row of the query "SELECT priority FROM issues WHERE id = 'test#2'" has "priority" set to "Low"
+        And the query "SELECT * FROM issues WHERE priority IS NULL" returns 1 rows
+        And the first row of the query "SELECT title FROM issues WHERE priority IS NULL" has "title" set to "Test Task"
+
+  Rule: Issues can be counted per group
+
+    Background:
+        When I create a project "test" with the display name "Test Project"
+        And I create an issue of kind "bug" with the title "Test Bug" in project "test"
+        And I create an issue of kind "bug" with the title "Test Bug 2" in project "test"
+        And I create an issue of kind "task" with the title "Test Task" in project "test"
+
+    Scenario: Issues are grouped by status
+        When I execute the query "CLOSE issue test#3"
+        Then grouping issues by "status" counts 2 for "Open"
+
+    Scenario: Closed issues are grouped together regardless of the close reason
+        When I create an issue of kind "task" with the title "Test Task 2" in project "test"
+        And I execute the query "UPDATE issue test#2 SET status = 'Assigned'"
+        And I execute the query "CLOSE issue test#3 WITH done"
+        And I execute the query "CLOSE issue test#4 WITH wontfix"
+        Then grouping issues by "status" counts 1 for "Open"
+        And grouping issues by "status" counts 1 for "Assigned"
+        And grouping issues by "status" counts 2 for "Closed"
+
+    Scenario: Open and closed issues are selected through a shorthand
+        When I execute the query "CLOSE issue test#3 WITH wontfix"
+        Then the query "SELECT * FROM issues WHERE OPEN" returns 2 rows
+        And the query "SELECT * FROM issues WHERE CLOSED" returns 1 rows
+        And the first row of the query "SELECT title FROM issues WHERE CLOSED" has "title" set to "Test Task"
+
+    Scenario: Issues are selected and ordered by kind
+        Then the query "SELECT * FROM issues WHERE kind = bug" returns 2 rows
+        And the query "SELECT * FROM issues WHERE kind IN (task, epic)" returns 1 rows
+        And grouping issues by "kind" counts 2 for "Bug"
+        And the first row of the query "SELECT kind, title FROM issues ORDER BY kind DESC" has "title" set to "Test Task"
+        And the first row of the query "SELECT kind FROM issues ORDER BY kind" has "kind" set to "Bug"
+
+    Scenario: Issues are grouped by assignee
+        When I execute the query "CREATE USER alice"
+        And I execute the query "ASSIGN issue test#1 TO alice"
+        Then grouping issues by "assignee" counts 1 for "alice"
+        And grouping issues by "assignee" counts 2 for "default"
+
+    Scenario: Issues without the grouped field are counted under null
+        When I execute the query "UPDATE issue test#1 SET priority = high"
+        Then grouping issues by "priority" counts 2 for "null"
+
+    Scenario: Groups can be ordered by their count and limited
+        When I create an issue of kind "task" with the title "Test Task 2" in project "test"
+        And I create an issue of kind "task" with the title "Test Task 3" in project "test"
+        And I create an issue of kind "task" with the title "Test Task 4" in project "test"
+        And I create an issue of kind "task" with the title "Test Task 5" in project "test"
+        And I execute the query "CREATE USER alice"
+        And I execute the query "CREATE USER bob"
+        And I execute the query "CREATE USER carol"
+        And I execute the query "CREATE USER dave"
+        And I execute the query "CREATE USER erin"
+        And I execute the query "CREATE USER frank"
+        And I execute the query "ASSIGN issue test#1 TO alice"
+        And I execute the query "ASSIGN issue test#2 TO alice"
+        And I execute the query "ASSIGN issue test#3 TO bob"
+        And I execute the query "ASSIGN issue test#4 TO carol"
+        And I execute the query "ASSIGN issue test#5 TO dave"
+        And I execute the query "ASSIGN issue test#6 TO erin"
+        And I execute the query "ASSIGN issue test#7 TO frank"
+        Then the query "SELECT assignee, COUNT(*) AS n FROM issues GROUP BY assignee ORDER BY n DESC LIMIT 5" returns 5 rows
+        And the first group of the query "SELECT assignee, COUNT(*) AS n FROM issues GROUP BY assignee ORDER BY n DESC LIMIT 5" has "assignee" set to "alice"
+
+  Rule: Filters that can never match return no rows
+
+    Background:
+        When I create a project "test" with the display name "Test Project"
+        And I create an issue of kind "bug" with the title "Test Bug" in project "test"
+
+    Scenario: Contradicting equalities match nothing
+        Then the query "SELECT * FROM issues WHERE title = 'Test Bug'" returns 1 rows
+        And the query "SELECT * FROM issues WHERE title = 'Test Bug' AND title = 'Other'" returns 0 rows
+
+    Scenario: An empty IN list matches nothing
+        Then the query "SELECT * FROM issues WHERE title IN ()" returns 0 rows
+
+    Scenario: IN compares against the id
+        Then the query "SELECT * FROM issues WHERE id IN ('test#1', 'test#9')" returns 1 rows
+
+  Rule: Selected columns can be renamed
+
+    Background:
+        When I create a project "test" with the display name "Test Project"
+        And I create an issue of kind "bug" with the title "Test Bug" in project "test"
+
+    Scenario: An aliased column is rendered under its alias
+        Then the first row of the query "SELECT title AS summary, assignee AS owner FROM issues" has "summary" set to "Test Bug"
+        And the first row of the query "SELECT title AS summary, assignee AS owner FROM issues" has "owner" set to "default"
+        And the first row of the query "SELECT title AS summary FROM issues" has no "title"
+
+    Scenario: A column without an alias keeps its name
+        Then the first row of the query "SELECT title, assignee FROM issues" has "title" set to "Test Bug"
+
+  Rule: Results of any query can be displayed
+
+    Background:
+        When I create a project "test" with the display name "Test Project"
+        And I create an issue of kind "bug" with the title "Test Bug" in project "test"
+        And I comment "Test Comment" on issue "test#1"
+
+    Scenario Outline: Displaying a SELECT result does not panic
+        Then the result of the query "<query>" can be displayed
+
+    Examples:
+        | query                                                |
+        | SELECT * FROM users                                  |
+        | SELECT * FROM projects                               |
+        | SELECT * FROM issues                                 |
+        | SELECT * FROM comments                               |
+        | SELECT title AS summary FROM issues                  |
+        | SELECT status, COUNT(*) FROM issues GROUP BY status  |
+
+  Rule: Issues can be joined with their project
+
+    Background:
+        When I create a project "test" with the display name "Test Project"
+        And I create an issue of kind "bug" with the title "Test Bug" in project "test"
+
+    Scenario: The project name appears alongside the issue
+        Then the first row of the query "SELECT issues.title, projects.name FROM issues JOIN projects ON issues.project = projects.id" has "projects.name" set to "Test Project"
+        And the first row of the query "SELECT i.title, p.name AS project_name FROM issues i JOIN projects p ON p.id = i.project" has "project_name" set to "Test Project"
 
+    Scenario: Issues without a matching project are left out
+        When I execute the query "UPDATE issue test#1 SET project = 'missing'"
+        And I create a project "other" with the display name "Other Project"
+        And I create an issue of kind "bug" with the title "Other Bug" in project "other"
+        Then the query "SELECT * FROM issues JOIN projects ON issues.project = projects.id" returns 1 rows
@@ -2,7 +2,11 @@ use std::fmt::Debug;
 
 use anyhow::Result;
 use cucumber::{World, given, then, when};
-use issuecraft_core::{Entry, ExecutionEngine, ExecutionResult, SingleUserAuthorizationProvider};
+use facet_value::Value as FacetValue;
+use issuecraft_core::{
+    BackendError, Entry, ExecutionEngine, ExecutionResult, IssueField, IssueStatus, IssueTemplates,
+    SelectData, SingleUserAuthorizationProvider, UntypedEntry,
+};
 use issuecraft_ql::*;
 use issuecraft_redb::{Database, DatabaseType};
 
@@ -10,6 +14,11 @@ use issuecraft_redb::{Database, DatabaseType};
 pub struct IssuecraftWorld {
     pub authorization_provider: Option<SingleUserAuthorizationProvider>,
     pub engine: Option<Database>,
+    pub dump: Option<String>,
+    /// The options set by the queries run so far.
+    pub session: SessionOptions,
+    /// The result of the last query a `When` step ran.
+    pub last_result: Option<ExecutionResult>,
 }
 
 impl Debug for IssuecraftWorld {
@@ -29,9 +38,33 @@ impl IssuecraftWorld {
                 self.authorization_provider.as_ref().unwrap(),
                 UserId::new("default"),
                 &query,
+                &mut self.session,
             )
             .await?)
     }
+
+    /// Executes `query` and keeps its result for the `the result ...` steps.
+    async fn run_query(&mut self, query: &str) -> Result<ExecutionResult> {
+        let result = self.execute(query).await?;
+        self.last_result = Some(result.clone());
+        Ok(result)
+    }
+
+    fn last_result(&self) -> &ExecutionResult {
+        self.last_result
+            .as_ref()
+            .expect("No query was executed before asserting on its result")
+    }
+
+    async fn comment_on(&mut self, issue_id: &str) -> Result<Entry<CommentId>> {
+        let query = format!("SELECT * FROM comments WHERE issue = '{issue_id}'");
+        let result = self.execute(&query).await?;
+        let mut comments =
+            facet_json::from_str::<SelectData<Entry<CommentId>>>(result.data.as_ref().unwrap())?
+                .rows;
+        assert_eq!(comments.len(), 1);
+        Ok(comments.remove(0))
+    }
 }
 
 impl Default for IssuecraftWorld {
@@ -39,6 +72,9 @@ impl Default for IssuecraftWorld {
         Self {
             authorization_provider: None,
             engine: None,
+            dump: None,
+            session: SessionOptions::default(),
+            last_result: None,
         }
     }
 }
@@ -49,6 +85,33 @@ fn fresh_db(world: &mut IssuecraftWorld) -> Result<()> {
     Ok(())
 }
 
+#[given(expr = "issues of kind {string} require a {string}")]
+fn issues_require_field(world: &mut IssuecraftWorld, kind: String, field: String) {
+    let kind = match kind.to_lowercase().as_str() {
+        "epic" => IssueKind::Epic,
+        "improvement" => IssueKind::Improvement,
+        "bug" => IssueKind::Bug,
+        "task" => IssueKind::Task,
+        _ => panic!("Invalid issue kind"),
+    };
+    let field = match field.as_str() {
+        "description" => IssueField::Description,
+        "priority" => IssueField::Priority,
+        "assignee" => IssueField::Assignee,
+        "labels" => IssueField::Labels,
+        _ => panic!("Invalid issue field"),
+    };
+    let engine = world.engine.take().unwrap();
+    world.engine =
+        Some(engine.with_issue_templates(IssueTemplates::default().require(kind, field)));
+}
+
+#[given("project display names must be unique")]
+fn unique_project_names(world: &mut IssuecraftWorld) {
+    let engine = world.engine.take().unwrap();
+    world.engine = Some(engine.with_unique_project_names(true));
+}
+
 #[given("a single user authorization provider")]
 fn single_user_authorization_provider(world: &mut IssuecraftWorld) {
     world.authorization_provider = Some(SingleUserAuthorizationProvider);
@@ -56,7 +119,7 @@ fn single_user_authorization_provider(world: &mut IssuecraftWorld) {
 
 #[when(expr = "I execute the query {string}")]
 async fn execute_query(world: &mut IssuecraftWorld, query: String) -> Result<ExecutionResult> {
-    Ok(world.execute(&query).await?)
+    world.run_query(&query).await
 }
 
 #[when(expr = "I create a project {string} with the display name {string}")]
@@ -66,7 +129,7 @@ async fn create_project(
     display_name: String,
 ) -> Result<ExecutionResult> {
     let query = format!("CREATE PROJECT {project_id} WITH name '{display_name}'");
-    Ok(world.execute(&query).await?)
+    world.run_query(&query).await
 }
 
 #[when(expr = "I create an issue of kind {string} with the title {string} in project {string}")]
@@ -77,7 +140,7 @@ async fn create_issue(
     project_id: String,
 ) -> Result<ExecutionResult> {
     let query = format!("CREATE ISSUE OF KIND {kind} IN {project_id} WITH TITLE '{title}'");
-    Ok(world.execute(&query).await?)
+    world.run_query(&query).await
 }
 
 #[when(expr = "I comment {string} on issue {string}")]
@@ -87,7 +150,29 @@ async fn create_comment(
     issue_id: String,
 ) -> Result<ExecutionResult> {
     let query = format!("COMMENT ON ISSUE {issue_id} WITH '{comment}'");
-    Ok(world.execute(&query).await?)
+    world.run_query(&query).await
+}
+
+#[when(expr = "I react with {string} to the comment on issue {string}")]
+async fn react_to_comment(
+    world: &mut IssuecraftWorld,
+    reaction: String,
+    issue_id: String,
+) -> Result<ExecutionResult> {
+    let comment = world.comment_on(&issue_id).await?;
+    let query = format!("REACT TO COMMENT {} WITH '{reaction}'", &*comment.key);
+    world.run_query(&query).await
+}
+
+#[when(expr = "I remove my {string} reaction from the comment on issue {string}")]
+async fn unreact_to_comment(
+    world: &mut IssuecraftWorld,
+    reaction: String,
+    issue_id: String,
+) -> Result<ExecutionResult> {
+    let comment = world.comment_on(&issue_id).await?;
+    let query = format!("UNREACT TO COMMENT {} WITH '{reaction}'", &*comment.key);
+    world.run_query(&query).await
 }
 
 #[when(expr = "I update the display name of the project {string} to {string}")]
@@ -97,14 +182,48 @@ async fn update_project(
     display_name: String,
 ) -> Result<ExecutionResult> {
     let query = format!("UPDATE PROJECT {project_id} SET name = '{display_name}'");
-    Ok(world.execute(&query).await?)
+    world.run_query(&query).await
+}
+
+#[when("I export the database")]
+fn export_database(world: &mut IssuecraftWorld) -> Result<()> {
+    world.dump = Some(world.engine.as_ref().unwrap().export()?);
+    Ok(())
+}
+
+#[when("I import the export into a fresh database")]
+fn import_into_fresh_database(world: &mut IssuecraftWorld) -> Result<()> {
+    let mut engine = Database::new(DatabaseType::InMemory)?;
+    engine.import(world.dump.as_ref().unwrap(), false)?;
+    world.engine = Some(engine);
+    Ok(())
+}
+
+#[when("I merge the export into the database")]
+async fn merge_into_database(world: &mut IssuecraftWorld) -> Result<ExecutionResult> {
+    let query = ImportStatement {
+        dump: world.dump.clone().unwrap(),
+        merge: true,
+    };
+    world.run_query(&query.to_string()).await
+}
+
+#[then("importing the export into the database fails")]
+fn import_into_database_fails(world: &mut IssuecraftWorld) {
+    let result = world
+        .engine
+        .as_mut()
+        .unwrap()
+        .import(world.dump.as_ref().unwrap(), false);
+    assert!(result.is_err());
 }
 
 #[then(expr = "a user {string} exists with the name {string}")]
 async fn user_exists(world: &mut IssuecraftWorld, user_id: String, name: String) -> Result<()> {
     let query = format!("SELECT * FROM users WHERE id = '{user_id}'");
     let result = world.execute(&query).await?;
-    let result: Vec<Entry<UserId>> = facet_json::from_str(result.data.as_ref().unwrap())?;
+    let result =
+        facet_json::from_str::<SelectData<Entry<UserId>>>(result.data.as_ref().unwrap())?.rows;
     assert_eq!(result.len(), 1);
     let user = result.first().unwrap();
     assert_eq!(user.value.name, name);
@@ -119,7 +238,8 @@ async fn project_exists(
 ) -> Result<()> {
     let query = format!("SELECT * FROM projects WHERE id = '{project_id}'");
     let result = world.execute(&query).await?;
-    let result: Vec<Entry<ProjectId>> = facet_json::from_str(result.data.as_ref().unwrap())?;
+    let result =
+        facet_json::from_str::<SelectData<Entry<ProjectId>>>(result.data.as_ref().unwrap())?.rows;
     assert_eq!(result.len(), 1);
     let user = result.first().unwrap();
     assert_eq!(user.value.name, Some(name));
@@ -135,7 +255,8 @@ async fn issue_exists(
 ) -> Result<()> {
     let query = format!("SELECT * FROM issues WHERE id = '{issue_id}'");
     let result = world.execute(&query).await?;
-    let result: Vec<Entry<IssueId>> = facet_json::from_str(result.data.as_ref().unwrap())?;
+    let result =
+        facet_json::from_str::<SelectData<Entry<IssueId>>>(result.data.as_ref().unwrap())?.rows;
     assert_eq!(result.len(), 1);
     let issue = result.first().unwrap();
     assert_eq!(issue.value.title, title);
@@ -152,6 +273,23 @@ async fn issue_exists(
     Ok(())
 }
 
+#[then(expr = "the issue {string} is closed with the reason {string}")]
+async fn issue_closed_with_reason(
+    world: &mut IssuecraftWorld,
+    issue_id: String,
+    reason: String,
+) -> Result<()> {
+    let query = format!("SELECT * FROM issues WHERE id = '{issue_id}'");
+    let result = world.execute(&query).await?;
+    let result =
+        facet_json::from_str::<SelectData<Entry<IssueId>>>(result.data.as_ref().unwrap())?.rows;
+    match &result.first().unwrap().value.status {
+        IssueStatus::Closed { reason: closed } => assert_eq!(closed.to_string(), reason),
+        status => panic!("Expected a closed issue, got {status:?}"),
+    }
+    Ok(())
+}
+
 #[then(expr = "a comment exists with author {string}, issue id {string} and content {string}")]
 async fn comment_exists(
     world: &mut IssuecraftWorld,
@@ -161,7 +299,8 @@ async fn comment_exists(
 ) -> Result<()> {
     let query = format!("SELECT * FROM comments WHERE issue = '{issue_id}'");
     let result = world.execute(&query).await?;
-    let result: Vec<Entry<CommentId>> = facet_json::from_str(result.data.as_ref().unwrap())?;
+    let result =
+        facet_json::from_str::<SelectData<Entry<CommentId>>>(result.data.as_ref().unwrap())?.rows;
     assert_eq!(result.len(), 1);
     let user = result.first().unwrap();
     assert_eq!(user.value.author, UserId::new(&author));
@@ -169,6 +308,203 @@ async fn comment_exists(
     Ok(())
 }
 
+#[then(expr = "the comment on issue {string} has {int} {string} reactions")]
+async fn comment_has_reactions(
+    world: &mut IssuecraftWorld,
+    issue_id: String,
+    count: usize,
+    reaction: String,
+) -> Result<()> {
+    let comment = world.comment_on(&issue_id).await?;
+    let users = comment.value.reactions.get(&reaction).map_or(0, Vec::len);
+    assert_eq!(users, count);
+    Ok(())
+}
+
+#[then(expr = "the result of the query {string} can be displayed")]
+async fn result_can_be_displayed(world: &mut IssuecraftWorld, query: String) -> Result<()> {
+    let result = world.execute(&query).await?;
+    let (columns, rows) = result.table().expect("SELECT results are shown as a table");
+    assert!(!rows.is_empty());
+    let displayed = result.to_string();
+    let lines = displayed.lines().collect::<Vec<_>>();
+    let separator = lines
+        .iter()
+        .position(|line| line.starts_with('-') && line.chars().all(|c| "-+".contains(c)))
+        .expect("The table has a separator below its header");
+    let header = lines[separator - 1];
+    for column in &columns {
+        assert!(
+            header.contains(column.as_str()),
+            "{column} missing from {header}"
+        );
+    }
+    assert_eq!(lines.len() - separator - 1, rows.len());
+    Ok(())
+}
+
+#[then(expr = "the query {string} deletes {string}")]
+async fn query_deletes(world: &mut IssuecraftWorld, query: String, ids: String) -> Result<()> {
+    let result = world.execute(&query).await?;
+    let deleted: Vec<String> = facet_json::from_str(result.data.as_ref().unwrap())?;
+    let ids = ids.split(", ").collect::<Vec<_>>();
+    assert_eq!(result.rows, ids.len() as u64);
+    for id in ids {
+        assert!(
+            deleted.iter().any(|deleted| deleted == id),
+            "{id} was not deleted"
+        );
+    }
+    Ok(())
+}
+
+#[then(expr = "the query {string} fails with a missing {string}")]
+async fn query_fails_with_missing_field(world: &mut IssuecraftWorld, query: String, field: String) {
+    let error = world.execute(&query).await.unwrap_err();
+    assert!(
+        matches!(
+            error.downcast_ref::<BackendError>(),
+            Some(BackendError::MissingRequiredField { field: missing, .. }) if missing.to_string() == field
+        ),
+        "{error}"
+    );
+}
+
+#[then(expr = "the query {string} fails with a duplicate display name {string}")]
+async fn query_fails_with_duplicate_display_name(
+    world: &mut IssuecraftWorld,
+    query: String,
+    name: String,
+) {
+    let error = world.execute(&query).await.unwrap_err();
+    assert!(
+        matches!(
+            error.downcast_ref::<BackendError>(),
+            Some(BackendError::DuplicateDisplayName(duplicate)) if *duplicate == name
+        ),
+        "{error}"
+    );
+}
+
+#[then(expr = "the result affected {int} rows")]
+fn result_affected_rows(world: &mut IssuecraftWorld, rows: u64) {
+    assert_eq!(world.last_result().rows, rows);
+}
+
+#[then(expr = "the result returns {int} rows")]
+fn result_returns_rows(world: &mut IssuecraftWorld, rows: usize) -> Result<()> {
+    let data: SelectData<FacetValue> =
+        facet_json::from_str(world.last_result().data.as_ref().unwrap())?;
+    assert_eq!(data.rows.len(), rows);
+    Ok(())
+}
+
+#[then(expr = "the result info contains {string}")]
+fn result_info_contains(world: &mut IssuecraftWorld, text: String) {
+    let info = world.last_result().info.as_deref().unwrap_or_default();
+    assert!(info.contains(&text), "{info:?} does not contain {text:?}");
+}
+
+#[then(expr = "the query {string} affects {int} rows")]
+async fn query_affects_rows(world: &mut IssuecraftWorld, query: String, rows: u64) -> Result<()> {
+    let result = world.execute(&query).await?;
+    assert_eq!(result.rows, rows);
+    Ok(())
+}
+
+#[then(expr = "the query {string} returns {int} rows")]
+async fn query_returns_rows(world: &mut IssuecraftWorld, query: String, rows: usize) -> Result<()> {
+    let result = world.execute(&query).await?;
+    let data: SelectData<FacetValue> = facet_json::from_str(result.data.as_ref().unwrap())?;
+    assert_eq!(data.rows.len(), rows);
+    Ok(())
+}
+
+#[then(expr = "the first row of the query {string} has {string} set to {string}")]
+async fn first_row_has_field(
+    world: &mut IssuecraftWorld,
+    query: String,
+    field: String,
+    value: String,
+) -> Result<()> {
+    let result = world.execute(&query).await?;
+    let rows =
+        facet_json::from_str::<SelectData<UntypedEntry>>(result.data.as_ref().unwrap())?.rows;
+    let row = rows.first().unwrap().value.as_object().unwrap();
+    assert_eq!(
+        row.get(&field)
+            .and_then(|field| field.as_string())
+            .map(|field| field.as_str()),
+        Some(value.as_str())
+    );
+    Ok(())
+}
+
+#[then(expr = "the first row of the query {string} has no {string}")]
+async fn first_row_lacks_field(
+    world: &mut IssuecraftWorld,
+    query: String,
+    field: String,
+) -> Result<()> {
+    let result = world.execute(&query).await?;
+    let rows =
+        facet_json::from_str::<SelectData<UntypedEntry>>(result.data.as_ref().unwrap())?.rows;
+    let row = rows.first().unwrap().value.as_object().unwrap();
+    assert!(row.get(&field).is_none());
+    Ok(())
+}
+
+#[then(expr = "the first group of the query {string} has {string} set to {string}")]
+async fn first_group_has_field(
+    world: &mut IssuecraftWorld,
+    query: String,
+    field: String,
+    value: String,
+) -> Result<()> {
+    let result = world.execute(&query).await?;
+    let rows: SelectData<FacetValue> = facet_json::from_str(result.data.as_ref().unwrap())?;
+    let row = rows.rows.first().unwrap();
+    assert_eq!(
+        row.as_object()
+            .unwrap()
+            .get(&field)
+            .and_then(|field| field.as_string())
+            .map(|field| field.as_str()),
+        Some(value.as_str())
+    );
+    Ok(())
+}
+
+#[then(expr = "grouping issues by {string} counts {int} for {string}")]
+async fn group_count(
+    world: &mut IssuecraftWorld,
+    field: String,
+    count: u64,
+    key: String,
+) -> Result<()> {
+    let query = format!("SELECT {field}, COUNT(*) FROM issues GROUP BY {field}");
+    let result = world.execute(&query).await?;
+    let rows: SelectData<FacetValue> = facet_json::from_str(result.data.as_ref().unwrap())?;
+    let row = rows
+        .rows
+        .iter()
+        .map(|row| row.as_object().unwrap())
+        .find(|row| {
+            let group = row.get(&field).unwrap();
+            match key.as_str() {
+                "null" => group.is_null(),
+                key => group.as_string().is_some_and(|group| group.as_str() == key),
+            }
+        })
+        .unwrap();
+    assert_eq!(
+        row.get("count")
+            .and_then(|count| count.as_number()?.to_u64()),
+        Some(count)
+    );
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() {
     IssuecraftWorld::run("tests/features/query.feature").await